@@ -7,13 +7,14 @@ use mev_boost_rs::Config as BoostConfig;
 use mev_build_rs::reth_builder::Config as BuildConfig;
 #[cfg(feature = "relay")]
 use mev_relay_rs::Config as RelayConfig;
-use mev_rs::config::from_toml_file;
-use serde::Deserialize;
+use mev_rs::{config::from_toml_file, parse_network};
+use serde::{Deserialize, Deserializer};
 use std::{fmt, path::Path};
 use tracing::info;
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
+    #[serde(deserialize_with = "deserialize_network")]
     pub network: Network,
     #[cfg(feature = "boost")]
     pub boost: Option<BoostConfig>,
@@ -24,6 +25,13 @@ pub struct Config {
     pub relay: Option<RelayConfig>,
 }
 
+// Resolves the `network` of the config, so that an unknown name fails to load rather than being
+// taken as the path to the config of a custom network.
+fn deserialize_network<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Network, D::Error> {
+    let network = String::deserialize(deserializer)?;
+    parse_network(&network).map_err(serde::de::Error::custom)
+}
+
 impl Config {
     pub fn from_toml_file<P: AsRef<Path> + fmt::Display>(path: P) -> eyre::Result<Config> {
         tracing::info!("loading config from `{path}`...");
@@ -1,7 +1,21 @@
-use crate::cmd::config::Config;
+use crate::{cmd::config::Config, setup_logging, LogFormat};
 use clap::{Args, Subcommand};
 use mev_relay_rs::Service;
-use tracing::info;
+use std::str::FromStr;
+use tokio::signal::{
+    self,
+    unix::{signal as unix_signal, SignalKind},
+};
+use tracing::{error, info, warn};
+
+async fn shutdown_signal() -> eyre::Result<()> {
+    let mut terminate = unix_signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = signal::ctrl_c() => result?,
+        _ = terminate.recv() => {}
+    }
+    Ok(())
+}
 
 #[derive(Debug, Args)]
 #[clap(about = "🏗 connecting builders to proposers", subcommand_negates_reqs = true)]
@@ -31,11 +45,38 @@ impl Command {
         let config = Config::from_toml_file(config_file)?;
 
         let network = config.network;
-        info!("configured for `{network}`");
 
         if let Some(config) = config.relay {
-            let service = Service::from(network, config).spawn().await?;
-            Ok(service.await?)
+            let log_format = config.log_format.as_deref().map(LogFormat::from_str).transpose()?;
+            setup_logging(log_format.unwrap_or_default(), config.log_level.as_deref())?;
+            info!("configured for `{network}`");
+
+            let mut service = Service::from(network, config)?.spawn().await?;
+            let mut reload = unix_signal(SignalKind::hangup())?;
+            let shutdown = shutdown_signal();
+            tokio::pin!(shutdown);
+            loop {
+                tokio::select! {
+                    exit = &mut service => {
+                        error!(%exit, "relay service stopped");
+                        return Err(exit.into())
+                    }
+                    result = &mut shutdown => {
+                        result?;
+                        break
+                    }
+                    _ = reload.recv() => {
+                        info!("received SIGHUP; reloading config from `{config_file}`");
+                        match Config::from_toml_file(config_file) {
+                            Ok(Config { relay: Some(config), .. }) => service.reload(config),
+                            Ok(_) => warn!("missing relay config from file provided; not reloading"),
+                            Err(err) => warn!(%err, "could not reload config; not reloading"),
+                        }
+                    }
+                }
+            }
+            service.shutdown().await?;
+            Ok(())
         } else {
             Err(eyre::eyre!("missing relay config from file provided"))
         }
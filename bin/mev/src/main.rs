@@ -1,9 +1,9 @@
 mod cmd;
 
 use clap::{Parser, Subcommand};
-use std::future::Future;
+use std::{future::Future, str::FromStr};
 use tokio::signal;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about = "utilities for block space", long_about = None)]
@@ -23,17 +23,54 @@ enum Commands {
     Config(cmd::config::Command),
 }
 
-fn setup_logging() {
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+// Format of the log output.
+#[derive(Debug, Default, Clone, Copy)]
+enum LogFormat {
+    // human-readable lines
+    #[default]
+    Pretty,
+    // one JSON object per line, with the fields of each event at the top level
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(Self::Pretty),
+            "json" => Ok(Self::Json),
+            other => Err(eyre::eyre!("unknown log format `{other}`; expected `pretty` or `json`")),
+        }
+    }
+}
+
+// Installs the global subscriber writing logs in `format`, filtered by `level` if provided
+// or otherwise by `RUST_LOG`, defaulting to `info`.
+fn setup_logging(format: LogFormat, level: Option<&str>) -> eyre::Result<()> {
+    let filter = match level {
+        Some(level) => EnvFilter::try_new(level)?,
+        None => EnvFilter::new(std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into())),
+    };
+    let (pretty, json) = match format {
+        LogFormat::Pretty => (Some(fmt::layer()), None),
+        LogFormat::Json => {
+            // NOTE: fields of the enclosing span (e.g. the `slot` being processed) are
+            // included under `span`
+            let layer = fmt::layer()
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(false);
+            (None, Some(layer))
+        }
+    };
+    tracing_subscriber::registry().with(filter).with(pretty).with(json).init();
+    Ok(())
 }
 
 async fn run_task_until_signal(task: impl Future<Output = eyre::Result<()>>) -> eyre::Result<()> {
-    setup_logging();
+    setup_logging(LogFormat::default(), None)?;
 
     tokio::select! {
         task = task => task,
@@ -54,7 +91,9 @@ async fn main() -> eyre::Result<()> {
         #[cfg(feature = "build")]
         Commands::Build(cmd) => tokio::task::block_in_place(|| cmd.run()),
         #[cfg(feature = "relay")]
-        Commands::Relay(cmd) => run_task_until_signal(cmd.execute()).await,
+        // NOTE: the relay sets up logging from its config and handles signals itself
+        // to drain in-flight work on shutdown
+        Commands::Relay(cmd) => cmd.execute().await,
         Commands::Config(cmd) => run_task_until_signal(cmd.execute()).await,
     }
 }
@@ -82,7 +82,7 @@ impl Service {
             }
         });
 
-        let server = BlindedBlockProviderServer::new(host, port, relay_mux).spawn();
+        let server = BlindedBlockProviderServer::new(host.into(), port, relay_mux).spawn();
 
         Ok(ServiceHandle { relay_mux: relay_task, server })
     }
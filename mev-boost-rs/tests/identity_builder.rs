@@ -15,7 +15,7 @@ use mev_rs::{
         builder_bid, AuctionContents, AuctionRequest, BuilderBid, ExecutionPayload,
         ExecutionPayloadHeader, SignedBlindedBeaconBlock, SignedBuilderBid,
     },
-    Error,
+    Error, RelayError,
 };
 use std::{
     collections::HashMap,
@@ -60,7 +60,13 @@ impl BlindedBlockProvider for IdentityBuilder {
     ) -> Result<SignedBuilderBid, Error> {
         let capella_fork_slot = self.context.capella_fork_epoch * self.context.slots_per_epoch;
         let state = self.registrations.lock().unwrap();
-        let preferences = state.get(public_key).unwrap();
+        let preferences = state.get(public_key).ok_or_else(|| {
+            Error::NoBidPrepared(AuctionRequest {
+                slot: *slot,
+                parent_hash: parent_hash.clone(),
+                public_key: public_key.clone(),
+            })
+        })?;
         let value = U256::from(1337);
         let (payload, mut builder_bid) = if *slot < capella_fork_slot {
             let mut payload = bellatrix::ExecutionPayload {
@@ -110,7 +116,8 @@ impl BlindedBlockProvider for IdentityBuilder {
     ) -> Result<AuctionContents, Error> {
         let slot = signed_block.message().slot();
         let state = self.bids.lock().unwrap();
-        let execution_payload = state.get(&slot).cloned().unwrap();
+        let execution_payload =
+            state.get(&slot).cloned().ok_or(RelayError::InvalidSignedBlindedBeaconBlock)?;
         let auction_contents = match signed_block.message().version() {
             Fork::Bellatrix => AuctionContents::Bellatrix(execution_payload),
             Fork::Capella => AuctionContents::Capella(execution_payload),
@@ -19,6 +19,7 @@ use mev_rs::{
     blinded_block_provider::{Client as RelayClient, Server as RelayServer},
     signing::sign_builder_message,
     types::{AuctionRequest, SignedBlindedBeaconBlock},
+    Error,
 };
 use rand::seq::SliceRandom;
 use std::{
@@ -87,7 +88,7 @@ async fn test_end_to_end() {
     let host = Ipv4Addr::LOCALHOST;
     let port = 28545;
     let builder = IdentityBuilder::new(context.clone());
-    let relay = RelayServer::new(host, port, builder);
+    let relay = RelayServer::new(host.into(), port, builder);
     std::mem::drop(relay.spawn());
 
     // start mux server
@@ -130,6 +131,98 @@ async fn test_end_to_end() {
     }
 }
 
+#[tokio::test]
+async fn test_provider_client_round_trip() {
+    let mut rng = rand::thread_rng();
+    let proposer = create_proposers(&mut rng, 1).pop().unwrap();
+
+    let context = Context::try_from(Network::Sepolia).unwrap();
+    let port = 28645;
+    let builder = IdentityBuilder::new(context.clone());
+    let relay = RelayServer::new(Ipv4Addr::LOCALHOST.into(), port, builder);
+    std::mem::drop(relay.spawn());
+
+    let client =
+        RelayClient::new(ApiClient::new(Url::parse(&format!("http://127.0.0.1:{port}")).unwrap()));
+    client.check_status().await.unwrap();
+
+    let slot = 30 + context.capella_fork_epoch * context.slots_per_epoch;
+    let request = AuctionRequest {
+        slot,
+        parent_hash: Hash32::try_from([1u8; 32].as_ref()).unwrap(),
+        public_key: proposer.validator.public_key.clone(),
+    };
+    // no bid is prepared for an unregistered proposer
+    let err = client.fetch_best_bid(&request).await.unwrap_err();
+    assert!(matches!(err, Error::NoBidPrepared(..)));
+
+    let mut registration = ValidatorRegistration {
+        fee_recipient: proposer.fee_recipient.clone(),
+        gas_limit: 30_000_000,
+        timestamp: get_time(),
+        public_key: proposer.validator.public_key.clone(),
+    };
+    let signature =
+        sign_builder_message(&mut registration, &proposer.signing_key, &context).unwrap();
+    let registrations = [SignedValidatorRegistration { message: registration, signature }];
+    client.register_validators(&registrations).await.unwrap();
+
+    let signed_bid = client.fetch_best_bid(&request).await.unwrap();
+    assert_eq!(signed_bid.message.header().parent_hash(), &request.parent_hash);
+
+    // the server's error is surfaced for a block without a matching bid
+    let beacon_block = capella::BlindedBeaconBlock { slot: slot + 1, ..Default::default() };
+    let signed_block = SignedBlindedBeaconBlock::Capella(capella::SignedBlindedBeaconBlock {
+        message: beacon_block,
+        ..Default::default()
+    });
+    let err = client.open_bid(&signed_block).await.unwrap_err();
+    match err {
+        Error::Api(beacon_api_client::Error::Api(beacon_api_client::ApiError::ErrorMessage {
+            code,
+            message,
+        })) => {
+            assert_eq!(code, 400);
+            assert!(message.contains("signed blinded beacon block is invalid"));
+        }
+        err => panic!("unexpected error: {err}"),
+    }
+}
+
+#[tokio::test]
+async fn test_fetch_best_bid_without_bid_is_empty_no_content() {
+    let mut rng = rand::thread_rng();
+    let proposer = create_proposers(&mut rng, 1).pop().unwrap();
+
+    let context = Context::try_from(Network::Sepolia).unwrap();
+    let port = 28646;
+    let builder = IdentityBuilder::new(context.clone());
+    let relay = RelayServer::new(Ipv4Addr::LOCALHOST.into(), port, builder);
+    std::mem::drop(relay.spawn());
+
+    let api = ApiClient::new(Url::parse(&format!("http://127.0.0.1:{port}")).unwrap());
+    let client = RelayClient::new(api.clone());
+    client.check_status().await.unwrap();
+
+    // no submissions were made for this slot, so there is no bid
+    let slot = 30 + context.capella_fork_epoch * context.slots_per_epoch;
+    let request = AuctionRequest {
+        slot,
+        parent_hash: Hash32::try_from([1u8; 32].as_ref()).unwrap(),
+        public_key: proposer.validator.public_key.clone(),
+    };
+    let err = client.fetch_best_bid(&request).await.unwrap_err();
+    assert!(matches!(err, Error::NoBidPrepared(..)));
+
+    let target = format!(
+        "/eth/v1/builder/header/{}/{:?}/{:?}",
+        request.slot, request.parent_hash, request.public_key
+    );
+    let response = api.http_get(&target).await.unwrap();
+    assert_eq!(response.status(), 204);
+    assert!(response.bytes().await.unwrap().is_empty());
+}
+
 async fn propose_block(
     beacon_node: &RelayClient,
     proposer: &Proposer,
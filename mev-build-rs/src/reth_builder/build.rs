@@ -41,7 +41,7 @@ fn make_submission(
     };
     let execution_payload = to_execution_payload(payload);
     let signature = sign_builder_message(&mut message, signing_key, context)?;
-    Ok(SignedBidSubmission { message, execution_payload, signature })
+    Ok(SignedBidSubmission { message, execution_payload, blobs_bundle: None, signature })
 }
 
 // TODO: drop unnecessary things...
@@ -0,0 +1,37 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use mev_relay_rs::bench::{builders, BidStore};
+
+// Measures inserting a bid from each builder into a new auction, with the auctions of the epoch
+// allocated ahead for `expected_builders` or on the first bid of the auction.
+fn bench_store_bids(c: &mut Criterion) {
+    let mut group = c.benchmark_group("store_bids");
+    for count in [8, 32, 128] {
+        let builders = builders(count);
+        for expected_builders in [None, Some(count as usize)] {
+            let name = if expected_builders.is_some() {
+                "with_expected_builders"
+            } else {
+                "without_expected_builders"
+            };
+            group.bench_with_input(BenchmarkId::new(name, count), &builders, |b, builders| {
+                b.iter_batched(
+                    || {
+                        let bid_store = BidStore::new(expected_builders);
+                        bid_store.prewarm_auctions(0);
+                        let bids = bid_store.bids(1, builders);
+                        (bid_store, bids)
+                    },
+                    |(bid_store, bids)| {
+                        bid_store.store_bids(bids);
+                        bid_store
+                    },
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_store_bids);
+criterion_main!(benches);
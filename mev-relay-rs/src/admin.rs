@@ -0,0 +1,273 @@
+use crate::relay::Relay;
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap, Method, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use hmac::{Hmac, Mac};
+use mev_rs::Error;
+use parking_lot::Mutex;
+use serde::Serialize;
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+
+/// Header carrying the time an admin request was made, in seconds since the unix epoch
+pub const TIMESTAMP_HEADER: &str = "x-admin-timestamp";
+/// Header carrying the hex-encoded signature of an admin request from [`sign_admin_request`]
+pub const SIGNATURE_HEADER: &str = "x-admin-signature";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Serialize)]
+struct ReloadedRegistrations {
+    count: usize,
+}
+
+// Compares `a` and `b` in time independent of where they first differ, so that the admin token
+// can not be guessed from response times.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+// Returns `true` if the `headers` carry the admin `token` as a bearer token.
+fn is_authorized(headers: &HeaderMap, token: &str) -> bool {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|provided| constant_time_eq(provided.trim().as_bytes(), token.as_bytes()))
+        .unwrap_or(false)
+}
+
+// Rejects requests without the admin token with `401 Unauthorized`.
+async fn require_token<B>(
+    State(token): State<Arc<str>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if is_authorized(request.headers(), &token) {
+        next.run(request).await
+    } else {
+        warn!(path = %request.uri().path(), "rejecting admin request without a valid token");
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+// Returns the timestamp of a request with the `headers`, or why it is not within `window` of
+// `now`, in seconds since the unix epoch, if it is not.
+fn check_timestamp(headers: &HeaderMap, now: u64, window: Duration) -> Result<u64, &'static str> {
+    let timestamp = headers
+        .get(TIMESTAMP_HEADER)
+        .ok_or("missing timestamp")?
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .ok_or("invalid timestamp")?;
+    let window = window.as_secs();
+    if timestamp.saturating_add(window) < now {
+        return Err("stale timestamp")
+    }
+    if timestamp > now.saturating_add(window) {
+        return Err("timestamp in the future")
+    }
+    Ok(timestamp)
+}
+
+// Returns the MAC of a request with `method` to `path` at `timestamp` under the admin `token`.
+fn request_mac(token: &str, method: &Method, path: &str, timestamp: u64) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(token.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{method}\n{path}\n{timestamp}").as_bytes());
+    mac
+}
+
+/// Returns the signature of an admin request with `method` to `path` at `timestamp`, in seconds
+/// since the unix epoch, to send hex-encoded in the [`SIGNATURE_HEADER`]
+///
+/// The `path` excludes any query, which is not signed.
+pub fn sign_admin_request(token: &str, method: &Method, path: &str, timestamp: u64) -> String {
+    let signature = request_mac(token, method, path, timestamp).finalize().into_bytes();
+    signature.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+// Returns the signature of a request with the `headers` to `path` at `timestamp`, or why it is
+// not a valid signature under the admin `token`.
+fn check_signature(
+    headers: &HeaderMap,
+    token: &str,
+    method: &Method,
+    path: &str,
+    timestamp: u64,
+) -> Result<Vec<u8>, &'static str> {
+    let encoded = headers
+        .get(SIGNATURE_HEADER)
+        .ok_or("missing signature")?
+        .to_str()
+        .map_err(|_| "invalid signature")?
+        .trim();
+    if encoded.len() % 2 != 0 {
+        return Err("invalid signature")
+    }
+    let signature = (0..encoded.len())
+        .step_by(2)
+        .map(|i| encoded.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or("invalid signature")?;
+    request_mac(token, method, path, timestamp)
+        .verify_slice(&signature)
+        .map_err(|_| "invalid signature")?;
+    Ok(signature)
+}
+
+// Checks admin requests are signed with the admin token at a time within `window` of the
+// current time, accepting each signature only once.
+struct RequestSigning {
+    token: Arc<str>,
+    window: Duration,
+    // signatures of the requests accepted within the window, with their timestamps
+    seen: Mutex<HashMap<Vec<u8>, u64>>,
+}
+
+impl RequestSigning {
+    fn check(&self, headers: &HeaderMap, method: &Method, path: &str) -> Result<(), &'static str> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let timestamp = check_timestamp(headers, now, self.window)?;
+        let signature = check_signature(headers, &self.token, method, path, timestamp)?;
+        let mut seen = self.seen.lock();
+        let window = self.window.as_secs();
+        seen.retain(|_, timestamp| timestamp.saturating_add(window) >= now);
+        if seen.insert(signature, timestamp).is_some() {
+            return Err("replayed signature")
+        }
+        Ok(())
+    }
+}
+
+// Rejects requests not signed with the admin token at a time within the window around the
+// current time, or signed the same as an earlier request, with `401 Unauthorized`.
+async fn require_signature<B>(
+    State(signing): State<Arc<RequestSigning>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match signing.check(request.headers(), request.method(), request.uri().path()) {
+        Ok(()) => next.run(request).await,
+        Err(reason) => {
+            warn!(path = %request.uri().path(), reason, "rejecting admin request");
+            StatusCode::UNAUTHORIZED.into_response()
+        }
+    }
+}
+
+async fn handle_reload_registrations(
+    State(relay): State<Relay>,
+) -> Result<Json<ReloadedRegistrations>, Error> {
+    info!("reloading validator registrations on request");
+    let count = relay.reload_registrations().await?;
+    Ok(Json(ReloadedRegistrations { count }))
+}
+
+/// Returns routes for operators of the `relay`, each requiring `token` as a bearer token by default
+///
+/// If a `request_window` is provided, requests are instead authenticated by their signature
+/// with `token` from [`sign_admin_request`] in the [`SIGNATURE_HEADER`], over their time in the
+/// [`TIMESTAMP_HEADER`], so that the token is never sent. Requests are rejected if their time is
+/// further than the window from the current time in either direction, or if their signature
+/// was already accepted.
+pub fn router(relay: Relay, token: String, request_window: Option<Duration>) -> Router {
+    let token: Arc<str> = token.into();
+    let router =
+        Router::new().route("/admin/reload_registrations", post(handle_reload_registrations));
+    let router = match request_window {
+        Some(window) => {
+            let signing = RequestSigning { token, window, seen: Default::default() };
+            router.route_layer(middleware::from_fn_with_state(Arc::new(signing), require_signature))
+        }
+        None => router.route_layer(middleware::from_fn_with_state(token, require_token)),
+    };
+    router.with_state(relay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_is_authorized() {
+        let token = "s3cret";
+        let mut headers = HeaderMap::new();
+        assert!(!is_authorized(&headers, token));
+
+        for (authorization, expected) in [
+            ("Bearer s3cret", true),
+            ("Bearer s3cre", false),
+            ("Bearer s3cret!", false),
+            ("Basic s3cret", false),
+            ("s3cret", false),
+        ] {
+            headers.insert(AUTHORIZATION, HeaderValue::from_static(authorization));
+            assert_eq!(is_authorized(&headers, token), expected, "{authorization}");
+        }
+    }
+
+    #[test]
+    fn test_check_timestamp() {
+        let now = 1_700_000_000;
+        let window = Duration::from_secs(30);
+        let mut headers = HeaderMap::new();
+        assert_eq!(check_timestamp(&headers, now, window), Err("missing timestamp"));
+
+        for (timestamp, expected) in [
+            ("1700000000", Ok(1_700_000_000)),
+            ("1699999970", Ok(1_699_999_970)),
+            (" 1700000030 ", Ok(1_700_000_030)),
+            ("1699999969", Err("stale timestamp")),
+            ("0", Err("stale timestamp")),
+            ("1700000031", Err("timestamp in the future")),
+            ("18446744073709551615", Err("timestamp in the future")),
+            ("-1", Err("invalid timestamp")),
+            ("1700000000.5", Err("invalid timestamp")),
+        ] {
+            headers.insert(TIMESTAMP_HEADER, HeaderValue::from_static(timestamp));
+            assert_eq!(check_timestamp(&headers, now, window), expected, "{timestamp}");
+        }
+    }
+
+    #[test]
+    fn test_check_signature() {
+        let token = "s3cret";
+        let path = "/admin/reload_registrations";
+        let timestamp = 1_700_000_000;
+        let signature = sign_admin_request(token, &Method::POST, path, timestamp);
+        let mut headers = HeaderMap::new();
+        let check = |headers: &HeaderMap, method: &Method, path: &str, timestamp: u64| {
+            check_signature(headers, token, method, path, timestamp).map(|_| ())
+        };
+        assert_eq!(check(&headers, &Method::POST, path, timestamp), Err("missing signature"));
+
+        headers.insert(SIGNATURE_HEADER, HeaderValue::from_str(&signature).unwrap());
+        assert_eq!(check(&headers, &Method::POST, path, timestamp), Ok(()));
+        // for another request, time or token
+        let other_path = "/admin/other";
+        assert_eq!(check(&headers, &Method::GET, path, timestamp), Err("invalid signature"));
+        assert_eq!(check(&headers, &Method::POST, other_path, timestamp), Err("invalid signature"));
+        assert_eq!(check(&headers, &Method::POST, path, timestamp + 1), Err("invalid signature"));
+        let result = check_signature(&headers, "guess", &Method::POST, path, timestamp);
+        assert_eq!(result, Err("invalid signature"));
+
+        for signature in ["", "0", "zz", &signature[..signature.len() - 2]] {
+            headers.insert(SIGNATURE_HEADER, HeaderValue::from_str(signature).unwrap());
+            let result = check(&headers, &Method::POST, path, timestamp);
+            assert_eq!(result, Err("invalid signature"), "{signature}");
+        }
+    }
+}
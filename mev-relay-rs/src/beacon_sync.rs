@@ -0,0 +1,109 @@
+use mev_rs::{BeaconNodePool, Error};
+use std::sync::OnceLock;
+use tracing::{error, info, warn};
+
+/// Version and sync status the beacon node reported as the relay started
+#[derive(Debug, Default)]
+pub struct BeaconNodeInfo {
+    version: OnceLock<String>,
+    // `None` if the beacon node did not report whether it is syncing
+    is_syncing: OnceLock<bool>,
+}
+
+impl BeaconNodeInfo {
+    pub fn version(&self) -> Option<&str> {
+        self.version.get().map(String::as_str)
+    }
+
+    pub fn is_syncing(&self) -> Option<bool> {
+        self.is_syncing.get().copied()
+    }
+}
+
+/// Logs the version and sync status of the `beacon_node`, recording them in `info`, and fails
+/// if the beacon node is still syncing and `require_synced` is set
+///
+/// A beacon node that does not report its sync status, e.g. as it does not implement the
+/// endpoint, is of unknown sync status and never fails the check.
+pub async fn check_beacon_node(
+    beacon_node: &BeaconNodePool,
+    require_synced: bool,
+    info: &BeaconNodeInfo,
+) -> Result<(), Error> {
+    match beacon_node.call(|client| async move { client.get_node_version().await }).await {
+        Ok(version) => {
+            info!(%version, "connected to beacon node");
+            let _ = info.version.set(version);
+        }
+        Err(err) => warn!(%err, "could not fetch version of beacon node"),
+    }
+
+    let status =
+        match beacon_node.call(|client| async move { client.get_sync_status().await }).await {
+            Ok(status) => status,
+            Err(err) => {
+                warn!(%err, "could not fetch sync status of beacon node; treating it as unknown");
+                return Ok(())
+            }
+        };
+    let _ = info.is_syncing.set(status.is_syncing);
+    let head_slot = status.head_slot;
+    let sync_distance = status.sync_distance;
+    if !status.is_syncing {
+        info!(head_slot, "beacon node is synced");
+        return Ok(())
+    }
+    if require_synced {
+        error!(head_slot, sync_distance, "beacon node is still syncing; refusing to start");
+        return Err(Error::BeaconNodeSyncing(head_slot))
+    }
+    warn!(head_slot, sync_distance, "beacon node is still syncing; its duties may be unreliable");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_beacon_node::spawn_mock_beacon_node;
+    use axum::{routing::get, Json, Router};
+    use beacon_api_client::mainnet::Client;
+
+    // Serves a mock beacon node reporting whether it `is_syncing`, or without the syncing
+    // endpoint if `None`.
+    fn spawn_beacon_node(is_syncing: Option<bool>) -> BeaconNodePool {
+        let version = serde_json::json!({ "data": { "version": "Lighthouse/v4.5.0" } });
+        let mut router =
+            Router::new().route("/eth/v1/node/version", get(move || async move { Json(version) }));
+        if let Some(is_syncing) = is_syncing {
+            let status = serde_json::json!({
+                "data": {
+                    "head_slot": "100",
+                    "sync_distance": if is_syncing { "5" } else { "0" },
+                    "is_syncing": is_syncing,
+                    "is_optimistic": false,
+                    "el_offline": false,
+                },
+            });
+            router = router.route("/eth/v1/node/syncing", get(move || async move { Json(status) }));
+        }
+        let url = spawn_mock_beacon_node(router);
+        Client::new(url).into()
+    }
+
+    #[tokio::test]
+    async fn test_check_beacon_node_sync_status() {
+        for (is_syncing, require_synced, fails) in [
+            (Some(false), true, false),
+            (Some(true), false, false),
+            (Some(true), true, true),
+            (None, true, false),
+        ] {
+            let beacon_node = spawn_beacon_node(is_syncing);
+            let info = BeaconNodeInfo::default();
+            let result = check_beacon_node(&beacon_node, require_synced, &info).await;
+            assert_eq!(result.is_err(), fails, "{is_syncing:?} {require_synced}");
+            assert_eq!(info.version(), Some("Lighthouse/v4.5.0"));
+            assert_eq!(info.is_syncing(), is_syncing);
+        }
+    }
+}
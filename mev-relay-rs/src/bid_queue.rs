@@ -0,0 +1,72 @@
+use mev_rs::Error;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// Queues validated submissions for the bid store without waiting, so that a lagging bid
+/// store fails submissions quickly rather than holding them up
+#[derive(Debug)]
+pub struct BidQueue<T> {
+    sender: mpsc::Sender<T>,
+}
+
+/// Yields the submissions queued for the bid store in batches
+#[derive(Debug)]
+pub struct BidBatches<T> {
+    receiver: mpsc::Receiver<T>,
+    max_batch_size: usize,
+}
+
+/// Returns a queue holding at most `capacity` submissions, drained in batches of at most
+/// `max_batch_size`
+pub fn bounded<T>(capacity: usize, max_batch_size: usize) -> (BidQueue<T>, BidBatches<T>) {
+    let (sender, receiver) = mpsc::channel(capacity.max(1));
+    (BidQueue { sender }, BidBatches { receiver, max_batch_size: max_batch_size.max(1) })
+}
+
+impl<T> BidQueue<T> {
+    /// Queues the `bid`, failing if the queue is full or no longer drained
+    pub fn try_enqueue(&self, bid: T) -> Result<(), Error> {
+        self.sender.try_send(bid).map_err(|err| match err {
+            TrySendError::Full(_) => Error::BidQueueFull,
+            TrySendError::Closed(_) => Error::NotReady,
+        })
+    }
+}
+
+impl<T> BidBatches<T> {
+    /// Waits for a queued submission, returning it with any others already queued, or `None`
+    /// once the queue is dropped
+    pub async fn next_batch(&mut self) -> Option<Vec<T>> {
+        let mut batch = vec![self.receiver.recv().await?];
+        while batch.len() < self.max_batch_size {
+            match self.receiver.try_recv() {
+                Ok(bid) => batch.push(bid),
+                Err(_) => break,
+            }
+        }
+        Some(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    #[tokio::test]
+    async fn test_full_queue_rejects_until_drained() {
+        let (queue, mut batches) = bounded(3, 2);
+        for bid in 0..3 {
+            queue.try_enqueue(bid).unwrap();
+        }
+        let err = queue.try_enqueue(3).unwrap_err();
+        assert!(matches!(err, Error::BidQueueFull));
+        assert_eq!(err.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        assert_eq!(batches.next_batch().await, Some(vec![0, 1]));
+        queue.try_enqueue(3).unwrap();
+        assert_eq!(batches.next_batch().await, Some(vec![2, 3]));
+
+        drop(queue);
+        assert_eq!(batches.next_batch().await, None);
+    }
+}
@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use ethereum_consensus::ssz::prelude::Merkleized;
+use mev_rs::{
+    types::{ExecutionPayload, SignedBidSubmission},
+    Error, RelayError,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+use tracing::debug;
+use url::Url;
+
+const VALIDATE_BUILDER_SUBMISSION_METHOD: &str = "flashbots_validateBuilderSubmissionV2";
+
+/// Checks the validity of a builder's submission before the relay accepts it
+#[async_trait]
+pub trait BlockValidator: Send + Sync {
+    /// Returns an error if the block in `signed_submission` is not valid for a proposer
+    /// who has registered a preference for `registered_gas_limit`
+    async fn validate(
+        &self,
+        signed_submission: &SignedBidSubmission,
+        registered_gas_limit: u64,
+    ) -> Result<(), Error>;
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse {
+    error: Option<JsonRpcError>,
+}
+
+/// Validates submissions by simulating them against an execution client
+#[derive(Debug, Clone)]
+pub struct ExecutionBlockValidator {
+    client: reqwest::Client,
+    endpoint: Url,
+}
+
+impl ExecutionBlockValidator {
+    /// Validates against the execution client at `endpoint`, giving up on each
+    /// request after `timeout`
+    pub fn new(endpoint: Url, timeout: Duration) -> Result<Self, Error> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|err| RelayError::BlockValidationUnavailable(err.to_string()))?;
+        Ok(Self { client, endpoint })
+    }
+}
+
+#[async_trait]
+impl BlockValidator for ExecutionBlockValidator {
+    async fn validate(
+        &self,
+        signed_submission: &SignedBidSubmission,
+        registered_gas_limit: u64,
+    ) -> Result<(), Error> {
+        let mut params = json!({
+            "message": signed_submission.message,
+            "execution_payload": signed_submission.execution_payload,
+            "signature": signed_submission.signature,
+            "registered_gas_limit": registered_gas_limit.to_string(),
+        });
+        let withdrawals = match &signed_submission.execution_payload {
+            ExecutionPayload::Bellatrix(..) => None,
+            ExecutionPayload::Capella(payload) => Some(payload.withdrawals.clone()),
+            ExecutionPayload::Deneb(payload) => Some(payload.withdrawals.clone()),
+        };
+        if let Some(mut withdrawals) = withdrawals {
+            let withdrawals_root = withdrawals.hash_tree_root().map_err(|err| {
+                RelayError::BlockValidationUnavailable(format!(
+                    "could not compute withdrawals root ({err})"
+                ))
+            })?;
+            params["withdrawals_root"] = json!(withdrawals_root);
+        }
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": VALIDATE_BUILDER_SUBMISSION_METHOD,
+            "params": [params],
+        });
+
+        let unavailable =
+            |err: reqwest::Error| RelayError::BlockValidationUnavailable(err.to_string());
+        let response = self
+            .client
+            .post(self.endpoint.clone())
+            .json(&request)
+            .send()
+            .await
+            .map_err(unavailable)?
+            .json::<JsonRpcResponse>()
+            .await
+            .map_err(unavailable)?;
+        if let Some(err) = response.error {
+            debug!(message = %err.message, "execution client rejected builder submission");
+            return Err(RelayError::InvalidBlock(err.message).into())
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use ethereum_consensus::primitives::BlsPublicKey;
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+// Maximum number of builders tracked at once, to bound the memory used by the reputations.
+const MAX_TRACKED_BUILDERS: usize = 4096;
+
+/// Limits on failed submissions from each builder before they are banned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReputationPolicy {
+    // length of the sliding window failures are counted in, and of any resulting ban
+    pub window: Duration,
+    // number of failures within the window after which a builder is banned
+    pub failure_threshold: u32,
+}
+
+/// Kind of failed submission counted against the reputation of a builder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuilderFailure {
+    InvalidSignature,
+    FailedSimulation,
+    InvalidFeeRecipient,
+}
+
+impl BuilderFailure {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidSignature => "invalid_signature",
+            Self::FailedSimulation => "failed_simulation",
+            Self::InvalidFeeRecipient => "invalid_fee_recipient",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Record {
+    // times of the failures within the window, oldest first
+    failures: VecDeque<Instant>,
+    banned_until: Option<Instant>,
+}
+
+impl Record {
+    fn is_active(&self, now: Instant, window: Duration) -> bool {
+        let banned = self.banned_until.map(|until| now < until).unwrap_or(false);
+        let recent_failure = self
+            .failures
+            .back()
+            .map(|&failure| now.saturating_duration_since(failure) < window)
+            .unwrap_or(false);
+        banned || recent_failure
+    }
+}
+
+/// Tracks the recent failures of each builder, banning those with too many failures within
+/// a sliding window until the window has passed
+#[derive(Debug)]
+pub struct BuilderReputation {
+    policy: ReputationPolicy,
+    max_builders: usize,
+    records: Mutex<HashMap<BlsPublicKey, Record>>,
+}
+
+impl BuilderReputation {
+    pub fn new(policy: ReputationPolicy) -> Self {
+        Self::with_max_builders(policy, MAX_TRACKED_BUILDERS)
+    }
+
+    fn with_max_builders(policy: ReputationPolicy, max_builders: usize) -> Self {
+        let policy =
+            ReputationPolicy { failure_threshold: policy.failure_threshold.max(1), ..policy };
+        Self { policy, max_builders, records: Default::default() }
+    }
+
+    /// Returns the time left on the ban of `builder`, if it is banned
+    pub fn banned_for(&self, builder: &BlsPublicKey) -> Option<Duration> {
+        self.banned_for_at(builder, Instant::now())
+    }
+
+    fn banned_for_at(&self, builder: &BlsPublicKey, now: Instant) -> Option<Duration> {
+        let mut records = self.records.lock();
+        let record = records.get_mut(builder)?;
+        let banned_until = record.banned_until?;
+        if now < banned_until {
+            Some(banned_until - now)
+        } else {
+            record.banned_until = None;
+            None
+        }
+    }
+
+    /// Records a failed submission from `builder`, returning the length of its ban if the
+    /// failure got it banned
+    pub fn record_failure(&self, builder: &BlsPublicKey) -> Option<Duration> {
+        self.record_failure_at(builder, Instant::now())
+    }
+
+    fn record_failure_at(&self, builder: &BlsPublicKey, now: Instant) -> Option<Duration> {
+        let ReputationPolicy { window, failure_threshold } = self.policy;
+        let mut records = self.records.lock();
+        if !records.contains_key(builder) && records.len() >= self.max_builders {
+            self.evict(&mut records, now);
+        }
+        let record = records.entry(builder.clone()).or_default();
+        if record.banned_until.map(|until| now < until).unwrap_or(false) {
+            return None
+        }
+        while let Some(&oldest) = record.failures.front() {
+            if now.saturating_duration_since(oldest) < window {
+                break
+            }
+            record.failures.pop_front();
+        }
+        record.failures.push_back(now);
+        if record.failures.len() < failure_threshold as usize {
+            return None
+        }
+        record.failures.clear();
+        record.banned_until = Some(now + window);
+        Some(window)
+    }
+
+    // Drops the records of builders neither banned nor with a failure in the window, as they
+    // behave the same as untracked builders, falling back to the least recently failed builder
+    // if every tracked builder is still active.
+    fn evict(&self, records: &mut HashMap<BlsPublicKey, Record>, now: Instant) {
+        records.retain(|_, record| record.is_active(now, self.policy.window));
+        if records.len() >= self.max_builders {
+            let least_recent = records
+                .iter()
+                .min_by_key(|(_, record)| record.failures.back().copied().or(record.banned_until))
+                .map(|(builder, _)| builder.clone());
+            if let Some(builder) = least_recent {
+                records.remove(&builder);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_consensus::crypto::SecretKey;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    fn builder(byte: u8) -> BlsPublicKey {
+        SecretKey::try_from([byte; 32].as_ref()).unwrap().public_key()
+    }
+
+    fn reputation(max_builders: usize) -> BuilderReputation {
+        let policy = ReputationPolicy { window: WINDOW, failure_threshold: 3 };
+        BuilderReputation::with_max_builders(policy, max_builders)
+    }
+
+    #[test]
+    fn test_bans_builder_over_threshold_within_window() {
+        let reputation = reputation(8);
+        let builder = builder(1);
+        let start = Instant::now();
+
+        assert!(reputation.record_failure_at(&builder, start).is_none());
+        assert!(reputation.record_failure_at(&builder, start + Duration::from_secs(1)).is_none());
+        // failures older than the window no longer count
+        let later = start + WINDOW + Duration::from_secs(1);
+        assert!(reputation.record_failure_at(&builder, later).is_none());
+        assert!(reputation.banned_for_at(&builder, later).is_none());
+
+        assert!(reputation.record_failure_at(&builder, later + Duration::from_secs(1)).is_none());
+        let now = later + Duration::from_secs(2);
+        assert_eq!(reputation.record_failure_at(&builder, now), Some(WINDOW));
+        assert_eq!(reputation.banned_for_at(&builder, now), Some(WINDOW));
+        let now = now + Duration::from_secs(10);
+        assert_eq!(reputation.banned_for_at(&builder, now), Some(WINDOW - Duration::from_secs(10)));
+
+        // reinstated once the window has passed, without any failures carried over
+        let now = now + WINDOW;
+        assert!(reputation.banned_for_at(&builder, now).is_none());
+        assert!(reputation.record_failure_at(&builder, now).is_none());
+    }
+
+    #[test]
+    fn test_bounds_tracked_builders() {
+        let reputation = reputation(2);
+        let now = Instant::now();
+        for byte in 1..=3 {
+            reputation.record_failure_at(&builder(byte), now + Duration::from_secs(byte.into()));
+        }
+        let records = reputation.records.lock();
+        assert_eq!(records.len(), 2);
+        assert!(!records.contains_key(&builder(1)));
+    }
+}
@@ -0,0 +1,154 @@
+use ethereum_consensus::primitives::{BlsPublicKey, Slot};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{cmp::Reverse, collections::HashMap};
+
+// Maximum number of builders tracked at once, to bound the memory used by the statistics.
+const MAX_TRACKED_BUILDERS: usize = 4096;
+
+/// Submissions received from a builder while it was last active
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BuilderActivity {
+    #[serde(rename = "builder_pubkey")]
+    pub builder_public_key: BlsPublicKey,
+    #[serde(with = "ethereum_consensus::serde::as_str")]
+    pub submissions: u64,
+    #[serde(with = "ethereum_consensus::serde::as_str")]
+    pub accepted: u64,
+    #[serde(with = "ethereum_consensus::serde::as_str")]
+    pub rejected: u64,
+    // slot of the clock of the relay at which the latest submission was received
+    #[serde(with = "ethereum_consensus::serde::as_str")]
+    pub last_seen_slot: Slot,
+}
+
+/// Counts the submissions of each builder, forgetting builders once they have not submitted for
+/// `retention_slots`
+#[derive(Debug)]
+pub struct BuilderStats {
+    retention_slots: Slot,
+    max_builders: usize,
+    builders: Mutex<HashMap<BlsPublicKey, BuilderActivity>>,
+}
+
+impl BuilderStats {
+    pub fn new(retention_slots: Slot) -> Self {
+        Self::with_max_builders(retention_slots, MAX_TRACKED_BUILDERS)
+    }
+
+    fn with_max_builders(retention_slots: Slot, max_builders: usize) -> Self {
+        Self { retention_slots, max_builders, builders: Default::default() }
+    }
+
+    /// Records a submission from `builder` received at `slot`, as `accepted` or rejected
+    pub fn record(&self, builder: &BlsPublicKey, slot: Slot, accepted: bool) {
+        let mut builders = self.builders.lock();
+        if !builders.contains_key(builder) && builders.len() >= self.max_builders {
+            Self::evict_least_recent(&mut builders);
+        }
+        let activity = builders.entry(builder.clone()).or_insert_with(|| BuilderActivity {
+            builder_public_key: builder.clone(),
+            ..Default::default()
+        });
+        activity.submissions += 1;
+        if accepted {
+            activity.accepted += 1;
+        } else {
+            activity.rejected += 1;
+        }
+        activity.last_seen_slot = activity.last_seen_slot.max(slot);
+    }
+
+    /// Forgets the builders without a submission in the `retention_slots` up to `slot`
+    pub fn on_slot(&self, slot: Slot) {
+        let retention_slots = self.retention_slots;
+        self.builders
+            .lock()
+            .retain(|_, activity| activity.last_seen_slot + retention_slots >= slot);
+    }
+
+    /// Returns the activity of each tracked builder, most recently seen first
+    pub fn activity(&self) -> Vec<BuilderActivity> {
+        let mut activity = self.builders.lock().values().cloned().collect::<Vec<_>>();
+        activity.sort_by_key(|activity| Reverse(activity.last_seen_slot));
+        activity
+    }
+
+    fn evict_least_recent(builders: &mut HashMap<BlsPublicKey, BuilderActivity>) {
+        let least_recent = builders
+            .values()
+            .min_by_key(|activity| activity.last_seen_slot)
+            .map(|activity| activity.builder_public_key.clone());
+        if let Some(builder) = least_recent {
+            builders.remove(&builder);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_consensus::crypto::SecretKey;
+
+    fn builder(byte: u8) -> BlsPublicKey {
+        SecretKey::try_from([byte; 32].as_ref()).unwrap().public_key()
+    }
+
+    #[test]
+    fn test_counts_submissions_per_builder() {
+        let stats = BuilderStats::new(64);
+        stats.record(&builder(1), 10, true);
+        stats.record(&builder(1), 12, false);
+        // a submission handled late does not move the last seen slot back
+        stats.record(&builder(1), 11, true);
+        stats.record(&builder(2), 11, false);
+
+        let activity = stats.activity();
+        assert_eq!(
+            activity,
+            vec![
+                BuilderActivity {
+                    builder_public_key: builder(1),
+                    submissions: 3,
+                    accepted: 2,
+                    rejected: 1,
+                    last_seen_slot: 12,
+                },
+                BuilderActivity {
+                    builder_public_key: builder(2),
+                    submissions: 1,
+                    accepted: 0,
+                    rejected: 1,
+                    last_seen_slot: 11,
+                },
+            ]
+        );
+        let encoded = serde_json::to_value(&activity[0]).unwrap();
+        assert_eq!(encoded["builder_pubkey"], serde_json::to_value(builder(1)).unwrap());
+        assert_eq!(encoded["submissions"], "3");
+    }
+
+    #[test]
+    fn test_forgets_inactive_builders() {
+        let stats = BuilderStats::new(64);
+        stats.record(&builder(1), 10, true);
+        stats.record(&builder(2), 20, true);
+        stats.on_slot(74);
+        assert_eq!(stats.activity().len(), 2);
+        stats.on_slot(75);
+        let activity = stats.activity();
+        assert_eq!(activity.len(), 1);
+        assert_eq!(activity[0].builder_public_key, builder(2));
+    }
+
+    #[test]
+    fn test_bounds_tracked_builders() {
+        let stats = BuilderStats::with_max_builders(64, 2);
+        for byte in 1..=3 {
+            stats.record(&builder(byte), byte.into(), true);
+        }
+        let builders = stats.builders.lock();
+        assert_eq!(builders.len(), 2);
+        assert!(!builders.contains_key(&builder(1)));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::slot_loop::SlotClock;
+use ethereum_consensus::primitives::Slot;
+use mev_rs::BeaconNodePool;
+use parking_lot::Mutex;
+use std::{sync::Arc, time::Duration};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+// Number of slots between each comparison of the local clock against the beacon node.
+const CHECK_INTERVAL_SLOTS: u32 = 8;
+// Number of slots the head may be behind the local clock beyond the tolerance, as a head behind
+// the local slot can also be due to missed slots.
+// NOTE: one epoch on mainnet
+const MISSED_SLOTS_ALLOWANCE: u64 = 32;
+
+/// Tracks the difference between the slot of the local clock and the slot of the
+/// beacon node's head
+#[derive(Debug, Default)]
+pub struct ClockDrift {
+    // head slot minus the local slot, as of the last check
+    last_observed_slots: Mutex<Option<i64>>,
+}
+
+impl ClockDrift {
+    pub fn last_observed_slots(&self) -> Option<i64> {
+        *self.last_observed_slots.lock()
+    }
+
+    fn record(&self, drift_slots: i64) {
+        *self.last_observed_slots.lock() = Some(drift_slots);
+    }
+}
+
+// Returns the head slot minus the `local_slot`.
+//
+// NOTE: a head behind the local slot can also be due to missed slots, so only a head
+// *ahead* of the local clock conclusively shows the local clock to be late.
+fn compute_drift(local_slot: Slot, head_slot: Slot) -> i64 {
+    head_slot as i64 - local_slot as i64
+}
+
+// Returns `true` if `drift_slots` shows the local clock to be off by more than
+// `tolerance_slots`, allowing for missed slots if the head is behind.
+fn exceeds_tolerance(drift_slots: i64, tolerance_slots: u64) -> bool {
+    if drift_slots >= 0 {
+        drift_slots.unsigned_abs() > tolerance_slots
+    } else {
+        drift_slots.unsigned_abs() > tolerance_slots.saturating_add(MISSED_SLOTS_ALLOWANCE)
+    }
+}
+
+async fn check_drift(
+    beacon_node: &BeaconNodePool,
+    clock: &dyn SlotClock,
+    tolerance_slots: u64,
+    clock_drift: &ClockDrift,
+) {
+    let summary = match beacon_node
+        .call(|client| async move { client.get_beacon_header_at_head().await })
+        .await
+    {
+        Ok(summary) => summary,
+        Err(err) => {
+            warn!(%err, "could not fetch head from beacon node to check clock drift");
+            return
+        }
+    };
+    let Some(local_slot) = clock.current_slot() else { return };
+    let head_slot = summary.header.message.slot;
+    let drift_slots = compute_drift(local_slot, head_slot);
+    clock_drift.record(drift_slots);
+    if exceeds_tolerance(drift_slots, tolerance_slots) {
+        warn!(
+            local_slot,
+            head_slot,
+            drift_slots,
+            tolerance_slots,
+            "local clock has drifted from the beacon node; check time synchronization of the host"
+        );
+    } else {
+        debug!(local_slot, head_slot, drift_slots, "checked clock drift");
+    }
+}
+
+/// Spawns a task periodically comparing the slot of the local `clock` against the head
+/// of the `beacon_node`, warning if they differ by more than `tolerance_slots`, or by another
+/// epoch of possibly missed slots if the head is behind
+pub fn spawn_monitor(
+    beacon_node: BeaconNodePool,
+    clock: Arc<dyn SlotClock>,
+    slot_duration: Duration,
+    tolerance_slots: u64,
+    clock_drift: Arc<ClockDrift>,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(slot_duration * CHECK_INTERVAL_SLOTS);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => return,
+            }
+            check_drift(&beacon_node, &*clock, tolerance_slots, &clock_drift).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_beacon_node::spawn_mock_beacon_node, slot_loop::MockClock};
+    use axum::{routing::get, Json, Router};
+    use beacon_api_client::mainnet::Client;
+    use ethereum_consensus::phase0::SignedBeaconBlockHeader;
+    use url::Url;
+
+    // Serves a mock beacon node with its head at `head_slot`.
+    fn spawn_beacon_node(head_slot: Slot) -> BeaconNodePool {
+        let mut header = SignedBeaconBlockHeader::default();
+        header.message.slot = head_slot;
+        let summary = serde_json::json!({
+            "data": { "root": header.message.parent_root, "canonical": true, "header": header },
+        });
+        let router = Router::new()
+            .route("/eth/v1/beacon/headers/head", get(move || async move { Json(summary) }));
+        let url = spawn_mock_beacon_node(router);
+        Client::new(url).into()
+    }
+
+    #[test]
+    fn test_compute_drift() {
+        assert_eq!(compute_drift(100, 100), 0);
+        assert_eq!(compute_drift(100, 103), 3);
+        assert_eq!(compute_drift(100, 97), -3);
+    }
+
+    #[test]
+    fn test_exceeds_tolerance() {
+        for (drift_slots, expected) in [
+            (0, false),
+            (2, false),
+            (3, true),
+            (-2, false),
+            // a few missed slots in a row leave the head behind
+            (-3, false),
+            (-34, false),
+            (-35, true),
+        ] {
+            assert_eq!(exceeds_tolerance(drift_slots, 2), expected, "{drift_slots}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_drift() {
+        for (head_slot, expected) in [(100, 0), (105, 5), (90, -10)] {
+            let beacon_node = spawn_beacon_node(head_slot);
+            let clock = MockClock::new(32, 100, vec![]).resumed_at(100);
+            let clock_drift = ClockDrift::default();
+            check_drift(&beacon_node, &clock, 2, &clock_drift).await;
+            assert_eq!(clock_drift.last_observed_slots(), Some(expected), "{head_slot}");
+        }
+
+        // the drift is left unknown if the head can not be fetched
+        let beacon_node: BeaconNodePool =
+            Client::new(Url::parse("http://127.0.0.1:1").unwrap()).into();
+        let clock = MockClock::new(32, 100, vec![]).resumed_at(100);
+        let clock_drift = ClockDrift::default();
+        check_drift(&beacon_node, &clock, 2, &clock_drift).await;
+        assert_eq!(clock_drift.last_observed_slots(), None);
+    }
+}
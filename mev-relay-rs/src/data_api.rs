@@ -0,0 +1,203 @@
+use crate::{
+    builder_stats::BuilderActivity,
+    pagination::{BidTraceFilter, Cursor, Page},
+    relay::{DeliveredPayload, Relay},
+};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    routing::get,
+    Json, Router,
+};
+use beacon_api_client::ApiError;
+use ethereum_consensus::{
+    builder::ValidatorRegistration,
+    primitives::{BlsPublicKey, Hash32, Slot},
+    serde::try_bytes_from_hex_str,
+};
+use mev_rs::types::BidTrace;
+use serde::{Deserialize, Serialize};
+use tower_http::compression::CompressionLayer;
+use tracing::{trace, warn};
+
+// Number of bid traces served per page unless the request sets a `limit`.
+const DEFAULT_PAGE_LIMIT: usize = 100;
+// Upper bound on the `limit` of a request, so that each page stays bounded.
+const MAX_PAGE_LIMIT: usize = 500;
+// Header carrying the cursor of the next page, if there may be more bid traces, so that the
+// body remains the list of bid traces the relay data API specifies.
+const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+type ApiResult<T> = Result<T, (StatusCode, Json<ApiError>)>;
+
+#[derive(Debug, Default, Deserialize)]
+struct BidTracesQuery {
+    slot: Option<Slot>,
+    limit: Option<usize>,
+    cursor: Option<String>,
+    builder_pubkey: Option<String>,
+    block_hash: Option<String>,
+}
+
+impl BidTracesQuery {
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT)
+    }
+
+    fn cursor(&self) -> Result<Option<Cursor>, String> {
+        self.cursor.as_deref().map(str::parse).transpose()
+    }
+
+    fn filter(&self) -> Result<BidTraceFilter, String> {
+        Ok(BidTraceFilter {
+            slot: self.slot,
+            builder_public_key: self.builder_pubkey.as_deref().map(parse_public_key).transpose()?,
+            block_hash: self.block_hash.as_deref().map(parse_hash).transpose()?,
+        })
+    }
+}
+
+// Serves the entries of the `page`, with the cursor of the next page in a header.
+fn page_response<T: Serialize>(page: Page<T>) -> (HeaderMap, Json<Vec<T>>) {
+    let mut headers = HeaderMap::new();
+    if let Some(cursor) = page.next_cursor {
+        let cursor = HeaderValue::from_str(&cursor.to_string()).expect("cursor is a valid header");
+        headers.insert(HeaderName::from_static(NEXT_CURSOR_HEADER), cursor);
+    }
+    (headers, Json(page.entries))
+}
+
+async fn handle_get_delivered_payloads(
+    State(relay): State<Relay>,
+    Query(query): Query<BidTracesQuery>,
+) -> ApiResult<(HeaderMap, Json<Vec<DeliveredPayload>>)> {
+    trace!(?query, "serving delivered payloads");
+    let bad_request = |message| api_error(StatusCode::BAD_REQUEST, message);
+    let filter = query.filter().map_err(bad_request)?;
+    let cursor = query.cursor().map_err(bad_request)?;
+    let page = relay.get_delivered_payloads_page(&filter, cursor, query.limit());
+    Ok(page_response(page))
+}
+
+async fn handle_get_received_bids(
+    State(relay): State<Relay>,
+    Query(query): Query<BidTracesQuery>,
+) -> ApiResult<(HeaderMap, Json<Vec<BidTrace>>)> {
+    trace!(?query, "serving received builder blocks");
+    let bad_request = |message| api_error(StatusCode::BAD_REQUEST, message);
+    let filter = query.filter().map_err(bad_request)?;
+    let cursor = query.cursor().map_err(bad_request)?;
+    let page = relay.get_received_bids_page(&filter, cursor, query.limit());
+    Ok(page_response(page))
+}
+
+async fn handle_get_builder_stats(State(relay): State<Relay>) -> Json<Vec<BuilderActivity>> {
+    trace!("serving builder stats");
+    Json(relay.get_builder_activity())
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidatorRegistrationQuery {
+    pubkey: String,
+}
+
+fn api_error(code: StatusCode, message: String) -> (StatusCode, Json<ApiError>) {
+    (code, Json(ApiError::ErrorMessage { code, message }))
+}
+
+// Parses `encoded` as a hex-encoded BLS public key, rejecting any that is not a valid point.
+fn parse_public_key(encoded: &str) -> Result<BlsPublicKey, String> {
+    let bytes = try_bytes_from_hex_str(encoded)
+        .map_err(|err| format!("could not decode public key `{encoded}`: {err}"))?;
+    BlsPublicKey::try_from(bytes.as_slice())
+        .map_err(|err| format!("invalid public key `{encoded}`: {err}"))
+}
+
+fn parse_hash(encoded: &str) -> Result<Hash32, String> {
+    let bytes = try_bytes_from_hex_str(encoded)
+        .map_err(|err| format!("could not decode hash `{encoded}`: {err}"))?;
+    Hash32::try_from(bytes.as_slice()).map_err(|_| format!("invalid hash `{encoded}`"))
+}
+
+async fn handle_get_validator_registration(
+    State(relay): State<Relay>,
+    Query(query): Query<ValidatorRegistrationQuery>,
+) -> ApiResult<Json<ValidatorRegistration>> {
+    trace!(?query, "serving validator registration");
+    let public_key = parse_public_key(&query.pubkey)
+        .map_err(|message| api_error(StatusCode::BAD_REQUEST, message))?;
+    match relay.get_stored_registration(&public_key).await {
+        Ok(Some(registration)) => Ok(Json(registration.message)),
+        Ok(None) => {
+            let message = format!("no registration found for validator {public_key:?}");
+            Err(api_error(StatusCode::NOT_FOUND, message))
+        }
+        Err(err) => {
+            warn!(%err, %public_key, "could not load validator registration");
+            Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+/// Returns the read-only routes of the relay data API, compressing their responses as the
+/// request accepts if `compress_responses` is set
+pub fn router(relay: Relay, compress_responses: bool) -> Router {
+    let router = Router::new()
+        .route(
+            "/relay/v1/data/bidtraces/proposer_payload_delivered",
+            get(handle_get_delivered_payloads),
+        )
+        .route("/relay/v1/data/bidtraces/builder_blocks_received", get(handle_get_received_bids))
+        .route("/relay/v1/data/builder_stats", get(handle_get_builder_stats))
+        .route("/relay/v1/data/validator_registration", get(handle_get_validator_registration))
+        .with_state(relay);
+    // NOTE: layered before merging with other routes so that only the data API is compressed,
+    // leaving the latency of the builder and proposer APIs untouched
+    if compress_responses {
+        router.layer(CompressionLayer::new())
+    } else {
+        router
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_consensus::crypto::SecretKey;
+
+    #[test]
+    fn test_parse_public_key() {
+        let public_key = SecretKey::try_from([1u8; 32].as_ref()).unwrap().public_key();
+        let encoded = serde_json::to_value(&public_key).unwrap();
+        assert_eq!(parse_public_key(encoded.as_str().unwrap()).unwrap(), public_key);
+
+        assert!(parse_public_key("0x1234").is_err());
+        assert!(parse_public_key("not hex").is_err());
+        // the right length but not a point on the curve
+        assert!(parse_public_key(&format!("0x{}", "ff".repeat(48))).is_err());
+    }
+
+    #[test]
+    fn test_bid_traces_query() {
+        let query = BidTracesQuery::default();
+        assert_eq!(query.limit(), DEFAULT_PAGE_LIMIT);
+        assert!(query.cursor().unwrap().is_none());
+        let query = BidTracesQuery { limit: Some(10 * MAX_PAGE_LIMIT), ..Default::default() };
+        assert_eq!(query.limit(), MAX_PAGE_LIMIT);
+
+        let block_hash = format!("0x{}", "ab".repeat(32));
+        let query = BidTracesQuery {
+            cursor: Some("10-2".to_string()),
+            block_hash: Some(block_hash),
+            ..Default::default()
+        };
+        assert_eq!(query.cursor().unwrap(), Some(Cursor { slot: 10, sequence: 2 }));
+        let expected = Hash32::try_from([0xab; 32].as_ref()).unwrap();
+        assert_eq!(query.filter().unwrap().block_hash, Some(expected));
+
+        let query = BidTracesQuery { block_hash: Some("0xab".to_string()), ..Default::default() };
+        assert!(query.filter().is_err());
+        let query = BidTracesQuery { cursor: Some("latest".to_string()), ..Default::default() };
+        assert!(query.cursor().is_err());
+    }
+}
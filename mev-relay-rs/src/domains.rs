@@ -0,0 +1,120 @@
+use ethereum_consensus::{
+    domains::DomainType,
+    phase0::mainnet::compute_domain,
+    primitives::{Domain, Root, Slot},
+    state_transition::Context,
+    Error, Fork,
+};
+use mev_rs::signing::compute_builder_domain;
+use parking_lot::RwLock;
+use tracing::debug;
+
+fn compute_proposer_domain(
+    fork: Fork,
+    genesis_validators_root: &Root,
+    context: &Context,
+) -> Result<Domain, Error> {
+    let fork_version = context.fork_version_for(fork);
+    compute_domain(
+        DomainType::BeaconProposer,
+        Some(fork_version),
+        Some(*genesis_validators_root),
+        context,
+    )
+}
+
+/// Caches the signing domains used by the relay, as they only change at fork boundaries
+#[derive(Debug)]
+pub struct Domains {
+    // NOTE: per the builder specs, the builder domain is computed from the genesis fork
+    // version so is the same in every fork
+    builder: Domain,
+    // beacon proposer domain of the fork of the slot last passed to `on_slot`
+    proposer: RwLock<Option<(Fork, Domain)>>,
+}
+
+impl Domains {
+    pub fn new(context: &Context) -> Result<Self, Error> {
+        let builder = compute_builder_domain(context)?;
+        Ok(Self { builder, proposer: Default::default() })
+    }
+
+    /// Returns the domain of messages signed by builders, validators and the relay itself
+    pub fn builder(&self) -> Domain {
+        self.builder
+    }
+
+    /// Refreshes the beacon proposer domain if `slot` is in a different fork than the one
+    /// cached
+    pub fn on_slot(
+        &self,
+        slot: Slot,
+        genesis_validators_root: &Root,
+        context: &Context,
+    ) -> Result<(), Error> {
+        let fork = context.fork_for(slot);
+        if matches!(*self.proposer.read(), Some((cached_fork, _)) if cached_fork == fork) {
+            return Ok(())
+        }
+        let domain = compute_proposer_domain(fork, genesis_validators_root, context)?;
+        debug!(slot, %fork, "refreshed beacon proposer domain");
+        *self.proposer.write() = Some((fork, domain));
+        Ok(())
+    }
+
+    /// Returns the beacon proposer domain for `slot`, only computing it if `slot` is not in
+    /// the cached fork, e.g. for a block proposed right at a fork boundary
+    pub fn proposer(
+        &self,
+        slot: Slot,
+        genesis_validators_root: &Root,
+        context: &Context,
+    ) -> Result<Domain, Error> {
+        let fork = context.fork_for(slot);
+        let cached = *self.proposer.read();
+        match cached {
+            Some((cached_fork, domain)) if cached_fork == fork => Ok(domain),
+            _ => compute_proposer_domain(fork, genesis_validators_root, context),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proposer_domain_changes_at_fork_boundary() {
+        let mut context = Context::for_mainnet();
+        context.altair_fork_epoch = 0;
+        context.bellatrix_fork_epoch = 0;
+        context.capella_fork_epoch = 2;
+        context.deneb_fork_epoch = u64::MAX;
+        let genesis_validators_root = Root::default();
+        let capella_slot = context.capella_fork_epoch * context.slots_per_epoch;
+
+        let domains = Domains::new(&context).unwrap();
+        domains.on_slot(capella_slot - 1, &genesis_validators_root, &context).unwrap();
+        let bellatrix_domain =
+            domains.proposer(capella_slot - 1, &genesis_validators_root, &context).unwrap();
+        let expected =
+            compute_proposer_domain(Fork::Bellatrix, &genesis_validators_root, &context).unwrap();
+        assert_eq!(bellatrix_domain, expected);
+
+        domains.on_slot(capella_slot, &genesis_validators_root, &context).unwrap();
+        assert!(matches!(*domains.proposer.read(), Some((Fork::Capella, _))));
+        let capella_domain =
+            domains.proposer(capella_slot, &genesis_validators_root, &context).unwrap();
+        let expected =
+            compute_proposer_domain(Fork::Capella, &genesis_validators_root, &context).unwrap();
+        assert_eq!(capella_domain, expected);
+        assert_ne!(bellatrix_domain, capella_domain);
+
+        // a slot outside the cached fork is still given the domain of its own fork
+        let domain =
+            domains.proposer(capella_slot - 1, &genesis_validators_root, &context).unwrap();
+        assert_eq!(domain, bellatrix_domain);
+
+        assert_eq!(domains.builder(), compute_builder_domain(&context).unwrap());
+    }
+}
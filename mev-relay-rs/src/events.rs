@@ -0,0 +1,55 @@
+use crate::relay::Relay;
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use ethereum_consensus::primitives::{BlsPublicKey, Hash32, Slot, U256};
+use futures::{stream, Stream};
+use serde::Serialize;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::{debug, warn};
+
+/// Summary of a bid that became the best bid for its auction
+///
+/// NOTE: this is served publicly so must never contain the execution payload.
+#[derive(Debug, Clone, Serialize)]
+pub struct WinningBid {
+    pub slot: Slot,
+    pub builder_public_key: BlsPublicKey,
+    pub value: U256,
+    pub block_hash: Hash32,
+}
+
+// Streams events from `winning_bids` until the subscriber falls behind the broadcast
+// channel, so that a slow consumer is dropped rather than holding up submissions.
+fn to_event_stream(
+    winning_bids: Receiver<WinningBid>,
+) -> impl Stream<Item = Result<Event, axum::Error>> {
+    stream::unfold(winning_bids, |mut winning_bids| async move {
+        match winning_bids.recv().await {
+            Ok(winning_bid) => {
+                let event = Event::default().event("winning_bid").json_data(&winning_bid);
+                Some((event, winning_bids))
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(skipped, "dropping subscriber to winning bids that fell behind");
+                None
+            }
+            Err(RecvError::Closed) => None,
+        }
+    })
+}
+
+async fn handle_winning_bids(
+    State(relay): State<Relay>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    debug!("new subscriber to winning bids");
+    Sse::new(to_event_stream(relay.subscribe_winning_bids())).keep_alive(KeepAlive::default())
+}
+
+/// Returns a route streaming each new best bid accepted by the `relay` as Server-Sent Events
+pub fn router(relay: Relay) -> Router {
+    Router::new().route("/events/winning_bids", get(handle_winning_bids)).with_state(relay)
+}
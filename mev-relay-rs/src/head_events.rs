@@ -0,0 +1,185 @@
+use crate::relay::Relay;
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use beacon_api_client::{BlockId, HeadEvent, HeadTopic};
+use ethereum_consensus::primitives::{Hash32, Slot};
+use futures::StreamExt;
+use mev_rs::BeaconNodePool;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+// Number of slots to remember each head for when looking for heads orphaned by a reorg.
+const HEAD_HISTORY_SLOTS: Slot = 32;
+// Upper bound on the delay between attempts to resubscribe to head events.
+const RESUBSCRIBE_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Tracks the recent heads of the execution chain to find those orphaned by a reorg
+#[derive(Debug, Default)]
+pub struct HeadTracker {
+    // slot and parent hash of each recent head, by block hash
+    heads: HashMap<Hash32, (Slot, Hash32)>,
+    // recent heads that are not ancestors of the latest head
+    orphaned: HashSet<Hash32>,
+}
+
+impl HeadTracker {
+    /// Records the block with `block_hash` and `parent_hash` at `slot` as the new head,
+    /// returning the block hashes of any recent heads newly orphaned by it
+    pub fn on_head(
+        &mut self,
+        slot: Slot,
+        block_hash: Hash32,
+        parent_hash: Hash32,
+    ) -> HashSet<Hash32> {
+        self.heads.insert(block_hash.clone(), (slot, parent_hash));
+        self.heads.retain(|_, (head_slot, _)| *head_slot + HEAD_HISTORY_SLOTS >= slot);
+
+        let mut canonical = HashSet::new();
+        let mut cursor = Some(&block_hash);
+        while let Some(hash) = cursor {
+            canonical.insert(hash.clone());
+            cursor = self.heads.get(hash).map(|(_, parent_hash)| parent_hash);
+        }
+        let orphaned = self
+            .heads
+            .keys()
+            .filter(|hash| !canonical.contains(*hash))
+            .cloned()
+            .collect::<HashSet<_>>();
+        let newly_orphaned = orphaned.difference(&self.orphaned).cloned().collect();
+        self.orphaned = orphaned;
+        newly_orphaned
+    }
+}
+
+async fn on_head_event(
+    relay: &Relay,
+    beacon_node: &BeaconNodePool,
+    tracker: &mut HeadTracker,
+    event: HeadEvent,
+) {
+    let slot = event.slot;
+    let root = event.block;
+    let signed_block = match beacon_node
+        .call(|client| async move { client.get_beacon_block(BlockId::Root(root)).await })
+        .await
+    {
+        Ok(signed_block) => signed_block,
+        Err(err) => {
+            warn!(%err, slot, %root, "could not fetch head block from beacon node");
+            return
+        }
+    };
+    let block = signed_block.message();
+    let body = block.body();
+    // NOTE: blocks from before the merge do not build on an execution block
+    let Some(execution_payload) = body.execution_payload() else { return };
+    let block_hash = execution_payload.block_hash().clone();
+    let parent_hash = execution_payload.parent_hash().clone();
+
+    let orphaned = tracker.on_head(slot, block_hash.clone(), parent_hash);
+    if orphaned.is_empty() {
+        debug!(slot, %block_hash, "processed head event");
+        return
+    }
+    let dropped = relay.drop_orphaned_bids(&orphaned);
+    info!(slot, %block_hash, ?orphaned, dropped, "reorg detected; dropped bids on orphaned heads");
+}
+
+// Follows the head events of the active beacon node until the stream fails or ends.
+async fn follow_head_events<B: Backoff>(
+    relay: &Relay,
+    beacon_node: &BeaconNodePool,
+    tracker: &mut HeadTracker,
+    backoff: &mut B,
+) {
+    let client = beacon_node.active();
+    let mut stream = match client.get_events::<HeadTopic>().await {
+        Ok(stream) => stream,
+        Err(err) => {
+            error!(%err, "could not open head event stream");
+            beacon_node.fail_over_if_unreachable(&err);
+            return
+        }
+    };
+    info!("subscribed to head events");
+    backoff.reset();
+
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(event) => on_head_event(relay, beacon_node, tracker, event.data).await,
+            Err(err) => {
+                warn!(%err, "error reading head event stream");
+                beacon_node.fail_over_if_unreachable(&err);
+                return
+            }
+        }
+    }
+}
+
+/// Spawns a task following the head events of the `beacon_node` to drop any bids of the
+/// `relay` built on a head orphaned by a reorg
+///
+/// If the event stream fails or ends, the task resubscribes after backing off.
+pub fn spawn_subscription(
+    relay: Relay,
+    beacon_node: BeaconNodePool,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tracker = HeadTracker::default();
+        let mut backoff = ExponentialBackoff {
+            max_interval: RESUBSCRIBE_MAX_BACKOFF,
+            max_elapsed_time: None,
+            ..Default::default()
+        };
+        loop {
+            tokio::select! {
+                _ = follow_head_events(&relay, &beacon_node, &mut tracker, &mut backoff) => {}
+                _ = shutdown.cancelled() => return,
+            }
+            let delay = backoff.next_backoff().unwrap_or(RESUBSCRIBE_MAX_BACKOFF);
+            warn!(?delay, "head event stream ended; resubscribing");
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.cancelled() => return,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(byte: u8) -> Hash32 {
+        Hash32::try_from([byte; 32].as_ref()).unwrap()
+    }
+
+    #[test]
+    fn test_head_tracker_finds_orphaned_heads() {
+        let mut tracker = HeadTracker::default();
+        assert!(tracker.on_head(10, hash(10), hash(9)).is_empty());
+        assert!(tracker.on_head(11, hash(11), hash(10)).is_empty());
+        assert!(tracker.on_head(11, hash(11), hash(10)).is_empty());
+
+        // a block at slot 12 building on slot 10 orphans the head at slot 11
+        let orphaned = tracker.on_head(12, hash(12), hash(10));
+        assert_eq!(orphaned, HashSet::from([hash(11)]));
+        assert!(tracker.on_head(12, hash(12), hash(10)).is_empty());
+
+        // a reorg back onto the head at slot 11 orphans the head at slot 12 instead
+        let orphaned = tracker.on_head(13, hash(13), hash(11));
+        assert_eq!(orphaned, HashSet::from([hash(12)]));
+        assert!(tracker.on_head(14, hash(14), hash(13)).is_empty());
+
+        // old heads are forgotten
+        let slot = 14 + HEAD_HISTORY_SLOTS + 1;
+        assert!(tracker.on_head(slot, hash(15), hash(14)).is_empty());
+        assert_eq!(tracker.heads.len(), 1);
+    }
+}
@@ -0,0 +1,127 @@
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use mev_rs::{
+    tls::{self, ServerConfig},
+    BeaconNodePool,
+};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+// Interval between checks that the beacon node is still reachable.
+const BEACON_NODE_PING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Tracks the readiness of the relay to serve requests
+#[derive(Debug)]
+pub struct Health {
+    initialized: AtomicBool,
+    last_beacon_node_contact: Mutex<Option<Instant>>,
+    // duration without contact after which the beacon node is considered unreachable
+    unreachable_threshold: Duration,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub initialized: bool,
+    pub beacon_node_reachable: bool,
+    pub pending: Vec<&'static str>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl Health {
+    pub fn new(unreachable_threshold: Duration) -> Self {
+        Self {
+            initialized: AtomicBool::new(false),
+            last_beacon_node_contact: Mutex::new(None),
+            unreachable_threshold,
+        }
+    }
+
+    pub fn set_initialized(&self) {
+        self.initialized.store(true, Ordering::Release);
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.load(Ordering::Acquire)
+    }
+
+    pub fn record_beacon_node_contact(&self) {
+        *self.last_beacon_node_contact.lock() = Some(Instant::now());
+    }
+
+    pub fn is_beacon_node_reachable(&self) -> bool {
+        let last_contact = *self.last_beacon_node_contact.lock();
+        last_contact.map(|contact| contact.elapsed() <= self.unreachable_threshold).unwrap_or(false)
+    }
+
+    pub fn report(&self) -> HealthReport {
+        let initialized = self.is_initialized();
+        let beacon_node_reachable = self.is_beacon_node_reachable();
+        let mut pending = vec![];
+        if !initialized {
+            pending.push("relay initialization");
+        }
+        if !beacon_node_reachable {
+            pending.push("beacon node connectivity");
+        }
+        HealthReport { initialized, beacon_node_reachable, pending }
+    }
+}
+
+async fn handle_health(State(health): State<Arc<Health>>) -> (StatusCode, Json<HealthReport>) {
+    let report = health.report();
+    let code = if report.is_healthy() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (code, Json(report))
+}
+
+async fn monitor_beacon_node(beacon_node: BeaconNodePool, health: Arc<Health>) {
+    let mut interval = tokio::time::interval(BEACON_NODE_PING_INTERVAL);
+    loop {
+        interval.tick().await;
+        match beacon_node.call(|client| async move { client.get_genesis_details().await }).await {
+            Ok(_) => health.record_beacon_node_contact(),
+            Err(err) => warn!(%err, "could not reach beacon node"),
+        }
+    }
+}
+
+/// Spawns a server exposing the `health` of the relay at `/health`, along with a task
+/// to periodically check the `beacon_node` is reachable
+pub fn spawn_server(
+    host: IpAddr,
+    port: u16,
+    tls: Option<Arc<ServerConfig>>,
+    health: Arc<Health>,
+    beacon_node: BeaconNodePool,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    let router = Router::new().route("/health", get(handle_health)).with_state(health.clone());
+    let addr = SocketAddr::from((host, port));
+    tokio::spawn(async move {
+        info!(tls = tls.is_some(), "serving health checks at {addr}...");
+        let server = tls::serve(addr, router, tls, async move { shutdown.cancelled().await });
+        // NOTE: the monitor runs until the server has shut down
+        tokio::select! {
+            result = server => {
+                if let Err(err) = result {
+                    error!(%err, "error while serving health checks")
+                }
+            }
+            _ = monitor_beacon_node(beacon_node, health) => {}
+        }
+    })
+}
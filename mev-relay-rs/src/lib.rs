@@ -1,4 +1,34 @@
+mod admin;
+mod beacon_sync;
+mod bid_queue;
+mod block_validator;
+mod builder_reputation;
+mod builder_stats;
+mod clock_drift;
+mod data_api;
+mod domains;
+mod events;
+mod head_events;
+mod health;
+mod metrics;
+#[cfg(test)]
+mod mock_beacon_node;
+mod pagination;
+mod registration_pool;
+mod registration_store;
 mod relay;
 mod service;
+mod slot_loop;
+mod status;
+mod upstream;
 
-pub use service::{Config, Service};
+pub use admin::sign_admin_request;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub use relay::bench;
+pub use relay::ParentHashCheck;
+pub use service::{
+    BeaconNodeUrl, Config, ForkOverrides, RelayIdentity, Service, ServiceBuilder, ServiceExit,
+    TlsFiles,
+};
+pub use slot_loop::SlotClock;
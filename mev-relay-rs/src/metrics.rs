@@ -0,0 +1,162 @@
+use axum::{extract::State, routing::get, Router};
+use ethereum_consensus::primitives::BlsPublicKey;
+use metrics::{
+    counter, decrement_gauge, describe_counter, describe_gauge, describe_histogram, histogram,
+    increment_gauge,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use mev_rs::{
+    connection_limit::ConnectionEvent,
+    request_log::RequestSummary,
+    tls::{self, ServerConfig},
+};
+use std::{
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+// NOTE: keep labels with unbounded cardinality (e.g. slots) off of these metrics
+pub const VALIDATOR_REGISTRATIONS_RECEIVED: &str = "relay_validator_registrations_received_total";
+pub const BIDS_SERVED: &str = "relay_bids_served_total";
+pub const BLINDED_BLOCKS_RECEIVED: &str = "relay_blinded_blocks_received_total";
+pub const BUILDER_SUBMISSIONS_REJECTED: &str = "relay_builder_submissions_rejected_total";
+pub const DUPLICATE_BLOCK_HASHES_REJECTED: &str = "relay_duplicate_block_hashes_rejected_total";
+pub const HEADER_CACHE_HITS: &str = "relay_header_cache_hits_total";
+pub const HEADER_CACHE_MISSES: &str = "relay_header_cache_misses_total";
+pub const CURRENT_EPOCH: &str = "relay_current_epoch";
+pub const VALIDATOR_REGISTRATIONS: &str = "relay_validator_registrations";
+pub const BUILDER_FAILURES: &str = "relay_builder_failures_total";
+pub const BUILDERS_BANNED: &str = "relay_builders_banned_total";
+pub const MISSED_BIDS: &str = "relay_missed_bids_total";
+pub const RECENT_MISSED_BIDS: &str = "relay_recent_missed_bids";
+pub const PAYLOAD_REQUESTS_IN_FLIGHT: &str = "relay_payload_requests_in_flight";
+pub const REQUEST_DURATION: &str = "relay_request_duration_seconds";
+pub const OPEN_CONNECTIONS: &str = "relay_open_connections";
+pub const CONNECTIONS_REFUSED: &str = "relay_connections_refused_total";
+
+// Buckets for the latency of requests in seconds, finest from tens to hundreds of milliseconds
+// as proposers must be served well within a slot.
+const REQUEST_DURATION_BUCKETS: &[f64] =
+    &[0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.15, 0.25, 0.5, 1.0, 2.0, 4.0];
+
+// Number of leading hex characters of a builder's public key used to label its metrics.
+const BUILDER_LABEL_HEX_CHARS: usize = 8;
+
+/// Returns the label for metrics of the builder with `public_key`
+///
+/// Only a prefix of the public key is used to keep the cardinality of the labels small.
+pub fn builder_label(public_key: &BlsPublicKey) -> String {
+    let mut label = public_key.to_string();
+    label.truncate("0x".len() + BUILDER_LABEL_HEX_CHARS);
+    label
+}
+
+fn describe_metrics() {
+    describe_counter!(
+        VALIDATOR_REGISTRATIONS_RECEIVED,
+        "number of validator registrations received from proposers"
+    );
+    describe_counter!(BIDS_SERVED, "number of signed builder bids served to proposers");
+    describe_counter!(BLINDED_BLOCKS_RECEIVED, "number of signed blinded blocks received to open");
+    describe_counter!(
+        BUILDER_SUBMISSIONS_REJECTED,
+        "number of bid submissions rejected from builders not on the allowlist"
+    );
+    describe_counter!(
+        DUPLICATE_BLOCK_HASHES_REJECTED,
+        "number of bid submissions rejected for a block hash already submitted by another builder"
+    );
+    describe_counter!(HEADER_CACHE_HITS, "number of bids served from the cache of serialized bids");
+    describe_counter!(
+        HEADER_CACHE_MISSES,
+        "number of bids serialized to serve as they were missing from the cache"
+    );
+    describe_gauge!(CURRENT_EPOCH, "epoch last processed by the relay");
+    describe_gauge!(VALIDATOR_REGISTRATIONS, "number of validator registrations held by the relay");
+    describe_counter!(
+        BUILDER_FAILURES,
+        "number of failed bid submissions counted against the reputation of each builder"
+    );
+    describe_counter!(BUILDERS_BANNED, "number of times each builder was banned for failures");
+    describe_counter!(
+        MISSED_BIDS,
+        "number of slots with a registered proposer to which no bid was served"
+    );
+    describe_gauge!(
+        RECENT_MISSED_BIDS,
+        "number of recent slots with a registered proposer to which no bid was served"
+    );
+    describe_gauge!(
+        PAYLOAD_REQUESTS_IN_FLIGHT,
+        "number of requests to reveal a payload currently being handled"
+    );
+    describe_histogram!(REQUEST_DURATION, "latency of requests to the relay API in seconds");
+    describe_gauge!(OPEN_CONNECTIONS, "number of connections to the relay API currently open");
+    describe_counter!(
+        CONNECTIONS_REFUSED,
+        "number of connections to the relay API closed as they exceeded the connection limit"
+    );
+}
+
+/// Records the latency of a request to the relay API, labelled by its method, route and status
+pub fn record_request(summary: &RequestSummary) {
+    let route = summary.route.clone().unwrap_or_else(|| "unmatched".to_string());
+    histogram!(
+        REQUEST_DURATION,
+        summary.latency.as_secs_f64(),
+        "method" => summary.method.to_string(),
+        "route" => route,
+        "status" => summary.status.as_u16().to_string()
+    );
+}
+
+/// Records a change to the connections of the relay API
+pub fn record_connection(event: ConnectionEvent) {
+    match event {
+        ConnectionEvent::Opened => increment_gauge!(OPEN_CONNECTIONS, 1.0),
+        ConnectionEvent::Closed => decrement_gauge!(OPEN_CONNECTIONS, 1.0),
+        ConnectionEvent::Refused => counter!(CONNECTIONS_REFUSED, 1),
+    }
+}
+
+async fn handle_metrics(State(handle): State<PrometheusHandle>) -> String {
+    handle.render()
+}
+
+/// Installs a global Prometheus recorder and spawns a server exposing it at `/metrics`
+///
+/// Returns `None` if the recorder could not be installed, e.g. if another recorder
+/// has already been installed in this process.
+pub fn spawn_server<F>(
+    host: IpAddr,
+    port: u16,
+    tls: Option<Arc<ServerConfig>>,
+    shutdown: F,
+) -> Option<JoinHandle<()>>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let builder = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.into()), REQUEST_DURATION_BUCKETS)
+        .expect("buckets are not empty");
+    let handle = match builder.install_recorder() {
+        Ok(handle) => handle,
+        Err(err) => {
+            warn!(%err, "could not install metrics recorder; metrics will not be served");
+            return None
+        }
+    };
+    describe_metrics();
+
+    let router = Router::new().route("/metrics", get(handle_metrics)).with_state(handle);
+    let addr = SocketAddr::from((host, port));
+    Some(tokio::spawn(async move {
+        info!(tls = tls.is_some(), "serving metrics at {addr}...");
+        if let Err(err) = tls::serve(addr, router, tls, shutdown).await {
+            error!(%err, "error while serving metrics")
+        }
+    }))
+}
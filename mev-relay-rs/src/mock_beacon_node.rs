@@ -0,0 +1,12 @@
+use axum::Router;
+use std::net::{Ipv4Addr, SocketAddr};
+use url::Url;
+
+/// Serves `router` as a mock beacon node on an ephemeral port, returning the URL it is served at
+pub fn spawn_mock_beacon_node(router: Router) -> Url {
+    let server = axum::Server::bind(&SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+        .serve(router.into_make_service());
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    Url::parse(&format!("http://{addr}")).unwrap()
+}
@@ -0,0 +1,156 @@
+use ethereum_consensus::primitives::{BlsPublicKey, Hash32, Slot};
+use mev_rs::types::BidTrace;
+use std::{fmt, str::FromStr};
+
+/// Filters on the bid traces listed by the data API
+#[derive(Debug, Clone, Default)]
+pub struct BidTraceFilter {
+    pub slot: Option<Slot>,
+    pub builder_public_key: Option<BlsPublicKey>,
+    pub block_hash: Option<Hash32>,
+}
+
+impl BidTraceFilter {
+    pub fn matches(&self, bid_trace: &BidTrace) -> bool {
+        self.slot.map(|slot| bid_trace.slot == slot).unwrap_or(true) &&
+            self.builder_public_key
+                .as_ref()
+                .map(|public_key| &bid_trace.builder_public_key == public_key)
+                .unwrap_or(true) &&
+            self.block_hash
+                .as_ref()
+                .map(|block_hash| &bid_trace.block_hash == block_hash)
+                .unwrap_or(true)
+    }
+}
+
+/// Position in a listing of bid traces ordered by slot and then order of recording, both
+/// descending, before which the next page starts
+///
+/// Encoded as `{slot}-{sequence}`; a bare slot starts the page at the most recent entry of
+/// that slot. As the order of existing entries never changes, a cursor stays valid as new
+/// entries are recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cursor {
+    pub slot: Slot,
+    pub sequence: u64,
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.slot, self.sequence)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid cursor `{s}`");
+        match s.split_once('-') {
+            Some((slot, sequence)) => Ok(Self {
+                slot: slot.parse().map_err(|_| invalid())?,
+                sequence: sequence.parse().map_err(|_| invalid())?,
+            }),
+            None => Ok(Self { slot: s.parse().map_err(|_| invalid())?, sequence: u64::MAX }),
+        }
+    }
+}
+
+/// A page of entries, with the cursor of the next page if there may be more entries
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub entries: Vec<T>,
+    pub next_cursor: Option<Cursor>,
+}
+
+/// Returns at most `limit` of the `entries`, each with the sequence it was recorded at, that
+/// match the `filter`, ordered by slot and then sequence descending from before the `cursor`
+pub fn paginate<'a, T: Clone + 'a>(
+    entries: impl Iterator<Item = (u64, &'a T)>,
+    bid_trace: impl Fn(&T) -> &BidTrace,
+    filter: &BidTraceFilter,
+    cursor: Option<Cursor>,
+    limit: usize,
+) -> Page<T> {
+    let mut matching = entries
+        .map(|(sequence, entry)| (Cursor { slot: bid_trace(entry).slot, sequence }, entry))
+        .filter(|(position, entry)| {
+            cursor.map(|cursor| *position < cursor).unwrap_or(true) &&
+                filter.matches(bid_trace(entry))
+        })
+        .collect::<Vec<_>>();
+    matching.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+    let next_cursor = match limit.checked_sub(1) {
+        Some(last) if matching.len() > limit => Some(matching[last].0),
+        _ => None,
+    };
+    let entries = matching.into_iter().take(limit).map(|(_, entry)| entry.clone()).collect();
+    Page { entries, next_cursor }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(byte: u8) -> Hash32 {
+        Hash32::try_from([byte; 32].as_ref()).unwrap()
+    }
+
+    fn bid_trace(slot: Slot, block_hash: u8) -> BidTrace {
+        BidTrace { slot, block_hash: hash(block_hash), ..Default::default() }
+    }
+
+    fn slots_and_hashes(page: &Page<BidTrace>) -> Vec<(Slot, Hash32)> {
+        page.entries
+            .iter()
+            .map(|bid_trace| (bid_trace.slot, bid_trace.block_hash.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_cursor_encoding() {
+        let cursor = Cursor { slot: 10, sequence: 3 };
+        assert_eq!(cursor.to_string().parse::<Cursor>().unwrap(), cursor);
+        assert_eq!("10".parse::<Cursor>().unwrap(), Cursor { slot: 10, sequence: u64::MAX });
+        assert!("ten".parse::<Cursor>().is_err());
+        assert!("10-".parse::<Cursor>().is_err());
+    }
+
+    #[test]
+    fn test_paginate_by_slot_descending() {
+        let page = |entries: &[BidTrace], cursor| {
+            let listing =
+                entries.iter().enumerate().map(|(sequence, entry)| (sequence as u64, entry));
+            paginate(listing, |entry| entry, &Default::default(), cursor, 2)
+        };
+        // a late entry for slot 10 is recorded after those of slot 11
+        let mut entries =
+            vec![bid_trace(10, 1), bid_trace(11, 2), bid_trace(11, 3), bid_trace(10, 4)];
+
+        let first = page(&entries, None);
+        assert_eq!(slots_and_hashes(&first), vec![(11, hash(3)), (11, hash(2))]);
+        let cursor = first.next_cursor.unwrap();
+
+        // new entries do not shift the pages after a cursor
+        entries.push(bid_trace(12, 5));
+        entries.push(bid_trace(11, 6));
+        let second = page(&entries, Some(cursor));
+        assert_eq!(slots_and_hashes(&second), vec![(10, hash(4)), (10, hash(1))]);
+        assert!(second.next_cursor.is_none());
+
+        // a bare slot starts at the most recent entry of the slot
+        let from_slot = page(&entries, Some("11".parse().unwrap()));
+        assert_eq!(slots_and_hashes(&from_slot), vec![(11, hash(6)), (11, hash(3))]);
+    }
+
+    #[test]
+    fn test_paginate_filters_entries() {
+        let entries = [bid_trace(10, 1), bid_trace(11, 2), bid_trace(12, 1)];
+        let listing = entries.iter().enumerate().map(|(sequence, entry)| (sequence as u64, entry));
+        let filter = BidTraceFilter { block_hash: Some(hash(1)), ..Default::default() };
+        let page = paginate(listing, |entry| entry, &filter, None, 100);
+        assert_eq!(slots_and_hashes(&page), vec![(12, hash(1)), (10, hash(1))]);
+        assert!(page.next_cursor.is_none());
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, SemaphorePermit};
+
+/// Bounds the concurrency and backlog of validator registration processing
+#[derive(Debug)]
+pub struct RegistrationPool {
+    // one permit per batch of registrations being processed
+    workers: Semaphore,
+    // one permit per batch of registrations accepted, whether waiting or being processed
+    queue: Arc<Semaphore>,
+    // if set, acknowledge registrations once queued rather than once processed
+    acknowledge_when_queued: bool,
+}
+
+impl RegistrationPool {
+    pub fn new(workers: usize, queue_depth: usize, acknowledge_when_queued: bool) -> Self {
+        let workers = workers.max(1);
+        // NOTE: batches being processed also count against the queue
+        let queue_depth = queue_depth.max(workers);
+        Self {
+            workers: Semaphore::new(workers),
+            queue: Arc::new(Semaphore::new(queue_depth)),
+            acknowledge_when_queued,
+        }
+    }
+
+    /// Reserves a place in the queue for a batch of registrations, returning `None` if
+    /// the queue is full
+    pub fn try_enqueue(&self) -> Option<OwnedSemaphorePermit> {
+        self.queue.clone().try_acquire_owned().ok()
+    }
+
+    /// Waits until a worker is free to process a batch of registrations
+    pub async fn acquire_worker(&self) -> SemaphorePermit<'_> {
+        self.workers.acquire().await.expect("semaphore is never closed")
+    }
+
+    pub fn acknowledges_when_queued(&self) -> bool {
+        self.acknowledge_when_queued
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_registration_pool_bounds_queue() {
+        let pool = RegistrationPool::new(1, 2, false);
+        let first = pool.try_enqueue().unwrap();
+        let second = pool.try_enqueue().unwrap();
+        assert!(pool.try_enqueue().is_none());
+
+        drop(first);
+        let third = pool.try_enqueue().unwrap();
+
+        let worker = pool.acquire_worker().await;
+        assert!(pool.workers.try_acquire().is_err());
+        drop(worker);
+        assert!(pool.workers.try_acquire().is_ok());
+        drop((second, third));
+    }
+}
@@ -0,0 +1,319 @@
+use async_trait::async_trait;
+use ethereum_consensus::primitives::BlsPublicKey;
+use mev_rs::types::SignedValidatorRegistration;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl From<Error> for mev_rs::Error {
+    fn from(err: Error) -> Self {
+        Self::RegistrationStore(err.to_string())
+    }
+}
+
+/// Outcome of storing a registration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PutOutcome {
+    /// The registration is stored, replacing any of the validator with an earlier timestamp
+    Stored,
+    /// The registration is ignored, as the one stored for the validator has a later timestamp
+    Stale,
+}
+
+// Registrations are last-write-wins by timestamp, so that a replayed registration can not
+// replace the newer one of a validator.
+fn is_stale(
+    registration: &SignedValidatorRegistration,
+    stored: &SignedValidatorRegistration,
+) -> bool {
+    registration.message.timestamp < stored.message.timestamp
+}
+
+/// Persists the latest signed registration of each validator across restarts of the relay
+#[async_trait]
+pub trait RegistrationStore: Send + Sync {
+    async fn get(
+        &self,
+        public_key: &BlsPublicKey,
+    ) -> Result<Option<SignedValidatorRegistration>, Error>;
+
+    /// Stores the `registration` unless the one stored for the validator is newer
+    async fn put(&self, registration: SignedValidatorRegistration) -> Result<PutOutcome, Error>;
+
+    /// Stores all of the `registrations` as [`Self::put`] would, returning the outcome of each;
+    /// implementations should override this to write them in a single batch where possible
+    async fn put_all(
+        &self,
+        registrations: Vec<SignedValidatorRegistration>,
+    ) -> Result<Vec<PutOutcome>, Error> {
+        let mut outcomes = Vec::with_capacity(registrations.len());
+        for registration in registrations {
+            outcomes.push(self.put(registration).await?);
+        }
+        Ok(outcomes)
+    }
+
+    async fn all(&self) -> Result<Vec<SignedValidatorRegistration>, Error>;
+
+    /// Removes the registrations of the validators with `public_keys`, if present
+    async fn remove_all(&self, public_keys: Vec<BlsPublicKey>) -> Result<(), Error>;
+}
+
+/// A [`RegistrationStore`] that only keeps registrations in memory
+#[derive(Debug, Default)]
+pub struct InMemoryRegistrationStore {
+    registrations: RwLock<HashMap<BlsPublicKey, SignedValidatorRegistration>>,
+}
+
+#[async_trait]
+impl RegistrationStore for InMemoryRegistrationStore {
+    async fn get(
+        &self,
+        public_key: &BlsPublicKey,
+    ) -> Result<Option<SignedValidatorRegistration>, Error> {
+        Ok(self.registrations.read().get(public_key).cloned())
+    }
+
+    async fn put(&self, registration: SignedValidatorRegistration) -> Result<PutOutcome, Error> {
+        let mut registrations = self.registrations.write();
+        let public_key = &registration.message.public_key;
+        if registrations
+            .get(public_key)
+            .map(|stored| is_stale(&registration, stored))
+            .unwrap_or(false)
+        {
+            return Ok(PutOutcome::Stale)
+        }
+        registrations.insert(public_key.clone(), registration);
+        Ok(PutOutcome::Stored)
+    }
+
+    async fn all(&self) -> Result<Vec<SignedValidatorRegistration>, Error> {
+        Ok(self.registrations.read().values().cloned().collect())
+    }
+
+    async fn remove_all(&self, public_keys: Vec<BlsPublicKey>) -> Result<(), Error> {
+        let mut registrations = self.registrations.write();
+        for public_key in &public_keys {
+            registrations.remove(public_key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteRegistrationStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::{is_stale, Error, PutOutcome, RegistrationStore};
+    use async_trait::async_trait;
+    use ethereum_consensus::primitives::BlsPublicKey;
+    use mev_rs::types::SignedValidatorRegistration;
+    use parking_lot::Mutex;
+    use rusqlite::{params, Connection, OptionalExtension};
+    use std::{path::Path, sync::Arc};
+
+    /// A [`RegistrationStore`] backed by a SQLite database
+    #[derive(Clone)]
+    pub struct SqliteRegistrationStore {
+        connection: Arc<Mutex<Connection>>,
+    }
+
+    impl SqliteRegistrationStore {
+        pub fn open(path: &Path) -> Result<Self, Error> {
+            let connection = Connection::open(path)?;
+            connection.execute(
+                "CREATE TABLE IF NOT EXISTS registrations (
+                    public_key TEXT PRIMARY KEY,
+                    registration TEXT NOT NULL
+                )",
+                [],
+            )?;
+            Ok(Self { connection: Arc::new(Mutex::new(connection)) })
+        }
+
+        // Runs `f` against the database off of the async runtime
+        async fn with_connection<T, F>(&self, f: F) -> Result<T, Error>
+        where
+            T: Send + 'static,
+            F: FnOnce(&mut Connection) -> Result<T, Error> + Send + 'static,
+        {
+            let connection = self.connection.clone();
+            tokio::task::spawn_blocking(move || f(&mut connection.lock())).await?
+        }
+    }
+
+    #[async_trait]
+    impl RegistrationStore for SqliteRegistrationStore {
+        async fn get(
+            &self,
+            public_key: &BlsPublicKey,
+        ) -> Result<Option<SignedValidatorRegistration>, Error> {
+            let public_key = public_key.to_string();
+            self.with_connection(move |connection| {
+                let registration = connection
+                    .query_row(
+                        "SELECT registration FROM registrations WHERE public_key = ?1",
+                        params![public_key],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .optional()?;
+                match registration {
+                    Some(registration) => Ok(Some(serde_json::from_str(&registration)?)),
+                    None => Ok(None),
+                }
+            })
+            .await
+        }
+
+        async fn put(
+            &self,
+            registration: SignedValidatorRegistration,
+        ) -> Result<PutOutcome, Error> {
+            let outcomes = self.put_all(vec![registration]).await?;
+            Ok(outcomes[0])
+        }
+
+        async fn put_all(
+            &self,
+            registrations: Vec<SignedValidatorRegistration>,
+        ) -> Result<Vec<PutOutcome>, Error> {
+            self.with_connection(move |connection| {
+                let transaction = connection.transaction()?;
+                let mut outcomes = Vec::with_capacity(registrations.len());
+                {
+                    let mut select = transaction.prepare_cached(
+                        "SELECT registration FROM registrations WHERE public_key = ?1",
+                    )?;
+                    let mut insert = transaction.prepare_cached(
+                        "INSERT OR REPLACE INTO registrations (public_key, registration)
+                        VALUES (?1, ?2)",
+                    )?;
+                    for registration in &registrations {
+                        let public_key = registration.message.public_key.to_string();
+                        let stored = select
+                            .query_row(params![public_key], |row| row.get::<_, String>(0))
+                            .optional()?;
+                        if let Some(stored) = stored {
+                            if is_stale(registration, &serde_json::from_str(&stored)?) {
+                                outcomes.push(PutOutcome::Stale);
+                                continue
+                            }
+                        }
+                        let encoding = serde_json::to_string(registration)?;
+                        insert.execute(params![public_key, encoding])?;
+                        outcomes.push(PutOutcome::Stored);
+                    }
+                }
+                transaction.commit()?;
+                Ok(outcomes)
+            })
+            .await
+        }
+
+        async fn all(&self) -> Result<Vec<SignedValidatorRegistration>, Error> {
+            self.with_connection(|connection| {
+                let mut statement = connection.prepare("SELECT registration FROM registrations")?;
+                let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+                let mut registrations = vec![];
+                for row in rows {
+                    registrations.push(serde_json::from_str(&row?)?);
+                }
+                Ok(registrations)
+            })
+            .await
+        }
+
+        async fn remove_all(&self, public_keys: Vec<BlsPublicKey>) -> Result<(), Error> {
+            self.with_connection(move |connection| {
+                let transaction = connection.transaction()?;
+                {
+                    let mut statement = transaction
+                        .prepare_cached("DELETE FROM registrations WHERE public_key = ?1")?;
+                    for public_key in &public_keys {
+                        statement.execute(params![public_key.to_string()])?;
+                    }
+                }
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_consensus::primitives::ExecutionAddress;
+
+    #[tokio::test]
+    async fn test_in_memory_registration_store() {
+        let store = InMemoryRegistrationStore::default();
+        let registration = SignedValidatorRegistration::default();
+        let public_key = registration.message.public_key.clone();
+        assert!(store.get(&public_key).await.unwrap().is_none());
+
+        assert_eq!(store.put(registration.clone()).await.unwrap(), PutOutcome::Stored);
+        let stored = store.get(&public_key).await.unwrap().unwrap();
+        assert_eq!(stored.message.public_key, public_key);
+
+        store.put_all(vec![registration.clone(), registration]).await.unwrap();
+        assert_eq!(store.all().await.unwrap().len(), 1);
+
+        store.remove_all(vec![public_key.clone()]).await.unwrap();
+        assert!(store.get(&public_key).await.unwrap().is_none());
+        assert!(store.all().await.unwrap().is_empty());
+    }
+
+    fn registration_at(timestamp: u64, fee_recipient: u8) -> SignedValidatorRegistration {
+        let mut registration = SignedValidatorRegistration::default();
+        registration.message.timestamp = timestamp;
+        registration.message.fee_recipient =
+            ExecutionAddress::try_from([fee_recipient; 20].as_ref()).unwrap();
+        registration
+    }
+
+    async fn assert_newer_registration_retained(store: &dyn RegistrationStore) {
+        let newer = registration_at(20, 1);
+        let public_key = newer.message.public_key.clone();
+        assert_eq!(store.put(newer.clone()).await.unwrap(), PutOutcome::Stored);
+        assert_eq!(store.put(registration_at(10, 2)).await.unwrap(), PutOutcome::Stale);
+        let stored = store.get(&public_key).await.unwrap().unwrap();
+        assert_eq!(stored.message.fee_recipient, newer.message.fee_recipient);
+
+        let outcomes =
+            store.put_all(vec![registration_at(15, 3), registration_at(30, 4)]).await.unwrap();
+        assert_eq!(outcomes, vec![PutOutcome::Stale, PutOutcome::Stored]);
+        let stored = store.get(&public_key).await.unwrap().unwrap();
+        assert_eq!(stored.message.timestamp, 30);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_ignores_stale_registrations() {
+        assert_newer_registration_retained(&InMemoryRegistrationStore::default()).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_store_ignores_stale_registrations() {
+        let path = std::env::temp_dir().join("mev-relay-rs-test-stale-registrations.db");
+        let _ = std::fs::remove_file(&path);
+        let store = SqliteRegistrationStore::open(&path).unwrap();
+        assert_newer_registration_retained(&store).await;
+        let _ = std::fs::remove_file(&path);
+    }
+}
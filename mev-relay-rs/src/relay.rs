@@ -1,50 +1,90 @@
+use crate::{
+    bid_queue::{self, BidBatches, BidQueue},
+    block_validator::BlockValidator,
+    builder_reputation::{BuilderFailure, BuilderReputation, ReputationPolicy},
+    builder_stats::{BuilderActivity, BuilderStats},
+    domains::Domains,
+    events::WinningBid,
+    metrics::{
+        builder_label, BIDS_SERVED, BLINDED_BLOCKS_RECEIVED, BUILDERS_BANNED, BUILDER_FAILURES,
+        BUILDER_SUBMISSIONS_REJECTED, DUPLICATE_BLOCK_HASHES_REJECTED, HEADER_CACHE_HITS,
+        HEADER_CACHE_MISSES, MISSED_BIDS, PAYLOAD_REQUESTS_IN_FLIGHT, RECENT_MISSED_BIDS,
+        VALIDATOR_REGISTRATIONS, VALIDATOR_REGISTRATIONS_RECEIVED,
+    },
+    pagination::{paginate, BidTraceFilter, Cursor, Page},
+    registration_pool::RegistrationPool,
+    registration_store::{PutOutcome, RegistrationStore},
+    slot_loop::SlotClock,
+    upstream::{self, UpstreamRelay, UpstreamRelays},
+};
 use async_trait::async_trait;
+use axum::{body::Bytes, http::StatusCode};
 use beacon_api_client::{
-    mainnet::Client as ApiClient, BroadcastValidation, PayloadAttributesEvent,
+    ApiError, BlockId, BroadcastValidation, Error as ApiClientError, PayloadAttributesEvent,
+    StateId, VersionedValue,
 };
 use ethereum_consensus::{
     bellatrix::mainnet as bellatrix,
-    capella::mainnet as capella,
+    capella::{mainnet as capella, Withdrawal},
     clock::get_current_unix_time_in_nanos,
     crypto::SecretKey,
     deneb::mainnet as deneb,
-    primitives::{BlsPublicKey, Epoch, Slot, U256},
-    ssz::prelude::Merkleized,
+    primitives::{BlsPublicKey, Domain, Epoch, ExecutionAddress, Hash32, Root, Slot, U256},
+    ssz::prelude::{List, Merkleized},
     state_transition::Context,
     types::mainnet::{ExecutionPayloadHeaderRef, SignedBeaconBlock},
     Error as ConsensusError, Fork,
 };
+use lru::LruCache;
+use metrics::{counter, decrement_gauge, gauge, increment_gauge};
 use mev_rs::{
-    signing::sign_builder_message,
+    respects_preferred_gas_limit,
+    signing::{compute_signing_root, verify_signature},
     types::{
-        builder_bid, AuctionContents, AuctionRequest, BidTrace, BuilderBid, ExecutionPayload,
-        ExecutionPayloadHeader, ProposerSchedule, SignedBidSubmission, SignedBlindedBeaconBlock,
+        builder_bid, deneb::BlobsBundle, header_from_payload, AuctionContents, AuctionRequest,
+        BidTrace, BuilderBid, Compliance, ExecutionPayload, ExecutionPayloadHeader,
+        ProposerPreferences, ProposerSchedule, SignedBidSubmission, SignedBlindedBeaconBlock,
         SignedBuilderBid, SignedValidatorRegistration,
     },
-    BlindedBlockProvider, BlindedBlockRelayer, Error, ProposerScheduler, RelayError,
-    ValidatorRegistry,
+    BeaconNodePool, BlindedBlockProvider, BlindedBlockRelayer, Error, ProposerScheduler,
+    RelayError, ValidatorRegistry, ValidatorRegistryError,
 };
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    num::NonZeroUsize,
     ops::Deref,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{broadcast, Semaphore, SemaphorePermit},
+    task::JoinHandle,
 };
-use tracing::{debug, error, info, trace, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, instrument, trace, warn};
 
 // Sets the lifetime of an auction with respect to its proposal slot.
 const AUCTION_LIFETIME_SLOTS: Slot = 1;
 
-fn to_header(execution_payload: &mut ExecutionPayload) -> Result<ExecutionPayloadHeader, Error> {
-    let header = match execution_payload {
-        ExecutionPayload::Bellatrix(payload) => {
-            ExecutionPayloadHeader::Bellatrix(payload.try_into()?)
-        }
-        ExecutionPayload::Capella(payload) => ExecutionPayloadHeader::Capella(payload.try_into()?),
-        ExecutionPayload::Deneb(payload) => ExecutionPayloadHeader::Deneb(payload.try_into()?),
-    };
-    Ok(header)
-}
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+const NANOS_PER_MILLI: u128 = 1_000_000;
+
+// Number of winning bids buffered for each subscriber before it is considered too slow.
+const WINNING_BIDS_CHANNEL_CAPACITY: usize = 64;
+// Number of recent slots over which slots missing a bid for their proposer are counted.
+const MISSED_BID_WINDOW_SLOTS: Slot = 64;
+// Number of validated submissions that may wait for the bid store before submissions fail.
+const BID_QUEUE_CAPACITY: usize = 1024;
+// Number of queued submissions the bid store inserts under a single acquisition of the state.
+const BID_STORE_BATCH_SIZE: usize = 64;
+// Number of epochs without a submission after which the activity of a builder is forgotten.
+const BUILDER_STATS_RETENTION_EPOCHS: Slot = 256;
 
 fn validate_header_equality(
     local_header: &ExecutionPayloadHeader,
@@ -183,6 +223,144 @@ fn unblind_block(
     }
 }
 
+/// Genesis details of the chain as reported by the beacon node
+#[derive(Debug, Clone)]
+pub struct Genesis {
+    pub time: u64,
+    pub validators_root: Root,
+}
+
+/// Range of slots around the current slot for which the relay serves bids
+#[derive(Debug, Clone, Copy)]
+pub struct SlotWindow {
+    // number of slots before the current slot
+    pub behind: Slot,
+    // number of slots after the current slot
+    pub ahead: Slot,
+}
+
+impl Default for SlotWindow {
+    // only the current and the next slot
+    fn default() -> Self {
+        Self { behind: 0, ahead: 1 }
+    }
+}
+
+impl SlotWindow {
+    pub fn contains(&self, current_slot: Slot, slot: Slot) -> bool {
+        slot + self.behind >= current_slot && slot <= current_slot + self.ahead
+    }
+}
+
+/// How the relay treats a request for a header on a parent other than the head of the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParentHashCheck {
+    /// Serve the bid, logging a warning
+    Warn,
+    /// Withhold the bid
+    Reject,
+}
+
+/// Policy of the relay that may be updated while it is running
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    // if present, only accept bids from these builders
+    pub allowed_builders: Option<HashSet<BlsPublicKey>>,
+    // if set, builders may lower the value of their own earlier bids
+    pub cancellations_enabled: bool,
+    // if present, ignore bids with a value below this amount (in wei)
+    pub min_bid_value: Option<U256>,
+    // if present, ban builders with too many failed submissions within a window
+    pub builder_reputation: Option<ReputationPolicy>,
+    // if present, reject submissions received this many milliseconds after the start of their slot
+    pub submission_cutoff_ms: Option<u64>,
+    // fee recipients bids may pay instead of the registered one, by proposer; if a proposer has
+    // none, bids must pay its registered fee recipient
+    pub alternate_fee_recipients: HashMap<BlsPublicKey, HashSet<ExecutionAddress>>,
+    // compliance assumed of builders that do not declare one; if absent, their bids are only
+    // served to proposers without a compliance preference
+    pub unknown_builder_compliance: Option<Compliance>,
+    // if present, serve the bids of these builders before their submissions are simulated,
+    // unless they have been demoted after a failed simulation
+    pub optimistic_builders: Option<HashSet<BlsPublicKey>>,
+    // if present, check the parent of each requested header is the head of the chain
+    pub parent_hash_check: Option<ParentHashCheck>,
+}
+
+/// Settings of the relay fixed for as long as it runs
+#[derive(Debug, Clone)]
+pub struct RelayOptions {
+    // number of recent slots to retain delivered payload records for
+    pub delivered_payload_retention_slots: Slot,
+    // number of recent slots to retain records of received builder submissions for
+    pub received_bid_retention_slots: Slot,
+    // if set, ingest registrations and bids but never serve bids or reveal payloads
+    pub observation_mode: bool,
+    // if present, evict the oldest registrations beyond this many at each slot
+    pub max_registrations: Option<usize>,
+    // slots around the current slot for which bids are served
+    pub header_slot_window: SlotWindow,
+    // number of slots after the current slot for which submissions are accepted
+    pub max_submission_slots_ahead: Slot,
+    // number of slots after initialization during which bids are not yet served
+    pub warmup_slots: Slot,
+    // if present, prefetch the proposer duties of the next epoch this many slots before it starts
+    pub proposer_duties_prefetch_slots: Option<Slot>,
+    // number of serialized bids to cache
+    pub header_cache_size: usize,
+    // time allowed to reveal a payload before failing, so that proposers can fall back,
+    // including the publish of its block
+    pub payload_timeout: Duration,
+    // number of requests to reveal a payload handled at once
+    pub max_payload_requests: usize,
+    // if present, number of builders expected to bid for each slot, for which the auctions of
+    // each epoch are allocated as it starts
+    pub expected_builders: Option<usize>,
+    // if present, genesis validators root of the configured network, which the beacon node must
+    // report unless `allow_genesis_mismatch` is set
+    pub expected_genesis_validators_root: Option<Root>,
+    pub allow_genesis_mismatch: bool,
+    // if present, check this many slots after each delivery whether its block was published
+    pub delivered_payload_check_slots: Option<Slot>,
+    // if present, genesis time the slot clock runs from in place of that of the beacon node
+    pub genesis_time_override: Option<u64>,
+}
+
+impl Default for RelayOptions {
+    fn default() -> Self {
+        Self {
+            delivered_payload_retention_slots: 64,
+            received_bid_retention_slots: 8,
+            observation_mode: false,
+            max_registrations: None,
+            header_slot_window: SlotWindow::default(),
+            max_submission_slots_ahead: 2,
+            warmup_slots: 0,
+            proposer_duties_prefetch_slots: None,
+            header_cache_size: 512,
+            payload_timeout: Duration::from_secs(3),
+            max_payload_requests: 64,
+            expected_builders: None,
+            expected_genesis_validators_root: None,
+            allow_genesis_mismatch: false,
+            delivered_payload_check_slots: None,
+            genesis_time_override: None,
+        }
+    }
+}
+
+/// Services the relay stores registrations with and validates submissions against
+pub struct RelayComponents {
+    pub registration_store: Box<dyn RegistrationStore>,
+    // bounds the work spent processing registrations at any one time
+    pub registration_pool: RegistrationPool,
+    // if present, simulate each builder submission before accepting it
+    pub block_validator: Option<Box<dyn BlockValidator>>,
+    // if present, forward registrations to these relays and mirror their bids
+    pub upstream_relays: Option<UpstreamRelays>,
+}
+
 #[derive(Clone)]
 pub struct Relay(Arc<Inner>);
 
@@ -197,20 +375,213 @@ impl Deref for Relay {
 pub struct Inner {
     secret_key: SecretKey,
     public_key: BlsPublicKey,
+    domains: Domains,
     validator_registry: ValidatorRegistry,
+    registration_store: Box<dyn RegistrationStore>,
+    // bounds the work spent processing registrations at any one time
+    registration_pool: RegistrationPool,
+    // if present, simulate each builder submission before accepting it
+    block_validator: Option<Box<dyn BlockValidator>>,
+    // if present, forward registrations to these relays and mirror their bids
+    upstream_relays: Option<UpstreamRelays>,
     proposer_scheduler: ProposerScheduler,
-    builder_registry: HashSet<BlsPublicKey>,
-    beacon_node: ApiClient,
+    // read as a snapshot so that each request sees a consistent policy
+    policy: RwLock<Arc<Policy>>,
+    // recent failures of each builder, reset whenever the policy is replaced
+    builder_reputation: RwLock<Option<Arc<BuilderReputation>>>,
+    // submissions received from each recently active builder
+    builder_stats: BuilderStats,
+    beacon_node: BeaconNodePool,
     context: Context,
+    clock: Arc<dyn SlotClock>,
+    options: RelayOptions,
+    // one permit per request to reveal a payload being handled
+    payload_requests: Semaphore,
+    // set while delivered payloads are being checked, so that checks of later slots never overlap
+    checking_delivered_payloads: AtomicBool,
+    // publishes each bid that becomes the best bid for its auction
+    winning_bids: broadcast::Sender<WinningBid>,
+    // serialized best bid of recently requested auctions
+    header_cache: Mutex<LruCache<AuctionRequest, CachedHeader>>,
+    // validated submissions waiting to be inserted into their auctions by the bid store
+    bid_queue: BidQueue<PendingBid>,
+    // taken by the bid store task once spawned
+    bid_batches: Mutex<Option<BidBatches<PendingBid>>>,
+    // set once the relay has been initialized
+    genesis: OnceLock<Genesis>,
     state: Mutex<State>,
 }
 
+// Holds a place among the payload requests in flight, counted by a gauge while held.
+struct PayloadRequestPermit<'a> {
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Drop for PayloadRequestPermit<'_> {
+    fn drop(&mut self) {
+        decrement_gauge!(PAYLOAD_REQUESTS_IN_FLIGHT, 1.0);
+    }
+}
+
 #[derive(Debug)]
 struct AuctionContext {
-    builder_public_key: BlsPublicKey,
+    bid_trace: BidTrace,
+    signed_builder_bid: SignedBuilderBid,
+    payload: BidPayload,
+    // order in which the relay received this bid, used to break ties in value
+    receipt_order: u64,
+    // compliance the builder declared for the block, if any
+    compliance: Option<Compliance>,
+}
+
+// Signed bid of a validated submission, waiting to be inserted into its auction
+#[derive(Debug)]
+struct PendingBid {
+    auction_request: AuctionRequest,
+    bid_trace: BidTrace,
     signed_builder_bid: SignedBuilderBid,
-    execution_payload: ExecutionPayload,
-    value: U256,
+    payload: BidPayload,
+    compliance: Option<Compliance>,
+    // if present, the submission of an optimistic builder to simulate once the bid is inserted
+    simulation: Option<PendingSimulation>,
+}
+
+// Submission of an optimistic builder, simulated after its bid is already being served
+#[derive(Debug)]
+struct PendingSimulation {
+    signed_submission: SignedBidSubmission,
+    registered_gas_limit: u64,
+}
+
+// Where the payload a bid commits to is revealed from
+#[derive(Debug)]
+enum BidPayload {
+    // submitted to this relay by a builder
+    Local { execution_payload: ExecutionPayload },
+    // held by the upstream relay the bid was mirrored from
+    Upstream(Arc<UpstreamRelay>),
+}
+
+// Bid served to a proposer, which the proposer may later sign a blinded block for
+#[derive(Debug, Clone)]
+struct ServedBid {
+    auction_context: Arc<AuctionContext>,
+    selection_reason: SelectionReason,
+}
+
+// A block unblinded with the payload of the bid served for it, to publish before revealing
+// the payload to its proposer
+struct UnblindedBlock {
+    signed_block: SignedBeaconBlock,
+    block_root: Root,
+    auction_request: AuctionRequest,
+    auction_context: Arc<AuctionContext>,
+    selection_reason: SelectionReason,
+    auction_contents: AuctionContents,
+}
+
+// Outcome of preparing to reveal the payload of a served bid
+enum PreparedReveal {
+    // the upstream relay holding the payload revealed it
+    Revealed(AuctionContents),
+    // the payload is held locally and is revealed once its block is published
+    Publish(Box<UnblindedBlock>),
+}
+
+// Serialized response to a proposer's request for the bid with `receipt_order`
+#[derive(Debug)]
+struct CachedHeader {
+    receipt_order: u64,
+    body: Bytes,
+}
+
+/// Why a bid won its auction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionReason {
+    /// The bid had a strictly higher value than every other bid
+    HighestValue,
+    /// The bid tied for the highest value and was received before the others
+    TieEarliest,
+    /// The bid was the only one in the auction
+    OnlyBid,
+}
+
+/// The bid trace of a payload delivered to a proposer, with why its bid was selected
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveredPayload {
+    #[serde(flatten)]
+    pub bid_trace: BidTrace,
+    pub selection_reason: SelectionReason,
+    // if present, the upstream relay the bid was mirrored from, in which case the
+    // `builder_public_key` of the bid trace is the public key of that relay
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_relay: Option<String>,
+    // if present, whether the block of the payload was found on chain when last checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivered_onchain: Option<bool>,
+    // set once the block of the payload has been checked again an epoch after the first check
+    #[serde(skip)]
+    pub rechecked: bool,
+}
+
+// Latest bid from each builder for a given `AuctionRequest`
+//
+// Bids are tracked per builder public key so that a cancellation (a lower bid replacing
+// a builder's own earlier bid) only ever affects that builder's bid; the best bid is then
+// selected across the latest bid of every builder.
+#[derive(Debug, Default)]
+struct Auction {
+    bids: HashMap<BlsPublicKey, Arc<AuctionContext>>,
+}
+
+impl Auction {
+    // Returns the bid with the highest value, preferring the earliest received bid in a tie.
+    fn best_bid(&self) -> Option<&Arc<AuctionContext>> {
+        self.best_bid_with_reason().map(|(best_bid, _)| best_bid)
+    }
+
+    // Returns the best bid along with why it was selected over the other bids.
+    fn best_bid_with_reason(&self) -> Option<(&Arc<AuctionContext>, SelectionReason)> {
+        self.best_eligible_bid_with_reason(|_| true)
+    }
+
+    // Returns the best bid among those `is_eligible`, along with why it was selected over the
+    // other eligible bids.
+    // NOTE: `receipt_order` is assigned in the order bids are accepted, so the tie-break
+    // is deterministic for the same order of submissions, independent of any hashing.
+    fn best_eligible_bid_with_reason(
+        &self,
+        is_eligible: impl Fn(&AuctionContext) -> bool,
+    ) -> Option<(&Arc<AuctionContext>, SelectionReason)> {
+        let eligible_bids = self.bids.values().filter(|bid| is_eligible(bid)).collect::<Vec<_>>();
+        let best_bid = *eligible_bids.iter().max_by(|a, b| {
+            a.bid_trace.value.cmp(&b.bid_trace.value).then(b.receipt_order.cmp(&a.receipt_order))
+        })?;
+        let reason = if eligible_bids.len() == 1 {
+            SelectionReason::OnlyBid
+        } else if eligible_bids.iter().any(|bid| {
+            bid.receipt_order != best_bid.receipt_order &&
+                bid.bid_trace.value == best_bid.bid_trace.value
+        }) {
+            SelectionReason::TieEarliest
+        } else {
+            SelectionReason::HighestValue
+        };
+        Some((best_bid, reason))
+    }
+
+    // Unless cancellations are enabled, a builder may only raise (or match) the value
+    // of its own previous bid.
+    fn accepts(&self, bid_trace: &BidTrace, cancellations_enabled: bool) -> bool {
+        if cancellations_enabled {
+            return true
+        }
+        self.bids
+            .get(&bid_trace.builder_public_key)
+            .map(|previous| previous.bid_trace.value <= bid_trace.value)
+            .unwrap_or(true)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -218,44 +589,452 @@ struct State {
     // contains validator public keys that have been updated since we last refreshed
     // the proposer scheduler
     outstanding_validator_updates: HashSet<BlsPublicKey>,
+    // compliance preferred by proposers, as sent with their latest registration
+    proposer_compliance: HashMap<BlsPublicKey, Compliance>,
+
+    // builders outside the allowlist seen during the current epoch
+    disallowed_builders_seen: HashSet<BlsPublicKey>,
+    // optimistic builders with a submission that failed simulation, whose submissions are
+    // simulated before being accepted until the policy is replaced
+    demoted_builders: HashSet<BlsPublicKey>,
 
     // auction state
     open_auctions: HashSet<AuctionRequest>,
-    auctions: HashMap<AuctionRequest, Arc<AuctionContext>>,
+    auctions: HashMap<AuctionRequest, Auction>,
+    bids_received: u64,
+    // builder who first submitted each block hash, by slot
+    block_hash_submitters: HashMap<Slot, HashMap<Hash32, BlsPublicKey>>,
+    // bids served to proposers for each auction, by block hash
+    served_bids: HashMap<AuctionRequest, HashMap<Hash32, ServedBid>>,
+
+    // bid traces of payloads delivered to proposers, in order of delivery and numbered by
+    // `payloads_delivered`
+    delivered_payloads: Vec<(u64, DeliveredPayload)>,
+    payloads_delivered: u64,
+    // bid traces of builder submissions accepted, in order of receipt and numbered by
+    // `bids_received`
+    received_bids: Vec<(u64, BidTrace)>,
+
+    // while warming up, the slot at which the warmup ends at the latest
+    warmup_end_slot: Option<Slot>,
+    // recent slots with a registered proposer to which no bid was served, oldest first
+    missed_bid_slots: VecDeque<Slot>,
+    // slot of the latest lookup of the head of the chain, with the hash of its execution block
+    head_block_hash: Option<(Slot, Option<Hash32>)>,
+    // slot and parent hash of the latest lookup of expected withdrawals, with their root
+    expected_withdrawals_root: Option<(Slot, Hash32, Root)>,
+    // empty auctions allocated ahead of the slots of the current epoch
+    spare_auctions: Vec<Auction>,
+}
+
+impl State {
+    // Allocates an auction and the record of block hashes for each slot of the epoch starting at
+    // `slot`, sized for `expected_builders`, so that the first submissions of each slot do not
+    // allocate them while holding the state.
+    fn allocate_auctions(&mut self, slot: Slot, slots_per_epoch: Slot, expected_builders: usize) {
+        let missing = (slots_per_epoch as usize).saturating_sub(self.spare_auctions.len());
+        self.spare_auctions.extend(
+            (0..missing).map(|_| Auction { bids: HashMap::with_capacity(expected_builders) }),
+        );
+        for slot in slot..slot + slots_per_epoch {
+            self.block_hash_submitters
+                .entry(slot)
+                .or_insert_with(|| HashMap::with_capacity(expected_builders));
+        }
+    }
 }
 
 impl Relay {
     pub fn new(
-        beacon_node: ApiClient,
+        beacon_node: BeaconNodePool,
         secret_key: SecretKey,
-        accepted_builders: Vec<BlsPublicKey>,
+        policy: Policy,
+        options: RelayOptions,
+        components: RelayComponents,
         context: Context,
-    ) -> Self {
+        clock: Arc<dyn SlotClock>,
+    ) -> Result<Self, Error> {
+        let RelayComponents {
+            registration_store,
+            registration_pool,
+            block_validator,
+            upstream_relays,
+        } = components;
         let public_key = secret_key.public_key();
+        let domains = Domains::new(&context)?;
         let slots_per_epoch = context.slots_per_epoch;
         let validator_registry = ValidatorRegistry::new(beacon_node.clone(), slots_per_epoch);
         let proposer_scheduler = ProposerScheduler::new(beacon_node.clone(), slots_per_epoch);
+        let (bid_queue, bid_batches) = bid_queue::bounded(BID_QUEUE_CAPACITY, BID_STORE_BATCH_SIZE);
+        let payload_requests = Semaphore::new(options.max_payload_requests.max(1));
+        let header_cache = Mutex::new(LruCache::new(
+            NonZeroUsize::new(options.header_cache_size).unwrap_or(NonZeroUsize::MIN),
+        ));
         let inner = Inner {
             secret_key,
             public_key,
+            domains,
             validator_registry,
+            registration_store,
+            registration_pool,
+            block_validator,
+            upstream_relays,
             proposer_scheduler,
-            builder_registry: HashSet::from_iter(accepted_builders),
+            builder_reputation: RwLock::new(
+                policy.builder_reputation.map(|policy| Arc::new(BuilderReputation::new(policy))),
+            ),
+            policy: RwLock::new(Arc::new(policy)),
+            builder_stats: BuilderStats::new(BUILDER_STATS_RETENTION_EPOCHS * slots_per_epoch),
             beacon_node,
             context,
+            clock,
+            options,
+            payload_requests,
+            checking_delivered_payloads: AtomicBool::new(false),
+            winning_bids: broadcast::channel(WINNING_BIDS_CHANNEL_CAPACITY).0,
+            header_cache,
+            bid_queue,
+            bid_batches: Mutex::new(Some(bid_batches)),
+            genesis: Default::default(),
             state: Default::default(),
         };
         info!(public_key = %inner.public_key, "relay initialized");
-        Self(Arc::new(inner))
+        if inner.options.observation_mode {
+            warn!("relay is in observation mode and will not serve bids or reveal payloads");
+        }
+        Ok(Self(Arc::new(inner)))
+    }
+
+    /// Fetches the genesis details of the chain from the beacon node and restores any
+    /// validator registrations persisted by a previous run of the relay
+    pub async fn initialize(&self) -> Result<(), Error> {
+        let registrations = self.registration_store.all().await?;
+        let restored_keys = self.validator_registry.restore_registrations(registrations);
+        info!(count = restored_keys.len(), "restored validator registrations");
+        self.record_registration_count();
+        self.state.lock().outstanding_validator_updates.extend(restored_keys);
+
+        let details = self
+            .beacon_node
+            .call(|beacon_node| async move { beacon_node.get_genesis_details().await })
+            .await?;
+        let genesis = Genesis {
+            time: details.genesis_time,
+            validators_root: details.genesis_validators_root,
+        };
+        info!(
+            genesis_time = genesis.time,
+            genesis_validators_root = %genesis.validators_root,
+            "fetched genesis details from beacon node"
+        );
+        self.validate_genesis(&genesis)?;
+        if let Some(slot) = self.clock.current_slot() {
+            self.domains.on_slot(slot, &genesis.validators_root, &self.context)?;
+            self.start_warmup(slot);
+        }
+        let _ = self.genesis.set(genesis);
+        Ok(())
+    }
+
+    // Checks the beacon node follows the chain of the configured network, so that the relay never
+    // signs over the domains of another chain.
+    fn validate_genesis(&self, genesis: &Genesis) -> Result<(), Error> {
+        let Some(expected) = self.options.expected_genesis_validators_root.as_ref() else {
+            return Ok(())
+        };
+        let provided = &genesis.validators_root;
+        if provided == expected {
+            return Ok(())
+        }
+        if self.options.allow_genesis_mismatch {
+            warn!(%expected, %provided, "beacon node is not on the configured network; allowed");
+            return Ok(())
+        }
+        error!(%expected, %provided, "beacon node is not on the configured network; not starting");
+        Err(Error::GenesisMismatch { expected: expected.clone(), provided: provided.clone() })
+    }
+
+    // Withholds bids for the next `warmup_slots` slots, so that proposers are not served
+    // from the incomplete state of a relay that has only just started.
+    fn start_warmup(&self, slot: Slot) {
+        if self.options.warmup_slots == 0 {
+            return
+        }
+        let end_slot = slot + self.options.warmup_slots;
+        info!(end_slot, "warming up; not serving bids until proposer duties are loaded");
+        self.state.lock().warmup_end_slot = Some(end_slot);
+    }
+
+    // Ends the warmup once `slot` reaches its end or, earlier, once the proposer duties
+    // for the epoch of `slot` are loaded.
+    fn update_warmup(&self, slot: Slot) {
+        let epoch = slot / self.context.slots_per_epoch;
+        let has_duties = self.proposer_scheduler.has_duties_for(epoch);
+        let mut state = self.state.lock();
+        let Some(end_slot) = state.warmup_end_slot else { return };
+        if slot >= end_slot {
+            info!(slot, "warmup elapsed; serving bids");
+        } else if has_duties {
+            info!(slot, epoch, "proposer duties loaded; ending warmup early and serving bids");
+        } else {
+            return
+        }
+        state.warmup_end_slot = None;
+    }
+
+    /// Returns the slot by which the relay stops warming up, if it is still warming up
+    ///
+    /// The relay does not serve bids while warming up.
+    pub fn warmup_end_slot(&self) -> Option<Slot> {
+        self.state.lock().warmup_end_slot
+    }
+
+    // Verifies and stores `registrations`, off the async runtime as verification is CPU-bound,
+    // alongside the `preferences` of each proposer.
+    async fn process_registrations(
+        &self,
+        registrations: Vec<SignedValidatorRegistration>,
+        preferences: Vec<ProposerPreferences>,
+    ) -> Result<(), Error> {
+        let current_time =
+            (get_current_unix_time_in_nanos() / NANOS_PER_SEC).try_into().expect("fits in type");
+        let registration_count = registrations.len();
+        let duplicates = find_duplicate_registrations(&registrations);
+        if !duplicates.is_empty() {
+            debug!(duplicates = duplicates.len(), "ignoring duplicate validator registrations");
+        }
+        let mut duplicate_errs = vec![];
+        let mut batch_indices = vec![];
+        let mut unique_registrations = vec![];
+        let mut compliance_by_proposer = HashMap::new();
+        for (index, registration) in registrations.into_iter().enumerate() {
+            if let Some(timestamp) = duplicates.get(&index) {
+                let err = ValidatorRegistryError::DuplicateRegistration(*timestamp);
+                duplicate_errs.push((index, err));
+                continue
+            }
+            if let Some(preferences) = preferences.get(index) {
+                let public_key = registration.message.public_key.clone();
+                compliance_by_proposer.insert(public_key, preferences.compliance);
+            }
+            batch_indices.push(index);
+            unique_registrations.push(registration);
+        }
+        let registrations = unique_registrations;
+        let relay = self.clone();
+        let (updated_keys, errs) = tokio::task::spawn_blocking(move || {
+            let mut registrations = registrations;
+            relay.validator_registry.process_registrations(
+                &mut registrations,
+                current_time,
+                relay.domains.builder(),
+            )
+        })
+        .await
+        .map_err(|err| {
+            if err.is_panic() {
+                std::panic::resume_unwind(err.into_panic())
+            }
+            // NOTE: the task is only cancelled as the runtime shuts down
+            warn!(%err, "processing of validator registrations was cancelled");
+            Error::NotReady
+        })?;
+
+        let updated_key_count = updated_keys.len();
+        info!(
+            updates = updated_key_count,
+            registrations = registration_count,
+            "processed validator registrations"
+        );
+        let updated_registrations = updated_keys
+            .iter()
+            .filter_map(|public_key| self.validator_registry.get_signed_registration(public_key))
+            .collect::<Vec<_>>();
+        {
+            let mut state = self.state.lock();
+            // NOTE: preferences are not signed, so only those sent with a registration that
+            // replaced the one held are recorded, and a replay of an old registration
+            // can not change them
+            for public_key in &updated_keys {
+                match compliance_by_proposer.get(public_key).copied().flatten() {
+                    Some(compliance) => {
+                        state.proposer_compliance.insert(public_key.clone(), compliance)
+                    }
+                    None => state.proposer_compliance.remove(public_key),
+                };
+            }
+            state.outstanding_validator_updates.extend(updated_keys);
+        }
+        self.record_registration_count();
+        if self.upstream_relays.is_some() && !updated_registrations.is_empty() {
+            // NOTE: only forward registrations verified and updated locally so that upstream
+            // relays are not sent the same registrations repeatedly
+            let relay = self.clone();
+            let registrations = updated_registrations.clone();
+            tokio::spawn(async move {
+                if let Some(upstream_relays) = relay.upstream_relays.as_ref() {
+                    upstream_relays.register_validators(&registrations).await;
+                }
+            });
+        }
+        match self.registration_store.put_all(updated_registrations).await {
+            Ok(outcomes) => {
+                let stale =
+                    outcomes.iter().filter(|&&outcome| outcome == PutOutcome::Stale).count();
+                if stale > 0 {
+                    warn!(stale, "ignored validator registrations older than those stored");
+                }
+            }
+            Err(err) => warn!(%err, "could not persist validator registrations"),
+        }
+
+        // NOTE: report errors by the index of the registration in the batch as received
+        let mut errs =
+            errs.into_iter().map(|(index, err)| (batch_indices[index], err)).collect::<Vec<_>>();
+        if !errs.is_empty() {
+            warn!(?errs, "error processing some registrations");
+        }
+        errs.extend(duplicate_errs);
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            errs.sort_by_key(|(index, _)| *index);
+            Err(Error::RegistrationErrors(errs))
+        }
+    }
+
+    /// Returns the public key the relay signs bids with
+    pub fn public_key(&self) -> &BlsPublicKey {
+        &self.public_key
+    }
+
+    /// Signs a dummy bid with the secret key of the relay and verifies it against the public
+    /// key of the relay, over both the cached builder domain and one computed afresh from the
+    /// context, so that a relay unable to serve verifiable bids fails before going live
+    pub fn self_test(&self) -> Result<(), Error> {
+        let domain = self.domains.builder();
+        let bid = BuilderBid::Capella(builder_bid::capella::BuilderBid {
+            header: ExecutionPayloadHeader::Capella(Default::default()),
+            value: U256::from(1),
+            public_key: self.public_key.clone(),
+        });
+        let mut signed_bid = bid.sign_with_domain(&self.secret_key, domain)?;
+        let signing_root = compute_signing_root(&mut signed_bid.message, domain)?;
+        verify_signature(&self.public_key, signing_root.as_ref(), &signed_bid.signature).map_err(
+            |err| {
+                let public_key = &self.public_key;
+                Error::SelfTestFailed(format!("bid does not verify against {public_key}: {err}"))
+            },
+        )?;
+        signed_bid.verify_signature(&self.context).map_err(|err| {
+            let reason = format!("bid does not verify over the builder domain of the chain: {err}");
+            Error::SelfTestFailed(reason)
+        })
+    }
+
+    /// Returns the context of the chain the relay serves, which schedules its forks
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Returns a snapshot of the current policy of the relay
+    pub fn policy(&self) -> Arc<Policy> {
+        self.policy.read().clone()
+    }
+
+    /// Replaces the policy of the relay, taking effect for subsequent requests
+    ///
+    /// Any failures counted against builders are forgotten, reinstating banned builders and
+    /// demoted optimistic builders.
+    pub fn set_policy(&self, policy: Policy) {
+        let builder_reputation =
+            policy.builder_reputation.map(|policy| Arc::new(BuilderReputation::new(policy)));
+        *self.builder_reputation.write() = builder_reputation;
+        self.state.lock().demoted_builders.clear();
+        *self.policy.write() = Arc::new(policy);
+    }
+
+    /// Returns a receiver of each bid that becomes the best bid for its auction
+    pub fn subscribe_winning_bids(&self) -> broadcast::Receiver<WinningBid> {
+        self.winning_bids.subscribe()
+    }
+
+    /// Returns `true` if the relay only observes traffic, without serving bids or payloads
+    pub fn observation_mode(&self) -> bool {
+        self.options.observation_mode
+    }
+
+    /// Returns the genesis details of the chain, if the relay has been initialized
+    pub fn genesis(&self) -> Option<&Genesis> {
+        self.genesis.get()
+    }
+
+    fn validate_initialized(&self) -> Result<(), Error> {
+        if self.genesis().is_some() {
+            Ok(())
+        } else {
+            Err(Error::NotReady)
+        }
+    }
+
+    /// Re-reads every validator registration from the registration store and replaces those
+    /// held in memory with them, e.g. after the store has been updated by another process,
+    /// returning the number of registrations loaded
+    ///
+    /// Any more recent registration already held for a validator is kept, and the proposal
+    /// schedule is rebuilt from the loaded registrations.
+    pub async fn reload_registrations(&self) -> Result<usize, Error> {
+        let registrations = self.registration_store.all().await?;
+        let count = self.validator_registry.replace_registrations(registrations);
+        info!(count, "reloaded validator registrations from the registration store");
+        self.record_registration_count();
+        if let Some(epoch) = self.clock.current_epoch() {
+            self.refresh_proposer_schedule(epoch).await;
+        }
+        Ok(count)
+    }
+
+    fn record_registration_count(&self) {
+        gauge!(VALIDATOR_REGISTRATIONS, self.validator_registry.registration_count() as f64);
+    }
+
+    // Keeps the number of registrations within `max_registrations`, if set, evicting the oldest
+    // registrations first but never those of proposers scheduled in the current or next epoch.
+    async fn evict_registrations(&self) {
+        let Some(max_registrations) = self.options.max_registrations else { return };
+        let scheduled_proposers = self.proposer_scheduler.get_scheduled_proposers();
+        let evicted_keys = self
+            .validator_registry
+            .evict_oldest_registrations(max_registrations, &scheduled_proposers);
+        if evicted_keys.is_empty() {
+            return
+        }
+        info!(
+            count = evicted_keys.len(),
+            max_registrations, "evicted oldest validator registrations"
+        );
+        self.record_registration_count();
+        {
+            let mut state = self.state.lock();
+            for public_key in &evicted_keys {
+                state.proposer_compliance.remove(public_key);
+            }
+        }
+        if let Err(err) = self.registration_store.remove_all(evicted_keys).await {
+            warn!(%err, "could not remove evicted validator registrations from the store");
+        }
     }
 
     pub async fn on_epoch(&self, epoch: Epoch) {
         info!(epoch, "processing");
 
+        self.state.lock().disallowed_builders_seen.clear();
+
         if let Err(err) = self.validator_registry.on_epoch(epoch).await {
             error!(%err, epoch, "could not update validator registry");
         }
         self.refresh_proposer_schedule(epoch).await;
+        self.update_warmup(epoch * self.context.slots_per_epoch);
     }
 
     async fn refresh_proposer_schedule(&self, epoch: Epoch) {
@@ -271,9 +1050,27 @@ impl Relay {
         }
     }
 
+    // Allocates the auctions of `epoch` ahead of its slots, if the number of builders expected
+    // to bid for each slot is configured.
+    pub fn prewarm_auctions(&self, epoch: Epoch) {
+        let Some(expected_builders) = self.options.expected_builders else { return };
+        let slots_per_epoch = self.context.slots_per_epoch;
+        self.state.lock().allocate_auctions(
+            epoch * slots_per_epoch,
+            slots_per_epoch,
+            expected_builders,
+        );
+        debug!(epoch, expected_builders, "allocated auctions for epoch");
+    }
+
     pub async fn on_slot(&self, slot: Slot) {
         info!(slot, "processing");
 
+        // NOTE: before pruning the bids served for the slot that just finished
+        if let Some(finished_slot) = slot.checked_sub(1) {
+            self.record_slot_outcome(finished_slot);
+        }
+
         // TODO: no reason to wait for slot boundary,
         // but likely want some more sophisticated channel machinery to dispatch updates
         let keys_to_refresh = {
@@ -295,6 +1092,195 @@ impl Relay {
         state
             .auctions
             .retain(|auction_request, _| auction_request.slot + AUCTION_LIFETIME_SLOTS >= slot);
+        state
+            .served_bids
+            .retain(|auction_request, _| auction_request.slot + AUCTION_LIFETIME_SLOTS >= slot);
+        state
+            .block_hash_submitters
+            .retain(|&submission_slot, _| submission_slot + AUCTION_LIFETIME_SLOTS >= slot);
+        let retention_slots = self.options.delivered_payload_retention_slots;
+        state
+            .delivered_payloads
+            .retain(|(_, delivered)| delivered.bid_trace.slot + retention_slots >= slot);
+        let retention_slots = self.options.received_bid_retention_slots;
+        state.received_bids.retain(|(_, bid_trace)| bid_trace.slot + retention_slots >= slot);
+        drop(state);
+
+        self.builder_stats.on_slot(slot);
+        if let Some(delay) = self.options.delivered_payload_check_slots {
+            self.spawn_delivered_payload_check(slot, delay);
+        }
+        self.update_warmup(slot);
+        self.evict_registrations().await;
+        self.prefetch_proposer_duties(slot).await;
+
+        if let Some(genesis) = self.genesis() {
+            if let Err(err) = self.domains.on_slot(slot, &genesis.validators_root, &self.context) {
+                warn!(%err, slot, "could not refresh signing domains");
+            }
+        }
+
+        let mut header_cache = self.header_cache.lock();
+        let past_auctions = header_cache
+            .iter()
+            .filter(|(auction_request, _)| auction_request.slot < slot)
+            .map(|(auction_request, _)| auction_request.clone())
+            .collect::<Vec<_>>();
+        for auction_request in past_auctions {
+            header_cache.pop(&auction_request);
+        }
+    }
+
+    // Checks delivered payloads as of `slot` in the background, so that a slow beacon node does
+    // not hold up the slot, returning `false` if the check of an earlier slot is still running.
+    // NOTE: any payload due is checked by the next run, as payloads are due from a slot on
+    fn spawn_delivered_payload_check(&self, slot: Slot, delay: Slot) -> bool {
+        if self.checking_delivered_payloads.swap(true, Ordering::AcqRel) {
+            debug!(slot, "still checking delivered payloads of an earlier slot; skipping");
+            return false
+        }
+        let relay = self.clone();
+        tokio::spawn(async move {
+            relay.check_delivered_payloads(slot, delay).await;
+            relay.checking_delivered_payloads.store(false, Ordering::Release);
+        });
+        true
+    }
+
+    // Checks whether the blocks of payloads delivered at least `delay` slots before `slot` and not
+    // yet checked were published, checking each block again from an epoch later so that a block
+    // reorged out after first appearing on chain is recorded as not on chain.
+    async fn check_delivered_payloads(&self, slot: Slot, delay: Slot) {
+        let recheck_delay = delay + self.context.slots_per_epoch;
+        let due = self
+            .state
+            .lock()
+            .delivered_payloads
+            .iter()
+            .filter(|(_, delivered)| {
+                let delivered_slot = delivered.bid_trace.slot;
+                (delivered.delivered_onchain.is_none() && delivered_slot + delay <= slot) ||
+                    (!delivered.rechecked && delivered_slot + recheck_delay <= slot)
+            })
+            .map(|(sequence, delivered)| {
+                (*sequence, delivered.bid_trace.slot, delivered.bid_trace.block_hash.clone())
+            })
+            .collect::<Vec<_>>();
+        for (sequence, delivered_slot, block_hash) in due {
+            let onchain = match self.is_block_onchain(delivered_slot, &block_hash).await {
+                Ok(onchain) => onchain,
+                Err(err) => {
+                    warn!(%err, slot = delivered_slot, "could not look up delivered block");
+                    continue
+                }
+            };
+            let mut state = self.state.lock();
+            let Some((_, delivered)) =
+                state.delivered_payloads.iter_mut().find(|(other, _)| *other == sequence)
+            else {
+                continue
+            };
+            match (delivered.delivered_onchain, onchain) {
+                (Some(true), false) => {
+                    warn!(slot = delivered_slot, %block_hash, "delivered block was reorged out")
+                }
+                (None, false) => {
+                    let proposer_public_key = &delivered.bid_trace.proposer_public_key;
+                    warn!(
+                        slot = delivered_slot,
+                        %block_hash,
+                        %proposer_public_key,
+                        "delivered block was not published"
+                    );
+                }
+                _ => {}
+            }
+            delivered.delivered_onchain = Some(onchain);
+            // NOTE: a block first checked an epoch or more after delivery needs no recheck
+            delivered.rechecked = delivered_slot + recheck_delay <= slot;
+        }
+    }
+
+    // Returns `true` if the canonical block at `slot` has the execution block with `block_hash`.
+    async fn is_block_onchain(&self, slot: Slot, block_hash: &Hash32) -> Result<bool, Error> {
+        let result =
+            self.beacon_node
+                .call(|beacon_node| async move {
+                    beacon_node.get_beacon_block(BlockId::Slot(slot)).await
+                })
+                .await;
+        let signed_block = match result {
+            Ok(signed_block) => signed_block,
+            // NOTE: the beacon node has no block for a slot that was missed
+            Err(ApiClientError::Api(ApiError::ErrorMessage { code, .. }))
+                if code == StatusCode::NOT_FOUND =>
+            {
+                return Ok(false)
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let onchain_block_hash = signed_block
+            .message()
+            .body()
+            .execution_payload()
+            .map(|execution_payload| execution_payload.block_hash().clone());
+        Ok(onchain_block_hash.as_ref() == Some(block_hash))
+    }
+
+    // Prefetches the proposer duties of the next epoch once `slot` is close enough to its start,
+    // so that they need not be fetched at the epoch boundary. A failed prefetch is retried at each
+    // later slot of the epoch, after which the duties are fetched at the boundary as usual.
+    async fn prefetch_proposer_duties(&self, slot: Slot) {
+        let Some(prefetch_slots) = self.options.proposer_duties_prefetch_slots else { return };
+        let slots_per_epoch = self.context.slots_per_epoch;
+        let next_epoch = slot / slots_per_epoch + 1;
+        if next_epoch * slots_per_epoch - slot > prefetch_slots {
+            return
+        }
+        if let Err(err) = self.proposer_scheduler.prefetch(next_epoch).await {
+            warn!(%err, slot, next_epoch, "could not prefetch proposer duties");
+        }
+    }
+
+    // Counts the finished `slot` as missed if a registered proposer was assigned to it but no
+    // bid was served to them; slots without a registered proposer never count as missed.
+    fn record_slot_outcome(&self, slot: Slot) {
+        // NOTE: bids are never served in observation mode
+        if self.options.observation_mode {
+            return
+        }
+        let has_registered_proposer = self
+            .proposer_scheduler
+            .get_proposer_public_key(slot)
+            .map(|public_key| {
+                self.validator_registry.get_signed_registration(&public_key).is_some()
+            })
+            .unwrap_or(false);
+        self.record_missed_bid(slot, has_registered_proposer);
+    }
+
+    fn record_missed_bid(&self, slot: Slot, has_registered_proposer: bool) {
+        let mut state = self.state.lock();
+        let bid_served =
+            state.served_bids.keys().any(|auction_request| auction_request.slot == slot);
+        if has_registered_proposer && !bid_served {
+            debug!(slot, "no bid was served to the registered proposer of the slot");
+            counter!(MISSED_BIDS, 1);
+            state.missed_bid_slots.push_back(slot);
+        }
+        while let Some(&oldest) = state.missed_bid_slots.front() {
+            if oldest + MISSED_BID_WINDOW_SLOTS > slot {
+                break
+            }
+            state.missed_bid_slots.pop_front();
+        }
+        gauge!(RECENT_MISSED_BIDS, state.missed_bid_slots.len() as f64);
+    }
+
+    /// Returns the number of slots among the most recent ones with a registered proposer
+    /// assigned to which no bid was served
+    pub fn recent_missed_bids(&self) -> usize {
+        self.state.lock().missed_bid_slots.len()
     }
 
     // TODO: build tip context and support reorgs...
@@ -314,43 +1300,402 @@ impl Relay {
         Ok(())
     }
 
-    fn get_auction_context(&self, auction_request: &AuctionRequest) -> Option<Arc<AuctionContext>> {
-        let state = self.state.lock();
-        state.auctions.get(auction_request).cloned()
-    }
+    /// Drops the auctions built on any of the `orphaned` parent hashes, e.g. after a reorg,
+    /// so that their bids are no longer served, returning the number of auctions dropped
+    pub fn drop_orphaned_bids(&self, orphaned: &HashSet<Hash32>) -> usize {
+        let mut state = self.state.lock();
+        let dropped = state
+            .auctions
+            .keys()
+            .filter(|auction_request| orphaned.contains(&auction_request.parent_hash))
+            .cloned()
+            .collect::<Vec<_>>();
+        for auction_request in &dropped {
+            state.auctions.remove(auction_request);
+        }
+        state
+            .open_auctions
+            .retain(|auction_request| !orphaned.contains(&auction_request.parent_hash));
+        drop(state);
 
-    fn validate_allowed_builder(&self, builder_public_key: &BlsPublicKey) -> Result<(), Error> {
-        if self.builder_registry.contains(builder_public_key) {
-            Ok(())
-        } else {
-            Err(RelayError::BuilderNotRegistered(builder_public_key.clone()).into())
+        let mut header_cache = self.header_cache.lock();
+        for auction_request in &dropped {
+            header_cache.pop(auction_request);
         }
+        dropped.len()
     }
 
-    fn validate_auction_request(&self, auction_request: &AuctionRequest) -> Result<(), RelayError> {
-        let state = self.state.lock();
-        if state.open_auctions.contains(auction_request) {
-            Ok(())
-        } else {
-            let err = RelayError::InvalidAuctionRequest(auction_request.clone());
-            warn!(%err);
-            Err(err)
-        }
+    /// Returns the registration of the validator with `public_key` as persisted in the
+    /// registration store, if any
+    pub async fn get_stored_registration(
+        &self,
+        public_key: &BlsPublicKey,
+    ) -> Result<Option<SignedValidatorRegistration>, Error> {
+        Ok(self.registration_store.get(public_key).await?)
     }
 
-    // NOTE: best route is likely through `execution-apis`
-    // fn compute_adjusted_gas_limit(&self, preferred_gas_limit: u64) -> u64 {
-    //     let parent_gas_limit = unimplemented!("need efficient way to get parent's gas limit");
-    //     compute_preferred_gas_limit(preferred_gas_limit, parent_gas_limit)
-    // }
+    /// Returns a page of at most `limit` of the payloads delivered to proposers that match the
+    /// `filter`, by slot descending and from before the `cursor` if provided
+    pub fn get_delivered_payloads_page(
+        &self,
+        filter: &BidTraceFilter,
+        cursor: Option<Cursor>,
+        limit: usize,
+    ) -> Page<DeliveredPayload> {
+        let state = self.state.lock();
+        let entries = state.delivered_payloads.iter().map(|(sequence, entry)| (*sequence, entry));
+        paginate(entries, |delivered| &delivered.bid_trace, filter, cursor, limit)
+    }
 
-    // Assume:
-    // - `execution_payload` is valid
-    // - pays the proposer the amount claimed in the `bid_trace`
-    // - respects the proposer's preferred gas limit, within protocol tolerance
-    fn validate_builder_submission_trusted(
+    /// Returns a page of at most `limit` of the builder submissions accepted by the relay that
+    /// match the `filter`, by slot descending and from before the `cursor` if provided
+    pub fn get_received_bids_page(
         &self,
-        bid_trace: &BidTrace,
+        filter: &BidTraceFilter,
+        cursor: Option<Cursor>,
+        limit: usize,
+    ) -> Page<BidTrace> {
+        let state = self.state.lock();
+        let entries = state.received_bids.iter().map(|(sequence, entry)| (*sequence, entry));
+        paginate(entries, |bid_trace| bid_trace, filter, cursor, limit)
+    }
+
+    /// Returns the submissions received from each recently active builder, most recently seen
+    /// first
+    pub fn get_builder_activity(&self) -> Vec<BuilderActivity> {
+        self.builder_stats.activity()
+    }
+
+    // Returns the best bid for the `auction_request` if it may be served to the proposer.
+    async fn select_bid_to_serve(
+        &self,
+        auction_request: &AuctionRequest,
+    ) -> Result<Arc<AuctionContext>, Error> {
+        self.validate_initialized()?;
+        if let Some(end_slot) = self.warmup_end_slot() {
+            debug!(%auction_request, end_slot, "warming up; not serving bid");
+            return Err(Error::NoBidPrepared(auction_request.clone()))
+        }
+        self.validate_slot_in_window(auction_request.slot)?;
+        self.validate_auction_request(auction_request)?;
+        self.validate_scheduled_proposer(auction_request)?;
+        self.mirror_upstream_bids(auction_request).await;
+
+        let (auction_context, selection_reason) = self
+            .get_auction_context(auction_request)
+            .ok_or_else(|| Error::NoBidPrepared(auction_request.clone()))?;
+        let signed_builder_bid = &auction_context.signed_builder_bid;
+        // NOTE: check again as the minimum may have been raised since the bid was accepted
+        if let Err(err) = self.validate_min_bid_value(auction_context.bid_trace.value) {
+            debug!(%err, %auction_request, "not serving bid");
+            return Err(Error::NoBidPrepared(auction_request.clone()))
+        }
+        self.validate_parent_hash(auction_request).await?;
+        if self.options.observation_mode {
+            info!(%auction_request, %signed_builder_bid, "observation mode enabled; not serving bid");
+            return Err(Error::NoBidPrepared(auction_request.clone()))
+        }
+        info!(%auction_request, %signed_builder_bid, "serving bid");
+        counter!(BIDS_SERVED, 1);
+        self.record_served_bid(auction_request, &auction_context, selection_reason);
+        Ok(auction_context)
+    }
+
+    // Returns the hash of the execution block at the head of the chain, or `None` before the
+    // merge, looking up the head from the beacon node at most once for each `slot`.
+    async fn head_block_hash(&self, slot: Slot) -> Result<Option<Hash32>, Error> {
+        if let Some((cached_slot, block_hash)) = &self.state.lock().head_block_hash {
+            if *cached_slot == slot {
+                return Ok(block_hash.clone())
+            }
+        }
+        let signed_block = self
+            .beacon_node
+            .call(|beacon_node| async move { beacon_node.get_beacon_block(BlockId::Head).await })
+            .await?;
+        let block_hash = signed_block
+            .message()
+            .body()
+            .execution_payload()
+            .map(|execution_payload| execution_payload.block_hash().clone());
+        self.state.lock().head_block_hash = Some((slot, block_hash.clone()));
+        Ok(block_hash)
+    }
+
+    // Checks the `auction_request` is for a header on the head of the chain, if the policy
+    // asks for it.
+    // NOTE: does not withhold the bid if the head can not be looked up
+    async fn validate_parent_hash(&self, auction_request: &AuctionRequest) -> Result<(), Error> {
+        let Some(check) = self.policy().parent_hash_check else { return Ok(()) };
+        let head_block_hash = match self.head_block_hash(auction_request.slot).await {
+            Ok(Some(block_hash)) => block_hash,
+            Ok(None) => return Ok(()),
+            Err(err) => {
+                warn!(%err, %auction_request, "could not look up head; not checking parent hash");
+                return Ok(())
+            }
+        };
+        if auction_request.parent_hash == head_block_hash {
+            return Ok(())
+        }
+        match check {
+            ParentHashCheck::Warn => {
+                warn!(%auction_request, %head_block_hash, "parent is not the head; serving bid");
+                Ok(())
+            }
+            ParentHashCheck::Reject => {
+                warn!(%auction_request, %head_block_hash, "parent is not head; not serving bid");
+                Err(Error::NoBidPrepared(auction_request.clone()))
+            }
+        }
+    }
+
+    fn record_served_bid(
+        &self,
+        auction_request: &AuctionRequest,
+        auction_context: &Arc<AuctionContext>,
+        selection_reason: SelectionReason,
+    ) {
+        let block_hash = auction_context.bid_trace.block_hash.clone();
+        let served_bid = ServedBid { auction_context: auction_context.clone(), selection_reason };
+        let mut state = self.state.lock();
+        state
+            .served_bids
+            .entry(auction_request.clone())
+            .or_default()
+            .insert(block_hash, served_bid);
+    }
+
+    // Returns the bid served for the `auction_request` that the proposer signed a blinded block
+    // with `provided_header` for, so that only payloads the relay offered are ever revealed.
+    fn get_served_bid(
+        &self,
+        auction_request: &AuctionRequest,
+        provided_header: ExecutionPayloadHeaderRef<'_>,
+    ) -> Result<ServedBid, RelayError> {
+        let block_hash = provided_header.block_hash();
+        let served_bid = self
+            .state
+            .lock()
+            .served_bids
+            .get(auction_request)
+            .and_then(|served_bids| served_bids.get(block_hash))
+            .cloned()
+            .ok_or_else(|| RelayError::BidNotServed(auction_request.clone(), block_hash.clone()))?;
+        let local_header = served_bid.auction_context.signed_builder_bid.message.header();
+        if let Err(err) = validate_header_equality(local_header, provided_header) {
+            warn!(%err, %auction_request, "invalid incoming signed blinded beacon block");
+            return Err(RelayError::InvalidSignedBlindedBeaconBlock)
+        }
+        Ok(served_bid)
+    }
+
+    fn get_auction_context(
+        &self,
+        auction_request: &AuctionRequest,
+    ) -> Option<(Arc<AuctionContext>, SelectionReason)> {
+        let unknown_builder_compliance = self.policy().unknown_builder_compliance;
+        let state = self.state.lock();
+        let auction = state.auctions.get(auction_request)?;
+        let preference = state.proposer_compliance.get(&auction_request.public_key).copied();
+        auction
+            .best_eligible_bid_with_reason(|bid| {
+                is_eligible(preference, bid.compliance, unknown_builder_compliance)
+            })
+            .map(|(best_bid, reason)| (best_bid.clone(), reason))
+    }
+
+    fn accepts_bid(
+        &self,
+        auction_request: &AuctionRequest,
+        bid_trace: &BidTrace,
+        cancellations_enabled: bool,
+    ) -> bool {
+        let state = self.state.lock();
+        state
+            .auctions
+            .get(auction_request)
+            .map(|auction| auction.accepts(bid_trace, cancellations_enabled))
+            .unwrap_or(true)
+    }
+
+    fn validate_allowed_builder(&self, builder_public_key: &BlsPublicKey) -> Result<(), Error> {
+        let policy = self.policy();
+        let Some(allowed_builders) = policy.allowed_builders.as_ref() else { return Ok(()) };
+        if allowed_builders.contains(builder_public_key) {
+            return Ok(())
+        }
+
+        counter!(BUILDER_SUBMISSIONS_REJECTED, 1);
+        let first_seen =
+            self.state.lock().disallowed_builders_seen.insert(builder_public_key.clone());
+        if first_seen {
+            warn!(%builder_public_key, "rejecting submission from builder not on the allowlist");
+        }
+        Err(RelayError::BuilderNotAllowed(builder_public_key.clone()).into())
+    }
+
+    // Returns the time `slot` starts at in milliseconds since the unix epoch, by the genesis time
+    // the slot clock runs from.
+    fn slot_start_ms(&self, slot: Slot) -> Result<u64, Error> {
+        let genesis = self.genesis.get().ok_or(Error::NotReady)?;
+        let genesis_time = self.options.genesis_time_override.unwrap_or(genesis.time);
+        Ok((genesis_time + slot * self.context.seconds_per_slot) * 1000)
+    }
+
+    // Rejects submissions arriving too late into their slot for the proposer to act on the bid.
+    fn validate_submission_cutoff(&self, slot: Slot) -> Result<(), Error> {
+        let Some(cutoff_ms) = self.policy().submission_cutoff_ms else { return Ok(()) };
+        let slot_start_ms = self.slot_start_ms(slot)?;
+        let now_ms =
+            (get_current_unix_time_in_nanos() / NANOS_PER_MILLI).try_into().expect("fits in type");
+        Ok(check_submission_cutoff(slot, slot_start_ms, now_ms, cutoff_ms)?)
+    }
+
+    // Rejects submissions for slots too far ahead, e.g. from a builder with a faulty clock, so that
+    // they are never stored.
+    fn validate_submission_slot(&self, slot: Slot) -> Result<(), Error> {
+        let current_slot = self.clock.current_slot().ok_or(Error::NotReady)?;
+        Ok(check_submission_slot(slot, current_slot, self.options.max_submission_slots_ahead)?)
+    }
+
+    fn validate_builder_reputation(&self, builder_public_key: &BlsPublicKey) -> Result<(), Error> {
+        let Some(builder_reputation) = self.builder_reputation.read().clone() else {
+            return Ok(())
+        };
+        let Some(banned_for) = builder_reputation.banned_for(builder_public_key) else {
+            return Ok(())
+        };
+        // NOTE: round up so that a builder retrying as told is no longer banned
+        let retry_after_secs = banned_for.as_secs() + u64::from(banned_for.subsec_nanos() > 0);
+        Err(RelayError::BuilderBanned { public_key: builder_public_key.clone(), retry_after_secs }
+            .into())
+    }
+
+    fn record_builder_failure(&self, builder_public_key: &BlsPublicKey, failure: BuilderFailure) {
+        let Some(builder_reputation) = self.builder_reputation.read().clone() else { return };
+        let label = builder_label(builder_public_key);
+        counter!(BUILDER_FAILURES, 1, "builder" => label.clone(), "failure" => failure.as_str());
+        if let Some(ban) = builder_reputation.record_failure(builder_public_key) {
+            counter!(BUILDERS_BANNED, 1, "builder" => label);
+            warn!(%builder_public_key, ?failure, ?ban, "banning builder after repeated failures");
+        }
+    }
+
+    // Whether the bids of the builder are served before its submissions are simulated, as it is
+    // an optimistic builder that has not been demoted.
+    fn is_optimistic(&self, builder_public_key: &BlsPublicKey) -> bool {
+        let policy = self.policy();
+        let Some(optimistic_builders) = policy.optimistic_builders.as_ref() else { return false };
+        optimistic_builders.contains(builder_public_key) &&
+            !self.state.lock().demoted_builders.contains(builder_public_key)
+    }
+
+    // Only serve bids for slots whose expected proposer, per the cached proposer duties,
+    // is the requesting validator and has a known registration.
+    fn validate_scheduled_proposer(&self, auction_request: &AuctionRequest) -> Result<(), Error> {
+        let slot = auction_request.slot;
+        let is_scheduled = self
+            .proposer_scheduler
+            .get_proposer_public_key(slot)
+            .map(|public_key| {
+                public_key == auction_request.public_key &&
+                    self.validator_registry.get_signed_registration(&public_key).is_some()
+            })
+            .unwrap_or(false);
+        if is_scheduled {
+            Ok(())
+        } else {
+            debug!(%auction_request, "no registered proposer is scheduled for this slot");
+            Err(Error::NoBidPrepared(auction_request.clone()))
+        }
+    }
+
+    // Only reveal payloads to the proposer assigned to the slot in the cached proposer duties.
+    fn validate_assigned_proposer(&self, auction_request: &AuctionRequest) -> Result<(), Error> {
+        let slot = auction_request.slot;
+        let assigned_proposer = self.proposer_scheduler.get_proposer_public_key(slot);
+        check_assigned_proposer(slot, assigned_proposer, &auction_request.public_key).map_err(
+            |err| {
+                warn!(%err, %auction_request, "refusing to reveal payload to unassigned proposer");
+                err.into()
+            },
+        )
+    }
+
+    fn validate_slot_in_window(&self, slot: Slot) -> Result<(), Error> {
+        let current_slot = self.clock.current_slot().ok_or(Error::NotReady)?;
+        if self.options.header_slot_window.contains(current_slot, slot) {
+            Ok(())
+        } else {
+            Err(RelayError::SlotOutsideWindow { slot, current_slot }.into())
+        }
+    }
+
+    fn validate_min_bid_value(&self, value: U256) -> Result<(), RelayError> {
+        match self.policy().min_bid_value {
+            Some(min_bid_value) if value < min_bid_value => {
+                Err(RelayError::BidBelowMinimum(value, min_bid_value))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Records the builder of `bid_trace` as the submitter of its block hash, unless another
+    // builder has already submitted the same block hash for the slot, in which case the
+    // first submitter is kept and the submission is rejected.
+    fn record_block_hash_submitter(&self, bid_trace: &BidTrace) -> Result<(), RelayError> {
+        let builder_public_key = &bid_trace.builder_public_key;
+        let block_hash = &bid_trace.block_hash;
+        let mut state = self.state.lock();
+        let submitters = state.block_hash_submitters.entry(bid_trace.slot).or_default();
+        let first_submitter =
+            submitters.entry(block_hash.clone()).or_insert_with(|| builder_public_key.clone());
+        if first_submitter == builder_public_key {
+            return Ok(())
+        }
+        counter!(DUPLICATE_BLOCK_HASHES_REJECTED, 1);
+        warn!(
+            slot = bid_trace.slot,
+            %block_hash,
+            %first_submitter,
+            %builder_public_key,
+            "rejecting submission of block hash already submitted by another builder"
+        );
+        Err(RelayError::DuplicateBlockHash(block_hash.clone(), first_submitter.clone()))
+    }
+
+    // Verifies the signature of the proposer with `public_key` over the `signed_block`
+    // before the payload it commits to is revealed.
+    fn verify_proposer_signature(
+        &self,
+        signed_block: &mut SignedBlindedBeaconBlock,
+        public_key: &BlsPublicKey,
+    ) -> Result<(), Error> {
+        let genesis = self.genesis().ok_or(Error::NotReady)?;
+        let slot = signed_block.message().slot();
+        let domain = self.domains.proposer(slot, &genesis.validators_root, &self.context)?;
+        verify_blinded_block_signature(signed_block, public_key, domain)
+    }
+
+    fn validate_auction_request(&self, auction_request: &AuctionRequest) -> Result<(), RelayError> {
+        let state = self.state.lock();
+        if state.open_auctions.contains(auction_request) {
+            Ok(())
+        } else {
+            let err = RelayError::InvalidAuctionRequest(auction_request.clone());
+            warn!(%err);
+            Err(err)
+        }
+    }
+
+    // Assume:
+    // - `execution_payload` is valid
+    // - pays the proposer the amount claimed in the `bid_trace`
+    // - respects the proposer's preferred gas limit, within protocol tolerance
+    fn validate_builder_submission_trusted(
+        &self,
+        bid_trace: &BidTrace,
         execution_payload: &ExecutionPayload,
     ) -> Result<(), RelayError> {
         let proposer_public_key = &bid_trace.proposer_public_key;
@@ -359,24 +1704,24 @@ impl Relay {
             .get_signed_registration(proposer_public_key)
             .ok_or_else(|| RelayError::ValidatorNotRegistered(proposer_public_key.clone()))?;
 
-        if bid_trace.proposer_fee_recipient != signed_registration.message.fee_recipient {
-            let fee_recipient = &signed_registration.message.fee_recipient;
-            return Err(RelayError::InvalidFeeRecipient(
+        check_fee_recipient(
+            proposer_public_key,
+            &bid_trace.proposer_fee_recipient,
+            &signed_registration.message.fee_recipient,
+            &self.policy().alternate_fee_recipients,
+        )?;
+
+        // NOTE: the parent's gas limit is not known here, so only check the block is within
+        // one adjustment step of the registered gas limit
+        let registered_gas_limit = signed_registration.message.gas_limit;
+        if !respects_preferred_gas_limit(bid_trace.gas_limit, registered_gas_limit) {
+            return Err(RelayError::InvalidGasLimitForProposer(
                 proposer_public_key.clone(),
-                fee_recipient.clone(),
+                registered_gas_limit,
+                bid_trace.gas_limit,
             ))
         }
 
-        // NOTE: disabled in the "trusted" validation
-        // let adjusted_gas_limit =
-        //     self.compute_adjusted_gas_limit(signed_registration.message.gas_limit);
-        // if bid_trace.gas_limit != adjusted_gas_limit {
-        //     return Err(Error::InvalidGasLimitForProposer(
-        //         proposer_public_key.clone(),
-        //         adjusted_gas_limit,
-        //     ))
-        // }
-
         if bid_trace.gas_limit != execution_payload.gas_limit() {
             return Err(RelayError::InvalidGasLimit(
                 bid_trace.gas_limit,
@@ -405,165 +1750,743 @@ impl Relay {
         Ok(())
     }
 
-    fn insert_bid_if_greater(
+    // Returns the root of the withdrawals the beacon node expects in a payload for `slot` on the
+    // block with `parent_hash`, looking them up again only once either changes.
+    //
+    // NOTE: the withdrawals are looked up on the state of the head block, so submissions on any
+    // other parent are rejected rather than checked against the withdrawals of another chain
+    async fn expected_withdrawals_root(
         &self,
-        auction_request: AuctionRequest,
-        mut execution_payload: ExecutionPayload,
-        value: U256,
-        builder_public_key: BlsPublicKey,
-    ) -> Result<(), Error> {
-        if let Some(bid) = self.get_auction_context(&auction_request) {
-            if bid.value > value {
-                info!(%auction_request, %builder_public_key, "block submission was not greater in value; ignoring");
-                return Ok(())
+        slot: Slot,
+        parent_hash: &Hash32,
+    ) -> Result<Root, RelayError> {
+        if let Some((cached_slot, cached_parent_hash, root)) =
+            &self.state.lock().expected_withdrawals_root
+        {
+            if *cached_slot == slot && cached_parent_hash == parent_hash {
+                return Ok(*root)
             }
         }
-        let header = to_header(&mut execution_payload)?;
-        let mut bid = match header.version() {
+        let unavailable = |err: ApiClientError| {
+            RelayError::BlockValidationUnavailable(format!(
+                "could not fetch expected withdrawals ({err})"
+            ))
+        };
+        let head = self
+            .beacon_node
+            .call(|beacon_node| async move { beacon_node.get_beacon_block(BlockId::Head).await })
+            .await
+            .map_err(unavailable)?;
+        let head_block_hash = head
+            .message()
+            .body()
+            .execution_payload()
+            .map(|execution_payload| execution_payload.block_hash().clone());
+        if head_block_hash.as_ref() != Some(parent_hash) {
+            warn!(slot, %parent_hash, ?head_block_hash, "submission is not on the head");
+            return Err(RelayError::ParentNotHead(parent_hash.clone()))
+        }
+        let state_root = *head.message().state_root();
+        let withdrawals = self
+            .beacon_node
+            .call(|beacon_node| async move {
+                let state_id = StateId::Root(state_root);
+                beacon_node.get_expected_withdrawals(state_id, Some(slot)).await
+            })
+            .await
+            .map_err(unavailable)?;
+        let root = withdrawals_root(&withdrawals)?;
+        self.state.lock().expected_withdrawals_root = Some((slot, parent_hash.clone(), root));
+        Ok(root)
+    }
+
+    // Checks the withdrawals of the `execution_payload` for the `bid_trace` are those the
+    // beacon node expects, so that a block that is not simulated can still be proposed.
+    async fn validate_withdrawals(
+        &self,
+        bid_trace: &BidTrace,
+        execution_payload: &ExecutionPayload,
+    ) -> Result<(), RelayError> {
+        // NOTE: only blocks from Capella on carry withdrawals
+        let fork = self.context.fork_for(bid_trace.slot);
+        if matches!(fork, Fork::Phase0 | Fork::Altair | Fork::Bellatrix) {
+            return Ok(())
+        }
+        let withdrawals = match execution_payload {
+            ExecutionPayload::Bellatrix(..) => return Ok(()),
+            ExecutionPayload::Capella(payload) => &payload.withdrawals,
+            ExecutionPayload::Deneb(payload) => &payload.withdrawals,
+        };
+        let provided = withdrawals_root(withdrawals)?;
+        let expected =
+            self.expected_withdrawals_root(bid_trace.slot, &bid_trace.parent_hash).await?;
+        if provided != expected {
+            return Err(RelayError::InvalidWithdrawalsRoot { expected, provided })
+        }
+        Ok(())
+    }
+
+    // Builds the bid served to the proposer for the given `execution_payload`, signed
+    // with the relay's key so it verifies against the relay's public key.
+    //
+    // NOTE: per the builder specs, the builder domain is computed with a zero
+    // `genesis_validators_root`, not the chain's genesis validators root.
+    fn sign_builder_bid(
+        &self,
+        execution_payload: &ExecutionPayload,
+        blobs_bundle: Option<&BlobsBundle>,
+        value: U256,
+    ) -> Result<SignedBuilderBid, Error> {
+        let header = header_from_payload(execution_payload)?;
+        let public_key = self.public_key.clone();
+        let bid = match header.version() {
             Fork::Bellatrix => BuilderBid::Bellatrix(builder_bid::bellatrix::BuilderBid {
                 header,
                 value,
-                public_key: self.public_key.clone(),
+                public_key,
             }),
-            Fork::Capella => BuilderBid::Capella(builder_bid::capella::BuilderBid {
-                header,
-                value,
-                public_key: self.public_key.clone(),
-            }),
-            Fork::Deneb => unimplemented!(),
+            Fork::Capella => {
+                BuilderBid::Capella(builder_bid::capella::BuilderBid { header, value, public_key })
+            }
+            Fork::Deneb => {
+                let blobs_bundle = blobs_bundle.ok_or(RelayError::MissingBlobsBundle)?;
+                BuilderBid::Deneb(builder_bid::deneb::BuilderBid {
+                    header,
+                    blob_kzg_commitments: blobs_bundle.commitments.clone(),
+                    value,
+                    public_key,
+                })
+            }
             _ => unreachable!("this fork is not reachable from this type"),
         };
-        let signature = sign_builder_message(&mut bid, &self.secret_key, &self.context)?;
-        let signed_builder_bid = SignedBuilderBid { message: bid, signature };
+        Ok(bid.sign_with_domain(&self.secret_key, self.domains.builder())?)
+    }
 
-        let block_hash = execution_payload.block_hash().clone();
-        let auction_context = Arc::new(AuctionContext {
-            builder_public_key,
-            signed_builder_bid,
-            execution_payload,
-            value,
-        });
-        info!(%auction_request, builder_public_key = %auction_context.builder_public_key, %block_hash, "inserting new bid");
-        let mut state = self.state.lock();
-        state.auctions.insert(auction_request, auction_context);
-        Ok(())
+    // Ensures the payload about to be delivered still pays the fee recipient the proposer
+    // has registered, or an alternate allowed for it, as the registration may have changed
+    // since the bid was submitted.
+    //
+    // NOTE: the proposer is paid at the `proposer_fee_recipient` of the bid trace, which
+    // need not be the `fee_recipient` of the execution payload if the builder pays the
+    // proposer with a transaction at the end of the block.
+    fn validate_delivered_fee_recipient(
+        &self,
+        auction_request: &AuctionRequest,
+        bid_trace: &BidTrace,
+    ) -> Result<(), RelayError> {
+        let public_key = &auction_request.public_key;
+        let signed_registration = self
+            .validator_registry
+            .get_signed_registration(public_key)
+            .ok_or_else(|| RelayError::ValidatorNotRegistered(public_key.clone()))?;
+        let registered_fee_recipient = &signed_registration.message.fee_recipient;
+        let delivered_fee_recipient = &bid_trace.proposer_fee_recipient;
+        let alternates = &self.policy().alternate_fee_recipients;
+        check_fee_recipient(
+            public_key,
+            delivered_fee_recipient,
+            registered_fee_recipient,
+            alternates,
+        )
+        .map_err(|err| {
+            warn!(
+                slot = auction_request.slot,
+                %registered_fee_recipient,
+                %delivered_fee_recipient,
+                "refusing to deliver payload that does not pay the registered fee recipient"
+            );
+            err
+        })
     }
-}
 
-#[async_trait]
-impl BlindedBlockProvider for Relay {
-    async fn register_validators(
+    // Signs the bid of a validated submission to be queued for the bid store, unless it is lower
+    // than the previous bid of its builder while cancellations are disabled.
+    fn prepare_bid(
         &self,
-        registrations: &mut [SignedValidatorRegistration],
-    ) -> Result<(), Error> {
-        let current_time = get_current_unix_time_in_nanos().try_into().expect("fits in type");
-        let (updated_keys, errs) = self.validator_registry.process_registrations(
-            registrations,
-            current_time,
-            &self.context,
-        );
+        auction_request: AuctionRequest,
+        execution_payload: ExecutionPayload,
+        blobs_bundle: Option<BlobsBundle>,
+        bid_trace: BidTrace,
+        compliance: Option<Compliance>,
+        simulation: Option<PendingSimulation>,
+    ) -> Result<Option<PendingBid>, Error> {
+        let builder_public_key = &bid_trace.builder_public_key;
+        let cancellations_enabled = self.policy().cancellations_enabled;
+        if !self.accepts_bid(&auction_request, &bid_trace, cancellations_enabled) {
+            info!(%auction_request, %builder_public_key, "block submission was lower in value than previous submission from builder and cancellations are disabled; ignoring");
+            return Ok(None)
+        }
+        let signed_builder_bid =
+            self.sign_builder_bid(&execution_payload, blobs_bundle.as_ref(), bid_trace.value)?;
+        let payload = BidPayload::Local { execution_payload };
+        Ok(Some(PendingBid {
+            auction_request,
+            bid_trace,
+            signed_builder_bid,
+            payload,
+            compliance,
+            simulation,
+        }))
+    }
 
-        let updated_key_count = updated_keys.len();
-        info!(
-            updates = updated_key_count,
-            registrations = registrations.len(),
-            "processed validator registrations"
-        );
+    // Inserts the `pending_bids` into their auctions under a single acquisition of the state,
+    // then starts the simulation of those from optimistic builders.
+    fn store_bids(&self, pending_bids: Vec<PendingBid>) {
+        let cancellations_enabled = self.policy().cancellations_enabled;
+        let mut simulations = vec![];
         let mut state = self.state.lock();
-        state.outstanding_validator_updates.extend(updated_keys);
+        let State { auctions, bids_received, received_bids, spare_auctions, .. } = &mut *state;
+        for pending_bid in pending_bids {
+            let PendingBid {
+                auction_request,
+                bid_trace,
+                signed_builder_bid,
+                payload,
+                compliance,
+                simulation,
+            } = pending_bid;
+            let builder_public_key = &bid_trace.builder_public_key;
+            let block_hash = &bid_trace.block_hash;
+            let auction = auctions
+                .entry(auction_request.clone())
+                .or_insert_with(|| spare_auctions.pop().unwrap_or_default());
+            // NOTE: check again in case another submission from this builder landed while
+            // this one was signed and queued
+            if !auction.accepts(&bid_trace, cancellations_enabled) {
+                info!(%auction_request, %builder_public_key, "block submission was lower in value than previous submission from builder and cancellations are disabled; ignoring");
+                continue
+            }
+            info!(%auction_request, %builder_public_key, %block_hash, "inserting new bid");
+            *bids_received += 1;
+            received_bids.push((*bids_received, bid_trace.clone()));
+            let auction_context = Arc::new(AuctionContext {
+                bid_trace,
+                signed_builder_bid,
+                payload,
+                receipt_order: *bids_received,
+                compliance,
+            });
+            auction.bids.insert(
+                auction_context.bid_trace.builder_public_key.clone(),
+                auction_context.clone(),
+            );
+            self.on_bid_inserted(&auction_request, auction, &auction_context);
+            if let Some(simulation) = simulation {
+                simulations.push((auction_request, auction_context, simulation));
+            }
+        }
+        drop(state);
 
-        if errs.is_empty() {
-            Ok(())
-        } else {
-            warn!(?errs, "error processing some registrations");
-            Err(Error::RegistrationErrors(errs))
+        for (auction_request, auction_context, simulation) in simulations {
+            self.spawn_optimistic_simulation(auction_request, auction_context, simulation);
         }
     }
 
-    async fn fetch_best_bid(
+    // Simulates the submission of an optimistic builder whose bid is already being served.
+    fn spawn_optimistic_simulation(
         &self,
-        auction_request: &AuctionRequest,
-    ) -> Result<SignedBuilderBid, Error> {
-        self.validate_auction_request(auction_request)?;
+        auction_request: AuctionRequest,
+        auction_context: Arc<AuctionContext>,
+        simulation: PendingSimulation,
+    ) {
+        let relay = self.clone();
+        tokio::spawn(async move {
+            let Some(block_validator) = relay.block_validator.as_ref() else { return };
+            let PendingSimulation { signed_submission, registered_gas_limit } = simulation;
+            if let Err(err) =
+                block_validator.validate(&signed_submission, registered_gas_limit).await
+            {
+                relay.on_optimistic_simulation_failed(&auction_request, &auction_context, err);
+            }
+        });
+    }
 
-        let auction_context = self
-            .get_auction_context(auction_request)
-            .ok_or_else(|| Error::NoBidPrepared(auction_request.clone()))?;
-        let signed_builder_bid = &auction_context.signed_builder_bid;
-        info!(%auction_request, %signed_builder_bid, "serving bid");
-        Ok(signed_builder_bid.clone())
+    // Withdraws the bid of an optimistic builder that failed simulation and, if its block was
+    // found invalid, demotes the builder and counts the failure against its reputation.
+    fn on_optimistic_simulation_failed(
+        &self,
+        auction_request: &AuctionRequest,
+        auction_context: &Arc<AuctionContext>,
+        err: Error,
+    ) {
+        let builder_public_key = &auction_context.bid_trace.builder_public_key;
+        let block_hash = &auction_context.bid_trace.block_hash;
+        // NOTE: only count blocks found invalid, not failures to reach the validator
+        if matches!(err, Error::Relay(RelayError::InvalidBlock(..))) {
+            self.record_builder_failure(builder_public_key, BuilderFailure::FailedSimulation);
+            if self.state.lock().demoted_builders.insert(builder_public_key.clone()) {
+                warn!(%builder_public_key, "demoting optimistic builder after failed simulation");
+            }
+        }
+        if self.withdraw_bid(auction_request, auction_context) {
+            error!(%err, %auction_request, %builder_public_key, %block_hash, "payload of optimistic bid was delivered before failing simulation");
+        } else {
+            warn!(%err, %auction_request, %builder_public_key, %block_hash, "withdrew optimistic bid that failed simulation");
+        }
     }
 
-    async fn open_bid(
+    // Removes the bid of `auction_context` from its auction and from the bids served for it, so
+    // that it is neither served nor revealed any longer, returning whether its payload was
+    // already delivered.
+    fn withdraw_bid(
         &self,
-        signed_block: &mut SignedBlindedBeaconBlock,
-    ) -> Result<AuctionContents, Error> {
-        let auction_request = {
-            let block = signed_block.message();
-            let slot = block.slot();
-            let body = block.body();
-            let payload_header = body.execution_payload_header();
-            let parent_hash = payload_header.parent_hash().clone();
-            let proposer_index = block.proposer_index();
-            let public_key = self
-                .validator_registry
-                .get_public_key(proposer_index)
-                .ok_or(RelayError::UnknownValidatorIndex(proposer_index))?;
-            AuctionRequest { slot, parent_hash, public_key }
-        };
+        auction_request: &AuctionRequest,
+        auction_context: &Arc<AuctionContext>,
+    ) -> bool {
+        let block_hash = &auction_context.bid_trace.block_hash;
+        let mut state = self.state.lock();
+        if let Some(auction) = state.auctions.get_mut(auction_request) {
+            auction.bids.retain(|_, bid| !Arc::ptr_eq(bid, auction_context));
+        }
+        if let Some(served_bids) = state.served_bids.get_mut(auction_request) {
+            served_bids.remove(block_hash);
+        }
+        let delivered = state
+            .delivered_payloads
+            .iter()
+            .any(|(_, delivered)| &delivered.bid_trace.block_hash == block_hash);
+        drop(state);
+        self.header_cache.lock().pop(auction_request);
+        delivered
+    }
 
-        self.validate_auction_request(&auction_request)?;
+    /// Spawns the bid store, which inserts the bids of validated submissions into their
+    /// auctions in the order they were queued, until `shutdown`
+    ///
+    /// Submissions fail with `503 Service Unavailable` while the queue of the bid store is full.
+    pub fn spawn_bid_store(&self, shutdown: CancellationToken) -> JoinHandle<()> {
+        let relay = self.clone();
+        let mut batches = self.bid_batches.lock().take().expect("bid store is only spawned once");
+        tokio::spawn(async move {
+            loop {
+                let batch = tokio::select! {
+                    batch = batches.next_batch() => batch,
+                    _ = shutdown.cancelled() => return,
+                };
+                let Some(batch) = batch else { return };
+                trace!(count = batch.len(), "storing queued bids");
+                relay.store_bids(batch);
+            }
+        })
+    }
 
-        let auction_context = self
-            .get_auction_context(&auction_request)
-            .ok_or_else(|| RelayError::MissingAuction(auction_request.clone()))?;
+    // Publishes the newly inserted `auction_context` if it is now the best bid of the `auction`.
+    fn on_bid_inserted(
+        &self,
+        auction_request: &AuctionRequest,
+        auction: &Auction,
+        auction_context: &Arc<AuctionContext>,
+    ) {
+        let is_best_bid = auction
+            .best_bid()
+            .map(|best_bid| Arc::ptr_eq(best_bid, auction_context))
+            .unwrap_or(false);
+        if is_best_bid {
+            self.header_cache.lock().pop(auction_request);
+            let bid_trace = &auction_context.bid_trace;
+            let winning_bid = WinningBid {
+                slot: bid_trace.slot,
+                builder_public_key: bid_trace.builder_public_key.clone(),
+                value: bid_trace.value,
+                block_hash: bid_trace.block_hash.clone(),
+            };
+            // NOTE: sending only fails if there are no subscribers
+            let _ = self.winning_bids.send(winning_bid);
+        }
+    }
 
-        {
-            let block = signed_block.message();
-            let body = block.body();
-            let execution_payload_header = body.execution_payload_header();
-            let local_header = auction_context.signed_builder_bid.message.header();
-            if let Err(err) = validate_header_equality(local_header, execution_payload_header) {
-                warn!(%err, %auction_request, "invalid incoming signed blinded beacon block");
-                return Err(RelayError::InvalidSignedBlindedBeaconBlock.into())
+    // Mirrors the bids of any upstream relays for the `auction_request` into its auction so
+    // they compete with the bids of builders; upstreams that fail are skipped so that
+    // the local bids are still served.
+    async fn mirror_upstream_bids(&self, auction_request: &AuctionRequest) {
+        let Some(upstream_relays) = self.upstream_relays.as_ref() else { return };
+        let proposer_public_key = &auction_request.public_key;
+        let Some(signed_registration) =
+            self.validator_registry.get_signed_registration(proposer_public_key)
+        else {
+            return
+        };
+        let fee_recipient = &signed_registration.message.fee_recipient;
+        let bids = upstream_relays.fetch_bids(auction_request, self.domains.builder()).await;
+        for (upstream, signed_bid) in bids {
+            let relay = upstream.to_string();
+            let bid = &signed_bid.message;
+            if let Err(err) =
+                self.insert_upstream_bid(auction_request, upstream, bid, fee_recipient)
+            {
+                warn!(%err, %auction_request, relay, "could not mirror upstream bid");
             }
         }
+    }
 
-        match unblind_block(signed_block, &auction_context.execution_payload) {
-            Ok(mut signed_block) => {
-                let version = signed_block.version();
-                let block_root =
-                    signed_block.message_mut().hash_tree_root().map_err(ConsensusError::from)?;
-                if let Err(err) = self
-                    .beacon_node
-                    .post_signed_beacon_block_v2(
-                        &signed_block,
-                        version,
-                        Some(BroadcastValidation::ConsensusAndEquivocation),
-                    )
-                    .await
-                {
-                    warn!(%err, %auction_request, %block_root, "block failed beacon node validation");
-                    Err(RelayError::InvalidSignedBlindedBeaconBlock.into())
-                } else {
-                    let local_payload = &auction_context.execution_payload;
-                    let block_hash = local_payload.block_hash();
-                    info!(%auction_request, %block_root, %block_hash, "returning local payload");
-                    let auction_contents = match local_payload.version() {
-                        Fork::Bellatrix => AuctionContents::Bellatrix(local_payload.clone()),
-                        Fork::Capella => AuctionContents::Capella(local_payload.clone()),
-                        Fork::Deneb => unimplemented!(),
-                        _ => unreachable!("fork not reachable from type"),
-                    };
-                    Ok(auction_contents)
-                }
+    // Inserts the `bid` served by the `upstream` relay into the auction for `auction_request`,
+    // re-signed with the relay's key as the proposer only trusts this relay.
+    //
+    // NOTE: the upstream relay is trusted to have validated the payload of the bid, including
+    // its payment to the `proposer_fee_recipient`
+    fn insert_upstream_bid(
+        &self,
+        auction_request: &AuctionRequest,
+        upstream: Arc<UpstreamRelay>,
+        bid: &BuilderBid,
+        proposer_fee_recipient: &ExecutionAddress,
+    ) -> Result<(), Error> {
+        let expected = self.context.fork_for(auction_request.slot);
+        let provided = bid.version();
+        if expected != provided {
+            return Err(Error::InvalidFork { expected, provided })
+        }
+        let header = bid.header();
+        let bid_trace = BidTrace {
+            slot: auction_request.slot,
+            parent_hash: auction_request.parent_hash.clone(),
+            block_hash: header.block_hash().clone(),
+            // NOTE: attribute the bid to the upstream relay as its builder is not known
+            builder_public_key: upstream.public_key.clone(),
+            proposer_public_key: auction_request.public_key.clone(),
+            proposer_fee_recipient: proposer_fee_recipient.clone(),
+            gas_limit: header.gas_limit(),
+            gas_used: header.gas_used(),
+            value: bid.value(),
+        };
+        // NOTE: keep a bid already mirrored so that it keeps its place in any tie
+        let is_mirrored = |state: &State| {
+            state
+                .auctions
+                .get(auction_request)
+                .and_then(|auction| auction.bids.get(&bid_trace.builder_public_key))
+                .map(|previous| previous.bid_trace.block_hash == bid_trace.block_hash)
+                .unwrap_or(false)
+        };
+        if is_mirrored(&self.state.lock()) {
+            return Ok(())
+        }
+
+        let signed_builder_bid = self.sign_upstream_bid(bid)?;
+        let mut state = self.state.lock();
+        if is_mirrored(&state) {
+            return Ok(())
+        }
+        let State { auctions, bids_received, spare_auctions, .. } = &mut *state;
+        let auction = auctions
+            .entry(auction_request.clone())
+            .or_insert_with(|| spare_auctions.pop().unwrap_or_default());
+        let block_hash = &bid_trace.block_hash;
+        info!(%auction_request, relay = %upstream, %block_hash, "inserting upstream bid");
+        *bids_received += 1;
+        let auction_context = Arc::new(AuctionContext {
+            bid_trace,
+            signed_builder_bid,
+            payload: BidPayload::Upstream(upstream),
+            receipt_order: *bids_received,
+            // NOTE: upstream relays do not relay the compliance of the builder of their bid
+            compliance: None,
+        });
+        auction
+            .bids
+            .insert(auction_context.bid_trace.builder_public_key.clone(), auction_context.clone());
+        self.on_bid_inserted(auction_request, auction, &auction_context);
+        Ok(())
+    }
+
+    // Signs the `bid` of an upstream relay with the relay's key, so that it verifies against
+    // the relay's public key.
+    fn sign_upstream_bid(&self, bid: &BuilderBid) -> Result<SignedBuilderBid, Error> {
+        let mut bid = bid.clone();
+        match &mut bid {
+            BuilderBid::Bellatrix(inner) | BuilderBid::Capella(inner) => {
+                inner.public_key = self.public_key.clone();
+            }
+            BuilderBid::Deneb(inner) => inner.public_key = self.public_key.clone(),
+        }
+        Ok(bid.sign_with_domain(&self.secret_key, self.domains.builder())?)
+    }
+
+    fn record_delivered_payload(
+        &self,
+        auction_context: &AuctionContext,
+        selection_reason: SelectionReason,
+    ) {
+        let upstream_relay = match &auction_context.payload {
+            BidPayload::Local { .. } => None,
+            BidPayload::Upstream(upstream) => Some(upstream.to_string()),
+        };
+        let delivered = DeliveredPayload {
+            bid_trace: auction_context.bid_trace.clone(),
+            selection_reason,
+            upstream_relay,
+            delivered_onchain: None,
+            rechecked: false,
+        };
+        let mut state = self.state.lock();
+        state.payloads_delivered += 1;
+        let sequence = state.payloads_delivered;
+        state.delivered_payloads.push((sequence, delivered));
+    }
+
+    // Asks the `upstream` relay the served bid of `auction_context` was mirrored from to reveal
+    // its payload, which must be the payload the bid commits to.
+    async fn open_upstream_bid(
+        &self,
+        signed_block: &SignedBlindedBeaconBlock,
+        auction_request: &AuctionRequest,
+        auction_context: &AuctionContext,
+        selection_reason: SelectionReason,
+        upstream: &UpstreamRelay,
+    ) -> Result<AuctionContents, Error> {
+        let auction_contents = upstream::open_bid(upstream, signed_block).await.map_err(|err| {
+            warn!(%err, %auction_request, relay = %upstream, "upstream did not reveal payload");
+            err
+        })?;
+        let header = header_from_payload(auction_contents.execution_payload())?;
+        if &header != auction_context.signed_builder_bid.message.header() {
+            warn!(%auction_request, relay = %upstream, "upstream revealed payload not in its bid");
+            return Err(RelayError::InvalidExecutionPayloadInBlock.into())
+        }
+        let block_hash = header.block_hash();
+        info!(%auction_request, %block_hash, relay = %upstream, "returning upstream payload");
+        self.record_delivered_payload(auction_context, selection_reason);
+        Ok(auction_contents)
+    }
+
+    // Reserves a place for a request to reveal a payload, failing fast if too many are being
+    // handled so that the requests in flight are not slowed down.
+    fn acquire_payload_request_permit(&self) -> Result<PayloadRequestPermit<'_>, Error> {
+        let permit = self.payload_requests.try_acquire().map_err(|_| {
+            warn!("too many payload requests in flight; rejecting request");
+            Error::TooManyPayloadRequests
+        })?;
+        increment_gauge!(PAYLOAD_REQUESTS_IN_FLIGHT, 1.0);
+        Ok(PayloadRequestPermit { _permit: permit })
+    }
+
+    // Prepares to reveal the payload of the bid served for the proposal in `signed_block`,
+    // unblinding the block if the payload is held locally so that it can be published.
+    async fn prepare_reveal(
+        &self,
+        signed_block: &mut SignedBlindedBeaconBlock,
+    ) -> Result<PreparedReveal, Error> {
+        self.validate_initialized()?;
+        if self.options.observation_mode {
+            let slot = signed_block.message().slot();
+            warn!(slot, "observation mode enabled; not revealing payload");
+            return Err(RelayError::ObservationMode.into())
+        }
+        let auction_request = {
+            let block = signed_block.message();
+            let slot = block.slot();
+            let body = block.body();
+            let payload_header = body.execution_payload_header();
+            let parent_hash = payload_header.parent_hash().clone();
+            let proposer_index = block.proposer_index();
+            let public_key = self
+                .validator_registry
+                .get_public_key(proposer_index)
+                .ok_or(RelayError::UnknownValidatorIndex(proposer_index))?;
+            AuctionRequest { slot, parent_hash, public_key }
+        };
+
+        self.validate_assigned_proposer(&auction_request)?;
+        self.validate_auction_request(&auction_request)?;
+        self.verify_proposer_signature(signed_block, &auction_request.public_key)?;
+
+        let ServedBid { auction_context, selection_reason } = {
+            let block = signed_block.message();
+            let body = block.body();
+            self.get_served_bid(&auction_request, body.execution_payload_header())?
+        };
+
+        let local_payload = match &auction_context.payload {
+            BidPayload::Local { execution_payload } => execution_payload,
+            BidPayload::Upstream(upstream) => {
+                let auction_contents = self
+                    .open_upstream_bid(
+                        signed_block,
+                        &auction_request,
+                        &auction_context,
+                        selection_reason,
+                        upstream,
+                    )
+                    .await?;
+                return Ok(PreparedReveal::Revealed(auction_contents))
+            }
+        };
+        self.validate_delivered_fee_recipient(&auction_request, &auction_context.bid_trace)?;
+
+        let auction_contents = match self.context.fork_for(auction_request.slot) {
+            Fork::Bellatrix => AuctionContents::Bellatrix(local_payload.clone()),
+            Fork::Capella => AuctionContents::Capella(local_payload.clone()),
+            // NOTE: Deneb payloads are rejected at submission, see `check_submission_fork`, so
+            // this only guards against publishing a block missing its blobs
+            Fork::Deneb => {
+                warn!(%auction_request, "publishing Deneb blocks is not supported; not revealing");
+                return Err(RelayError::UnsupportedFork(Fork::Deneb).into())
+            }
+            _ => unreachable!("fork not reachable from type"),
+        };
+        match unblind_block(signed_block, local_payload) {
+            Ok(mut signed_block) => {
+                let block_root =
+                    signed_block.message_mut().hash_tree_root().map_err(ConsensusError::from)?;
+                Ok(PreparedReveal::Publish(Box::new(UnblindedBlock {
+                    signed_block,
+                    block_root,
+                    auction_request,
+                    auction_context,
+                    selection_reason,
+                    auction_contents,
+                })))
             }
             Err(err) => {
                 warn!(%err, %auction_request, "invalid incoming signed blinded beacon block");
-                return Err(RelayError::InvalidSignedBlindedBeaconBlock.into())
+                Err(RelayError::InvalidSignedBlindedBeaconBlock.into())
             }
         }
     }
+
+    // Publishes the unblinded block, revealing its payload to the proposer once the beacon
+    // node accepts it.
+    async fn publish_unblinded_block(
+        &self,
+        unblinded_block: UnblindedBlock,
+    ) -> Result<AuctionContents, Error> {
+        let UnblindedBlock {
+            signed_block,
+            block_root,
+            auction_request,
+            auction_context,
+            selection_reason,
+            auction_contents,
+        } = unblinded_block;
+        let version = signed_block.version();
+        let signed_block = &signed_block;
+        if let Err(err) = self
+            .beacon_node
+            .call(|beacon_node| async move {
+                beacon_node
+                    .post_signed_beacon_block_v2(
+                        signed_block,
+                        version,
+                        Some(BroadcastValidation::ConsensusAndEquivocation),
+                    )
+                    .await
+            })
+            .await
+        {
+            warn!(%err, %auction_request, %block_root, "block failed beacon node validation");
+            return Err(RelayError::InvalidSignedBlindedBeaconBlock.into())
+        }
+        let block_hash = auction_contents.execution_payload().block_hash();
+        info!(%auction_request, %block_root, %block_hash, "returning local payload");
+        self.record_delivered_payload(&auction_context, selection_reason);
+        Ok(auction_contents)
+    }
+}
+
+#[async_trait]
+impl BlindedBlockProvider for Relay {
+    async fn register_validators(
+        &self,
+        registrations: &mut [SignedValidatorRegistration],
+    ) -> Result<(), Error> {
+        self.register_validators_with_preferences(registrations, &[]).await
+    }
+
+    async fn register_validators_with_preferences(
+        &self,
+        registrations: &mut [SignedValidatorRegistration],
+        preferences: &[ProposerPreferences],
+    ) -> Result<(), Error> {
+        counter!(VALIDATOR_REGISTRATIONS_RECEIVED, registrations.len() as u64);
+        let queued = self.registration_pool.try_enqueue().ok_or_else(|| {
+            warn!(registrations = registrations.len(), "registration queue is full");
+            Error::RegistrationQueueFull
+        })?;
+        let relay = self.clone();
+        let registrations = registrations.to_vec();
+        let preferences = preferences.to_vec();
+        let processing = async move {
+            let _queued = queued;
+            let _worker = relay.registration_pool.acquire_worker().await;
+            relay.process_registrations(registrations, preferences).await
+        };
+
+        if self.registration_pool.acknowledges_when_queued() {
+            tokio::spawn(async move {
+                if let Err(err) = processing.await {
+                    warn!(%err, "could not process queued validator registrations");
+                }
+            });
+            Ok(())
+        } else {
+            processing.await
+        }
+    }
+
+    fn queues_registrations(&self) -> bool {
+        self.registration_pool.acknowledges_when_queued()
+    }
+
+    #[instrument(skip_all, fields(slot = auction_request.slot))]
+    async fn fetch_best_bid(
+        &self,
+        auction_request: &AuctionRequest,
+    ) -> Result<SignedBuilderBid, Error> {
+        let auction_context = self.select_bid_to_serve(auction_request).await?;
+        Ok(auction_context.signed_builder_bid.clone())
+    }
+
+    #[instrument(skip_all, fields(slot = auction_request.slot))]
+    async fn fetch_best_bid_json(&self, auction_request: &AuctionRequest) -> Result<Bytes, Error> {
+        let auction_context = self.select_bid_to_serve(auction_request).await?;
+        let receipt_order = auction_context.receipt_order;
+        // NOTE: only serve a cached response for the bid selected above so that a
+        // superseded bid is never served
+        if let Some(cached) = self.header_cache.lock().get(auction_request) {
+            if cached.receipt_order == receipt_order {
+                counter!(HEADER_CACHE_HITS, 1);
+                return Ok(cached.body.clone())
+            }
+        }
+        counter!(HEADER_CACHE_MISSES, 1);
+
+        let signed_bid = auction_context.signed_builder_bid.clone();
+        let version = signed_bid.version();
+        let response = VersionedValue { version, data: signed_bid, meta: Default::default() };
+        let body = Bytes::from(serde_json::to_vec(&response).expect("bid is serializable"));
+        let cached = CachedHeader { receipt_order, body: body.clone() };
+        self.header_cache.lock().put(auction_request.clone(), cached);
+        Ok(body)
+    }
+
+    #[instrument(skip_all, fields(slot = signed_block.message().slot()))]
+    async fn open_bid(
+        &self,
+        signed_block: &mut SignedBlindedBeaconBlock,
+    ) -> Result<AuctionContents, Error> {
+        counter!(BLINDED_BLOCKS_RECEIVED, 1);
+        let _permit = self.acquire_payload_request_permit()?;
+        let slot = signed_block.message().slot();
+        // NOTE: the publish of the block counts against the deadline too, bounding the retries
+        // of the beacon node pool, so that the proposer can always fall back in time; a block
+        // whose publish is cut short may still have been broadcast, in which case the proposer
+        // is sent a timeout for it and any block it falls back to is an equivocation
+        let reveal = async {
+            match self.prepare_reveal(signed_block).await? {
+                PreparedReveal::Revealed(auction_contents) => Ok(auction_contents),
+                PreparedReveal::Publish(unblinded_block) => {
+                    self.publish_unblinded_block(*unblinded_block).await
+                }
+            }
+        };
+        reveal_within(self.options.payload_timeout, reveal).await.map_err(|err| {
+            if matches!(err, Error::Relay(RelayError::PayloadTimeout(..))) {
+                let timeout = self.options.payload_timeout;
+                warn!(slot, ?timeout, "could not reveal payload in time");
+            }
+            err
+        })
+    }
 }
 
 #[async_trait]
@@ -576,10 +2499,45 @@ impl BlindedBlockRelayer for Relay {
     }
 
     async fn submit_bid(&self, signed_submission: &mut SignedBidSubmission) -> Result<(), Error> {
-        let (auction_request, value, builder_public_key) = {
+        self.submit_bid_with_compliance(signed_submission, None).await
+    }
+
+    async fn submit_bid_with_compliance(
+        &self,
+        signed_submission: &mut SignedBidSubmission,
+        compliance: Option<Compliance>,
+    ) -> Result<(), Error> {
+        let result = self.accept_submission(signed_submission, compliance).await;
+        if let Some(slot) = self.clock.current_slot() {
+            let builder_public_key = &signed_submission.message.builder_public_key;
+            self.builder_stats.record(builder_public_key, slot, result.is_ok());
+        }
+        result
+    }
+}
+
+impl Relay {
+    // Validates the `signed_submission` and, if valid, queues its bid for the bid store.
+    #[instrument(
+        skip_all,
+        fields(
+            slot = signed_submission.message.slot,
+            builder_public_key = %signed_submission.message.builder_public_key,
+        )
+    )]
+    async fn accept_submission(
+        &self,
+        signed_submission: &mut SignedBidSubmission,
+        compliance: Option<Compliance>,
+    ) -> Result<(), Error> {
+        let (auction_request, bid_trace) = {
             let bid_trace = &signed_submission.message;
             let builder_public_key = &bid_trace.builder_public_key;
             self.validate_allowed_builder(builder_public_key)?;
+            self.validate_builder_reputation(builder_public_key)?;
+            self.validate_submission_cutoff(bid_trace.slot)?;
+            self.validate_submission_slot(bid_trace.slot)?;
+            self.validate_min_bid_value(bid_trace.value)?;
 
             let auction_request = AuctionRequest {
                 slot: bid_trace.slot,
@@ -588,22 +2546,1707 @@ impl BlindedBlockRelayer for Relay {
             };
             self.validate_auction_request(&auction_request)?;
 
-            self.validate_builder_submission_trusted(
+            let execution_payload = &signed_submission.execution_payload;
+            let expected = self.context.fork_for(bid_trace.slot);
+            let provided = execution_payload.version();
+            check_submission_fork(expected, provided).map_err(|err| {
+                if matches!(err, Error::Relay(RelayError::UnsupportedFork(..))) {
+                    warn!(%auction_request, "Deneb submissions are not supported; rejecting");
+                }
+                err
+            })?;
+
+            if let Err(err) = self.validate_builder_submission_trusted(
                 bid_trace,
                 &signed_submission.execution_payload,
-            )?;
+            ) {
+                if matches!(err, RelayError::InvalidFeeRecipient(..)) {
+                    self.record_builder_failure(
+                        builder_public_key,
+                        BuilderFailure::InvalidFeeRecipient,
+                    );
+                }
+                return Err(err.into())
+            }
             debug!(%auction_request, "validated builder submission");
-            (auction_request, bid_trace.value, bid_trace.builder_public_key.clone())
+            (auction_request, bid_trace.clone())
         };
 
-        signed_submission.verify_signature(&self.context)?;
+        if let Err(err) = signed_submission.verify_signature_with_domain(self.domains.builder()) {
+            let builder_public_key = &bid_trace.builder_public_key;
+            debug!(%err, %auction_request, "builder submission failed signature verification");
+            self.record_builder_failure(builder_public_key, BuilderFailure::InvalidSignature);
+            return Err(RelayError::InvalidBuilderSignature(builder_public_key.clone()).into())
+        }
+
+        // NOTE: only after verifying the signature so that a builder can not claim
+        // a block hash on behalf of another
+        self.record_block_hash_submitter(&bid_trace)?;
+
+        let mut simulation = None;
+        if let Some(block_validator) = self.block_validator.as_ref() {
+            let proposer_public_key = &bid_trace.proposer_public_key;
+            let registered_gas_limit = self
+                .validator_registry
+                .get_signed_registration(proposer_public_key)
+                .map(|signed_registration| signed_registration.message.gas_limit)
+                .ok_or_else(|| RelayError::ValidatorNotRegistered(proposer_public_key.clone()))?;
+            if self.is_optimistic(&bid_trace.builder_public_key) {
+                debug!(%auction_request, "accepting submission from optimistic builder before simulation");
+                simulation = Some(PendingSimulation {
+                    signed_submission: signed_submission.clone(),
+                    registered_gas_limit,
+                });
+            } else if let Err(err) =
+                block_validator.validate(signed_submission, registered_gas_limit).await
+            {
+                warn!(%err, %auction_request, "builder submission failed validation");
+                // NOTE: only count blocks found invalid, not failures to reach the validator
+                if matches!(err, Error::Relay(RelayError::InvalidBlock(..))) {
+                    let builder_public_key = &bid_trace.builder_public_key;
+                    self.record_builder_failure(
+                        builder_public_key,
+                        BuilderFailure::FailedSimulation,
+                    );
+                }
+                return Err(err)
+            }
+        } else if let Err(err) =
+            self.validate_withdrawals(&bid_trace, &signed_submission.execution_payload).await
+        {
+            warn!(%err, %auction_request, "builder submission has unexpected withdrawals");
+            return Err(err.into())
+        }
 
         let execution_payload = signed_submission.execution_payload.clone();
-        // NOTE: this does _not_ respect cancellations
-        // TODO: move to regime where we track best bid by builder
-        // and also move logic to cursor best bid for auction off this API
-        self.insert_bid_if_greater(auction_request, execution_payload, value, builder_public_key)?;
+        let blobs_bundle = signed_submission.blobs_bundle.clone();
+        let pending_bid = self.prepare_bid(
+            auction_request,
+            execution_payload,
+            blobs_bundle,
+            bid_trace,
+            compliance,
+            simulation,
+        )?;
+        if let Some(pending_bid) = pending_bid {
+            self.bid_queue.try_enqueue(pending_bid).map_err(|err| {
+                warn!(%err, "bid store is lagging; rejecting submission");
+                err
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+// Verifies the signature of `signed_block` by the proposer with `public_key` over the
+// beacon proposer `domain` of the fork of the block.
+fn verify_blinded_block_signature(
+    signed_block: &mut SignedBlindedBeaconBlock,
+    public_key: &BlsPublicKey,
+    domain: Domain,
+) -> Result<(), Error> {
+    let slot = signed_block.message().slot();
+    let (signing_root, signature) = match signed_block {
+        SignedBlindedBeaconBlock::Bellatrix(block) => {
+            (compute_signing_root(&mut block.message, domain)?, &block.signature)
+        }
+        SignedBlindedBeaconBlock::Capella(block) => {
+            (compute_signing_root(&mut block.message, domain)?, &block.signature)
+        }
+        SignedBlindedBeaconBlock::Deneb(block) => {
+            (compute_signing_root(&mut block.message, domain)?, &block.signature)
+        }
+    };
+    verify_signature(public_key, signing_root.as_ref(), signature).map_err(|err| {
+        warn!(%err, slot, %public_key, "invalid proposer signature on signed blinded beacon block");
+        RelayError::InvalidProposerSignature { slot, public_key: public_key.clone() }.into()
+    })
+}
+
+// Checks a submission for the `slot` starting at `slot_start_ms`, received at `now_ms`, both in
+// milliseconds since the unix epoch, arrived no later than `cutoff_ms` into the slot.
+fn check_submission_cutoff(
+    slot: Slot,
+    slot_start_ms: u64,
+    now_ms: u64,
+    cutoff_ms: u64,
+) -> Result<(), RelayError> {
+    // NOTE: submissions ahead of the start of their slot are always on time
+    let elapsed_ms = now_ms.saturating_sub(slot_start_ms);
+    if elapsed_ms > cutoff_ms {
+        return Err(RelayError::SubmissionAfterCutoff { slot, elapsed_ms, cutoff_ms })
+    }
+    Ok(())
+}
 
+// Checks a submission for the `slot` is at most `max_slots_ahead` slots after the `current_slot`.
+fn check_submission_slot(
+    slot: Slot,
+    current_slot: Slot,
+    max_slots_ahead: Slot,
+) -> Result<(), RelayError> {
+    if slot > current_slot.saturating_add(max_slots_ahead) {
+        return Err(RelayError::SubmissionTooFarAhead { slot, current_slot, max_slots_ahead })
+    }
+    Ok(())
+}
+
+// Checks a bid for the proposer with `public_key` pays its `registered` fee recipient or,
+// if any are allowed for the proposer, one of its `alternates`.
+fn check_fee_recipient(
+    public_key: &BlsPublicKey,
+    fee_recipient: &ExecutionAddress,
+    registered: &ExecutionAddress,
+    alternates: &HashMap<BlsPublicKey, HashSet<ExecutionAddress>>,
+) -> Result<(), RelayError> {
+    let is_allowed_alternate = || {
+        alternates.get(public_key).map(|allowed| allowed.contains(fee_recipient)).unwrap_or(false)
+    };
+    if fee_recipient == registered || is_allowed_alternate() {
         Ok(())
+    } else {
+        Err(RelayError::InvalidFeeRecipient(public_key.clone(), registered.clone()))
+    }
+}
+
+// Returns `true` if a bid from a builder of `builder_compliance`, or of `unknown_compliance`
+// if it declared none, may be served to a proposer with the compliance `preference`.
+fn is_eligible(
+    preference: Option<Compliance>,
+    builder_compliance: Option<Compliance>,
+    unknown_compliance: Option<Compliance>,
+) -> bool {
+    match preference {
+        Some(preference) => builder_compliance.or(unknown_compliance) == Some(preference),
+        None => true,
+    }
+}
+
+// Finds the registrations of each validator in a batch other than the one with the newest
+// timestamp, or the first of those with the newest, returning the index of each along with the
+// timestamp of the registration kept instead.
+fn find_duplicate_registrations(
+    registrations: &[SignedValidatorRegistration],
+) -> HashMap<usize, u64> {
+    let mut kept = HashMap::<&BlsPublicKey, usize>::new();
+    let mut duplicates = HashMap::new();
+    for (index, registration) in registrations.iter().enumerate() {
+        let public_key = &registration.message.public_key;
+        let Some(&kept_index) = kept.get(public_key) else {
+            kept.insert(public_key, index);
+            continue
+        };
+        if registration.message.timestamp > registrations[kept_index].message.timestamp {
+            kept.insert(public_key, index);
+        }
+    }
+    for (index, registration) in registrations.iter().enumerate() {
+        let kept_index = kept[&registration.message.public_key];
+        if kept_index != index {
+            duplicates.insert(index, registrations[kept_index].message.timestamp);
+        }
+    }
+    duplicates
+}
+
+// Computes the root of the `withdrawals` of an execution payload.
+fn withdrawals_root(withdrawals: &[Withdrawal]) -> Result<Root, RelayError> {
+    let unavailable = |err: String| {
+        RelayError::BlockValidationUnavailable(format!(
+            "could not compute withdrawals root ({err})"
+        ))
+    };
+    let mut withdrawals = List::<Withdrawal, { capella::MAX_WITHDRAWALS_PER_PAYLOAD }>::try_from(
+        withdrawals.to_vec(),
+    )
+    .map_err(|_| unavailable("too many withdrawals".into()))?;
+    withdrawals.hash_tree_root().map_err(|err| unavailable(err.to_string()))
+}
+
+// Waits for `reveal` to prepare a payload for at most `timeout`, dropping it if it runs over.
+async fn reveal_within<T>(
+    timeout: Duration,
+    reveal: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    tokio::time::timeout(timeout, reveal)
+        .await
+        .map_err(|_| RelayError::PayloadTimeout(timeout.as_millis() as u64))?
+}
+
+/// Drives the bid store of a relay without a beacon node, for the benchmarks in `benches/`
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::registration_store::InMemoryRegistrationStore;
+    use beacon_api_client::mainnet::Client;
+    use url::Url;
+
+    /// Bids ready to insert into their auction with [`BidStore::store_bids`]
+    pub struct PendingBids(Vec<PendingBid>);
+
+    /// Returns the public keys of `count` distinct builders, at most 255
+    pub fn builders(count: u8) -> Vec<BlsPublicKey> {
+        (1..=count).map(|i| SecretKey::try_from([i; 32].as_ref()).unwrap().public_key()).collect()
+    }
+
+    pub struct BidStore(Relay);
+
+    impl BidStore {
+        pub fn new(expected_builders: Option<usize>) -> Self {
+            let beacon_node = Client::new(Url::parse("http://127.0.0.1:1").unwrap());
+            let options = RelayOptions { expected_builders, ..Default::default() };
+            let components = RelayComponents {
+                registration_store: Box::<InMemoryRegistrationStore>::default(),
+                registration_pool: RegistrationPool::new(1, 1, false),
+                block_validator: None,
+                upstream_relays: None,
+            };
+            let secret_key = SecretKey::try_from([1u8; 32].as_ref()).unwrap();
+            let context = Context::for_mainnet();
+            let clock = Arc::new(context.clock_at(0));
+            let relay = Relay::new(
+                beacon_node.into(),
+                secret_key,
+                Policy::default(),
+                options,
+                components,
+                context,
+                clock,
+            )
+            .unwrap();
+            Self(relay)
+        }
+
+        pub fn prewarm_auctions(&self, epoch: Epoch) {
+            self.0.prewarm_auctions(epoch)
+        }
+
+        /// Returns a bid from each of the `builders` for the auction at `slot`
+        pub fn bids(&self, slot: Slot, builders: &[BlsPublicKey]) -> PendingBids {
+            let auction_request = AuctionRequest { slot, ..Default::default() };
+            let bids = builders
+                .iter()
+                .enumerate()
+                .map(|(i, builder)| {
+                    let value = U256::from(i + 1);
+                    let builder_bid = builder_bid::capella::BuilderBid {
+                        header: ExecutionPayloadHeader::Capella(Default::default()),
+                        value,
+                        public_key: builder.clone(),
+                    };
+                    PendingBid {
+                        auction_request: auction_request.clone(),
+                        bid_trace: BidTrace {
+                            slot,
+                            builder_public_key: builder.clone(),
+                            value,
+                            ..Default::default()
+                        },
+                        signed_builder_bid: SignedBuilderBid {
+                            message: BuilderBid::Capella(builder_bid),
+                            signature: Default::default(),
+                        },
+                        payload: BidPayload::Local {
+                            execution_payload: ExecutionPayload::Capella(Default::default()),
+                        },
+                        compliance: None,
+                        simulation: None,
+                    }
+                })
+                .collect();
+            PendingBids(bids)
+        }
+
+        pub fn store_bids(&self, bids: PendingBids) {
+            self.0.store_bids(bids.0)
+        }
+    }
+}
+
+// NOTE: the blobs of a Deneb block can not be published alongside it yet, so Deneb payloads are
+// rejected rather than served in bids whose payloads could never be revealed
+fn check_submission_fork(expected: Fork, provided: Fork) -> Result<(), Error> {
+    if expected != provided {
+        return Err(Error::InvalidFork { expected, provided })
+    }
+    if provided == Fork::Deneb {
+        return Err(RelayError::UnsupportedFork(Fork::Deneb).into())
+    }
+    Ok(())
+}
+
+// NOTE: fails closed if the proposer duties for `slot` are not known
+fn check_assigned_proposer(
+    slot: Slot,
+    assigned_proposer: Option<BlsPublicKey>,
+    public_key: &BlsPublicKey,
+) -> Result<(), RelayError> {
+    match assigned_proposer {
+        Some(expected) if &expected == public_key => Ok(()),
+        Some(expected) => {
+            Err(RelayError::UnassignedProposer { slot, expected, provided: public_key.clone() })
+        }
+        None => Err(RelayError::MissingProposerDuties(slot)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_beacon_node::spawn_mock_beacon_node,
+        registration_store::InMemoryRegistrationStore,
+        slot_loop::{run_slot_loop, MockClock, SlotLoopExit, SlotLoopStats},
+    };
+    use beacon_api_client::mainnet::Client;
+    use ethereum_consensus::{
+        domains::DomainType, phase0::mainnet::compute_domain, signing::sign_with_domain,
+    };
+    use mev_rs::RelayEndpoint;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use url::Url;
+
+    // NOTE: the beacon node is never reachable, so any calls to it fail immediately
+    fn new_relay(received_bid_retention_slots: Slot) -> Relay {
+        new_relay_with_warmup(received_bid_retention_slots, 0)
+    }
+
+    fn new_relay_with_warmup(received_bid_retention_slots: Slot, warmup_slots: Slot) -> Relay {
+        let clock = Arc::new(Context::for_mainnet().clock_at(0));
+        new_relay_with_clock(received_bid_retention_slots, warmup_slots, clock)
+    }
+
+    fn new_relay_with_clock(
+        received_bid_retention_slots: Slot,
+        warmup_slots: Slot,
+        clock: Arc<dyn SlotClock>,
+    ) -> Relay {
+        let beacon_node = Client::new(Url::parse("http://127.0.0.1:1").unwrap());
+        new_relay_with_beacon_node(
+            beacon_node,
+            received_bid_retention_slots,
+            warmup_slots,
+            None,
+            clock,
+        )
+    }
+
+    fn new_relay_with_beacon_node(
+        beacon_node: Client,
+        received_bid_retention_slots: Slot,
+        warmup_slots: Slot,
+        proposer_duties_prefetch_slots: Option<Slot>,
+        clock: Arc<dyn SlotClock>,
+    ) -> Relay {
+        let options = RelayOptions {
+            delivered_payload_retention_slots: 0,
+            received_bid_retention_slots,
+            warmup_slots,
+            proposer_duties_prefetch_slots,
+            header_cache_size: 1,
+            max_payload_requests: 1,
+            ..Default::default()
+        };
+        new_relay_with_options(beacon_node, options, clock)
+    }
+
+    fn new_relay_with_options(
+        beacon_node: Client,
+        options: RelayOptions,
+        clock: Arc<dyn SlotClock>,
+    ) -> Relay {
+        let secret_key = SecretKey::try_from([1u8; 32].as_ref()).unwrap();
+        let components = RelayComponents {
+            registration_store: Box::<InMemoryRegistrationStore>::default(),
+            registration_pool: RegistrationPool::new(1, 1, false),
+            block_validator: None,
+            upstream_relays: None,
+        };
+        let context = Context::for_mainnet();
+        Relay::new(
+            beacon_node.into(),
+            secret_key,
+            Policy::default(),
+            options,
+            components,
+            context,
+            clock,
+        )
+        .unwrap()
+    }
+
+    async fn run_slots(relay: &Relay, clock: MockClock) -> SlotLoopStats {
+        let shutdown = CancellationToken::new();
+        let (exit, stats) = run_slot_loop(relay, &clock, Duration::from_secs(12), &shutdown).await;
+        // NOTE: the slot stream of the mock clock ends after its last slot
+        assert_eq!(exit, SlotLoopExit::StreamEnded);
+        stats
+    }
+
+    fn received_slots(relay: &Relay) -> Vec<Slot> {
+        let page = relay.get_received_bids_page(&Default::default(), None, usize::MAX);
+        page.entries.iter().map(|bid| bid.slot).collect()
+    }
+
+    #[tokio::test]
+    async fn test_slot_loop_prunes_received_bids() {
+        let relay = new_relay(2);
+        for slot in 30..34 {
+            relay.state.lock().received_bids.push((slot, BidTrace { slot, ..Default::default() }));
+        }
+
+        let slots_per_epoch = relay.context.slots_per_epoch;
+        run_slots(&relay, MockClock::new(slots_per_epoch, 30, vec![31, 32, 33])).await;
+        let slots = received_slots(&relay);
+        assert_eq!(slots, vec![33, 32, 31]);
+
+        // records for slots skipped over by the clock are also pruned
+        run_slots(&relay, MockClock::new(slots_per_epoch, 33, vec![35])).await;
+        let slots = received_slots(&relay);
+        assert_eq!(slots, vec![33]);
+    }
+
+    #[tokio::test]
+    async fn test_slot_loop_resets_epoch_state_at_epoch_boundary() {
+        let relay = new_relay(0);
+        let slots_per_epoch = relay.context.slots_per_epoch;
+        let builder = SecretKey::try_from([2u8; 32].as_ref()).unwrap().public_key();
+
+        // mark the builder as seen before each slot is processed
+        let mock_clock = |current_slot, slots| {
+            let relay = relay.clone();
+            let builder = builder.clone();
+            MockClock::new(slots_per_epoch, current_slot, slots).on_yield(move |_| {
+                relay.state.lock().disallowed_builders_seen.insert(builder.clone());
+            })
+        };
+        let seen = |relay: &Relay| relay.state.lock().disallowed_builders_seen.contains(&builder);
+
+        let first_slot = 2 * slots_per_epoch;
+        let last_slot = 3 * slots_per_epoch - 1;
+        run_slots(&relay, mock_clock(first_slot, (first_slot + 1..=last_slot).collect())).await;
+        assert!(seen(&relay));
+
+        run_slots(&relay, mock_clock(first_slot, vec![last_slot, last_slot + 1])).await;
+        assert!(!seen(&relay));
+
+        // the loop starts in the epoch of the current slot
+        run_slots(&relay, mock_clock(last_slot + 1, vec![last_slot + 1, last_slot + 2])).await;
+        assert!(seen(&relay));
+
+        // skipping whole epochs still crosses an epoch boundary
+        run_slots(&relay, mock_clock(last_slot, vec![last_slot + 2 * slots_per_epoch])).await;
+        assert!(!seen(&relay));
+    }
+
+    #[tokio::test]
+    async fn test_slot_loop_skips_slots_passed_while_suspended() {
+        let relay = new_relay(2);
+        let slots_per_epoch = relay.context.slots_per_epoch;
+        let resumed_at = 10 + 4 * slots_per_epoch;
+        for slot in [10, resumed_at - 1] {
+            relay.state.lock().received_bids.push((slot, BidTrace { slot, ..Default::default() }));
+        }
+
+        // the slot stream catches up with a burst of every slot passed while suspended
+        let clock =
+            MockClock::new(slots_per_epoch, 10, (11..=resumed_at).collect()).resumed_at(resumed_at);
+        let stats = run_slots(&relay, clock).await;
+        let expected = SlotLoopStats {
+            slots_processed: 1,
+            slots_skipped: resumed_at - 11,
+            epochs_processed: 2,
+        };
+        assert_eq!(stats, expected);
+        let slots = received_slots(&relay);
+        assert_eq!(slots, vec![resumed_at - 1]);
+
+        // without a burst, each slot is processed
+        let stats = run_slots(&relay, MockClock::new(slots_per_epoch, 10, vec![11, 12])).await;
+        assert_eq!(stats.slots_processed, 2);
+        assert_eq!(stats.slots_skipped, 0);
+    }
+
+    // Serves the proposer duties of each epoch from a mock beacon node, naming as the proposer of
+    // the first slot of the epoch a validator derived from the slot the duties are requested at.
+    // Returns the URL of the mock and the slot it considers current, along with the slot and
+    // epoch of each request for duties, failing those requested at the `failing_slot`.
+    fn spawn_duties_server(
+        current_slot: Slot,
+        failing_slot: Slot,
+    ) -> (Url, Arc<AtomicU64>, Arc<Mutex<Vec<(Slot, Epoch)>>>) {
+        use axum::{extract::Path, http::StatusCode, routing::get, Json, Router};
+
+        let slots_per_epoch = Context::for_mainnet().slots_per_epoch;
+        let current_slot = Arc::new(AtomicU64::new(current_slot));
+        let requests = Arc::new(Mutex::new(vec![]));
+        let handle_duties = {
+            let current_slot = current_slot.clone();
+            let requests = requests.clone();
+            move |Path(epoch): Path<Epoch>| {
+                let slot = current_slot.load(Ordering::SeqCst);
+                requests.lock().push((slot, epoch));
+                async move {
+                    if slot == failing_slot {
+                        let error = serde_json::json!({ "code": 500, "message": "unavailable" });
+                        return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error)))
+                    }
+                    let proposer = SecretKey::try_from([slot as u8; 32].as_ref()).unwrap();
+                    Ok(Json(serde_json::json!({
+                        "dependent_root": Root::default(),
+                        "execution_optimistic": false,
+                        "data": [{
+                            "pubkey": proposer.public_key(),
+                            "validator_index": "1",
+                            "slot": (epoch * slots_per_epoch).to_string(),
+                        }],
+                    })))
+                }
+            }
+        };
+        let router =
+            Router::new().route("/eth/v1/validator/duties/proposer/:epoch", get(handle_duties));
+        let url = spawn_mock_beacon_node(router);
+        (url, current_slot, requests)
+    }
+
+    #[tokio::test]
+    async fn test_proposer_duties_prefetched_before_epoch_boundary() {
+        let slots_per_epoch = Context::for_mainnet().slots_per_epoch;
+        let boundary = 3 * slots_per_epoch;
+        // the first attempt to prefetch the duties of the next epoch fails
+        let (url, current_slot, requests) = spawn_duties_server(boundary - 4, boundary - 2);
+        let clock = Arc::new(MockClock::new(slots_per_epoch, boundary - 4, vec![]));
+        let relay = new_relay_with_beacon_node(Client::new(url), 0, 0, Some(2), clock);
+
+        let proposer = |slot: Slot| SecretKey::try_from([slot as u8; 32].as_ref()).unwrap();
+        let proposer_at_boundary = Arc::new(Mutex::new(None));
+        let mock_clock = {
+            let relay = relay.clone();
+            let proposer_at_boundary = proposer_at_boundary.clone();
+            let slots = (boundary - 3..=boundary + 1).collect();
+            MockClock::new(slots_per_epoch, boundary - 4, slots).on_yield(move |slot| {
+                current_slot.store(slot, Ordering::SeqCst);
+                if slot == boundary {
+                    let public_key = relay.proposer_scheduler.get_proposer_public_key(boundary);
+                    *proposer_at_boundary.lock() = public_key;
+                }
+            })
+        };
+        run_slots(&relay, mock_clock).await;
+
+        // the duties of the next epoch are fetched ahead of the boundary, retrying after a
+        // failure, rather than at the boundary, where only those of the epoch after are fetched
+        let expected = vec![
+            (boundary - 4, 2),
+            (boundary - 4, 3),
+            (boundary - 2, 3),
+            (boundary - 1, 3),
+            (boundary, 4),
+        ];
+        assert_eq!(*requests.lock(), expected);
+        // the prefetched duties only replace the earlier duties once the epoch starts
+        let earlier_proposer = proposer(boundary - 4).public_key();
+        assert_eq!(*proposer_at_boundary.lock(), Some(earlier_proposer));
+        let prefetched_proposer = proposer(boundary - 1).public_key();
+        let public_key = relay.proposer_scheduler.get_proposer_public_key(boundary);
+        assert_eq!(public_key, Some(prefetched_proposer));
+    }
+
+    // Serves a Capella block on the execution block with `block_hash` as the head of a mock
+    // beacon node, returning the URL of the mock with the number of requests for the head.
+    fn spawn_head_server(block_hash: Hash32) -> (Url, Arc<AtomicU64>) {
+        use axum::{routing::get, Json, Router};
+
+        let requests = Arc::new(AtomicU64::new(0));
+        let handle_head = {
+            let requests = requests.clone();
+            move || {
+                requests.fetch_add(1, Ordering::SeqCst);
+                let mut signed_block = capella::SignedBeaconBlock::default();
+                signed_block.message.body.execution_payload.block_hash = block_hash.clone();
+                let response = serde_json::json!({ "version": "capella", "data": signed_block });
+                async move { Json(response) }
+            }
+        };
+        let router = Router::new().route("/eth/v2/beacon/blocks/head", get(handle_head));
+        let url = spawn_mock_beacon_node(router);
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_validate_parent_hash_against_head() {
+        let head = Hash32::try_from([1u8; 32].as_ref()).unwrap();
+        let (url, requests) = spawn_head_server(head.clone());
+        let clock = Arc::new(Context::for_mainnet().clock_at(0));
+        let relay = new_relay_with_beacon_node(Client::new(url), 0, 0, None, clock);
+        let on_head = AuctionRequest { slot: 10, parent_hash: head, ..Default::default() };
+        let off_head = AuctionRequest {
+            parent_hash: Hash32::try_from([2u8; 32].as_ref()).unwrap(),
+            ..on_head.clone()
+        };
+
+        // the head is not looked up unless the policy asks for it
+        relay.validate_parent_hash(&off_head).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+
+        for check in [ParentHashCheck::Warn, ParentHashCheck::Reject] {
+            relay.set_policy(Policy { parent_hash_check: Some(check), ..Default::default() });
+            relay.validate_parent_hash(&on_head).await.unwrap();
+            let result = relay.validate_parent_hash(&off_head).await;
+            match check {
+                ParentHashCheck::Warn => assert!(result.is_ok()),
+                ParentHashCheck::Reject => {
+                    assert!(
+                        matches!(result, Err(Error::NoBidPrepared(request)) if request == off_head)
+                    )
+                }
+            }
+        }
+        // the head is looked up once for each slot
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        let next_slot = AuctionRequest { slot: 11, ..on_head };
+        relay.validate_parent_hash(&next_slot).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    // Serves the block with the execution block hash `published` at any slot, or no block if
+    // `None`, counting requests for blocks.
+    fn spawn_blocks_server(published: Arc<Mutex<Option<Hash32>>>) -> (Url, Arc<AtomicU64>) {
+        use axum::{routing::get, Json, Router};
+
+        let requests = Arc::new(AtomicU64::new(0));
+        let handle_block = {
+            let requests = requests.clone();
+            move || {
+                requests.fetch_add(1, Ordering::SeqCst);
+                let published = published.lock().clone();
+                async move {
+                    let Some(block_hash) = published else {
+                        let error = serde_json::json!({ "code": 404, "message": "not found" });
+                        return Err((StatusCode::NOT_FOUND, Json(error)))
+                    };
+                    let mut signed_block = capella::SignedBeaconBlock::default();
+                    signed_block.message.body.execution_payload.block_hash = block_hash;
+                    Ok(Json(serde_json::json!({ "version": "capella", "data": signed_block })))
+                }
+            }
+        };
+        let router = Router::new().route("/eth/v2/beacon/blocks/:block_id", get(handle_block));
+        let url = spawn_mock_beacon_node(router);
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_check_delivered_payloads_onchain() {
+        let block_hash = Hash32::try_from([1u8; 32].as_ref()).unwrap();
+        let published = Arc::new(Mutex::new(Some(block_hash.clone())));
+        let (url, requests) = spawn_blocks_server(published.clone());
+        let clock = Arc::new(Context::for_mainnet().clock_at(0));
+        let relay = new_relay_with_beacon_node(Client::new(url), 0, 0, None, clock);
+        let delivered = |slot, block_hash| DeliveredPayload {
+            bid_trace: BidTrace { slot, block_hash, ..Default::default() },
+            selection_reason: SelectionReason::HighestValue,
+            upstream_relay: None,
+            delivered_onchain: None,
+            rechecked: false,
+        };
+        let other_hash = Hash32::try_from([2u8; 32].as_ref()).unwrap();
+        relay
+            .state
+            .lock()
+            .delivered_payloads
+            .extend([(1, delivered(10, block_hash.clone())), (2, delivered(11, other_hash))]);
+        let onchain = || {
+            let page = relay.get_delivered_payloads_page(&Default::default(), None, usize::MAX);
+            page.entries.iter().map(|delivered| delivered.delivered_onchain).collect::<Vec<_>>()
+        };
+
+        // not checked until `delay` slots have passed
+        relay.check_delivered_payloads(11, 2).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+        relay.check_delivered_payloads(12, 2).await;
+        assert_eq!(onchain(), vec![None, Some(true)]);
+        // another block was published in the slot of the second payload
+        relay.check_delivered_payloads(13, 2).await;
+        assert_eq!(onchain(), vec![Some(false), Some(true)]);
+        // checked payloads are not checked again until an epoch later
+        relay.check_delivered_payloads(14, 2).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // the block of the first payload was reorged out, leaving its slot empty
+        *published.lock() = None;
+        relay.check_delivered_payloads(10 + 2 + 32, 2).await;
+        assert_eq!(onchain(), vec![Some(false), Some(false)]);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        // the recheck of the second payload is due from its slot on, even if that slot is skipped
+        relay.check_delivered_payloads(11 + 2 + 32 + 1, 2).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 4);
+        // each payload is only rechecked once
+        relay.check_delivered_payloads(11 + 2 + 32 + 2, 2).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_delivered_payload_checks_never_overlap() {
+        let (url, requests) = spawn_blocks_server(Default::default());
+        let clock = Arc::new(Context::for_mainnet().clock_at(0));
+        let relay = new_relay_with_beacon_node(Client::new(url), 0, 0, None, clock);
+        relay.checking_delivered_payloads.store(true, Ordering::SeqCst);
+        assert!(!relay.spawn_delivered_payload_check(12, 2));
+
+        relay.checking_delivered_payloads.store(false, Ordering::SeqCst);
+        assert!(relay.spawn_delivered_payload_check(12, 2));
+        assert!(!relay.spawn_delivered_payload_check(13, 2));
+        for _ in 0..100 {
+            if !relay.checking_delivered_payloads.load(Ordering::SeqCst) {
+                break
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(relay.spawn_delivered_payload_check(13, 2));
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_validate_parent_hash_without_head() {
+        let relay = new_relay(0);
+        relay.set_policy(Policy {
+            parent_hash_check: Some(ParentHashCheck::Reject),
+            ..Default::default()
+        });
+        // bids are not withheld if the beacon node is unreachable
+        relay.validate_parent_hash(&AuctionRequest::default()).await.unwrap();
+    }
+
+    // Serves a Capella block on the execution block with `block_hash` as the head of a mock
+    // beacon node, with the `withdrawals` expected on its state, returning the URL of the mock
+    // with the states for which the withdrawals were requested.
+    fn spawn_withdrawals_server(
+        block_hash: Hash32,
+        state_root: Root,
+        withdrawals: Vec<Withdrawal>,
+    ) -> (Url, Arc<Mutex<Vec<String>>>) {
+        use axum::{extract::Path, routing::get, Json, Router};
+
+        let handle_head = move || {
+            let mut signed_block = capella::SignedBeaconBlock::default();
+            signed_block.message.state_root = state_root;
+            signed_block.message.body.execution_payload.block_hash = block_hash.clone();
+            let response = serde_json::json!({ "version": "capella", "data": signed_block });
+            async move { Json(response) }
+        };
+        let requests = Arc::new(Mutex::new(vec![]));
+        let handle_withdrawals = {
+            let requests = requests.clone();
+            move |Path(state_id): Path<String>| {
+                requests.lock().push(state_id);
+                let response =
+                    serde_json::json!({ "execution_optimistic": false, "data": withdrawals });
+                async move { Json(response) }
+            }
+        };
+        let router = Router::new().route("/eth/v2/beacon/blocks/head", get(handle_head)).route(
+            "/eth/v1/builder/states/:state_id/expected_withdrawals",
+            get(handle_withdrawals),
+        );
+        let url = spawn_mock_beacon_node(router);
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_validate_withdrawals_against_expected() {
+        let withdrawal = |index| Withdrawal {
+            index,
+            validator_index: 42,
+            address: ExecutionAddress::try_from([0xab; 20].as_ref()).unwrap(),
+            amount: 32_000_000_000,
+        };
+        let expected = vec![withdrawal(7), withdrawal(8)];
+        let head = Hash32::try_from([1u8; 32].as_ref()).unwrap();
+        let state_root = Root::try_from([2u8; 32].as_ref()).unwrap();
+        let (url, requests) = spawn_withdrawals_server(head.clone(), state_root, expected.clone());
+        let clock = Arc::new(Context::for_mainnet().clock_at(0));
+        let relay = new_relay_with_beacon_node(Client::new(url), 0, 0, None, clock);
+
+        let context = Context::for_mainnet();
+        let bid_trace = BidTrace {
+            slot: context.capella_fork_epoch * context.slots_per_epoch,
+            parent_hash: head,
+            ..Default::default()
+        };
+        let payload_with = |withdrawals: Vec<Withdrawal>| {
+            ExecutionPayload::Capella(capella::ExecutionPayload {
+                withdrawals: List::try_from(withdrawals).unwrap(),
+                ..Default::default()
+            })
+        };
+        relay.validate_withdrawals(&bid_trace, &payload_with(expected.clone())).await.unwrap();
+
+        let result =
+            relay.validate_withdrawals(&bid_trace, &payload_with(vec![withdrawal(7)])).await;
+        assert!(matches!(result, Err(RelayError::InvalidWithdrawalsRoot { .. })));
+        // the expected withdrawals are looked up once for each slot and parent, on the state of
+        // the head block
+        assert_eq!(*requests.lock(), vec![StateId::Root(state_root).to_string()]);
+
+        // submissions on a parent other than the head are rejected
+        let other_parent = Hash32::try_from([3u8; 32].as_ref()).unwrap();
+        let other_bid_trace = BidTrace { parent_hash: other_parent, ..bid_trace.clone() };
+        let result =
+            relay.validate_withdrawals(&other_bid_trace, &payload_with(expected.clone())).await;
+        assert!(matches!(result, Err(RelayError::ParentNotHead(..))));
+        assert_eq!(requests.lock().len(), 1);
+
+        // blocks before Capella are not checked
+        let bid_trace = BidTrace { slot: 0, ..bid_trace };
+        relay.validate_withdrawals(&bid_trace, &payload_with(vec![])).await.unwrap();
+        assert_eq!(requests.lock().len(), 1);
+    }
+
+    #[test]
+    fn test_find_duplicate_registrations() {
+        let registration = |key: u8, timestamp| {
+            let mut registration = SignedValidatorRegistration::default();
+            registration.message.public_key =
+                SecretKey::try_from([key; 32].as_ref()).unwrap().public_key();
+            registration.message.timestamp = timestamp;
+            registration
+        };
+        // the newest registration of a validator is kept wherever it is in the batch, or the
+        // first of those with the same timestamp
+        let registrations = vec![
+            registration(2, 10),
+            registration(3, 10),
+            registration(2, 20),
+            registration(3, 10),
+            registration(2, 15),
+        ];
+        let duplicates = find_duplicate_registrations(&registrations);
+        assert_eq!(duplicates, HashMap::from([(0, 20), (3, 10), (4, 20)]));
+
+        // the same registrations are kept in any order
+        let mut reversed = registrations;
+        reversed.reverse();
+        let duplicates = find_duplicate_registrations(&reversed);
+        assert_eq!(duplicates, HashMap::from([(0, 20), (3, 10), (4, 20)]));
+    }
+
+    #[test]
+    fn test_allocate_auctions_for_epoch() {
+        let mut state = State::default();
+        state.allocate_auctions(32, 32, 8);
+        assert_eq!(state.spare_auctions.len(), 32);
+        assert!(state.spare_auctions.iter().all(|auction| auction.bids.capacity() >= 8));
+        let mut slots = state.block_hash_submitters.keys().copied().collect::<Vec<_>>();
+        slots.sort_unstable();
+        assert_eq!(slots, (32..64).collect::<Vec<_>>());
+        assert!(state.block_hash_submitters.values().all(|hashes| hashes.capacity() >= 8));
+
+        // only the auctions used in the previous epoch are allocated again
+        state.spare_auctions.truncate(30);
+        state.allocate_auctions(64, 32, 8);
+        assert_eq!(state.spare_auctions.len(), 32);
+    }
+
+    #[test]
+    fn test_prewarm_auctions_with_expected_builders() {
+        let clock = Arc::new(Context::for_mainnet().clock_at(0));
+        let beacon_node = Client::new(Url::parse("http://127.0.0.1:1").unwrap());
+        let relay = new_relay_with_options(beacon_node, Default::default(), clock.clone());
+        relay.prewarm_auctions(1);
+        assert!(relay.state.lock().spare_auctions.is_empty());
+
+        let options = RelayOptions { expected_builders: Some(8), ..Default::default() };
+        let beacon_node = Client::new(Url::parse("http://127.0.0.1:1").unwrap());
+        let relay = new_relay_with_options(beacon_node, options, clock);
+        relay.prewarm_auctions(1);
+        let state = relay.state.lock();
+        assert_eq!(state.spare_auctions.len(), 32);
+        assert!(state.block_hash_submitters.contains_key(&32));
+        assert!(state.block_hash_submitters.contains_key(&63));
+    }
+
+    #[tokio::test]
+    async fn test_reload_registrations_from_store() {
+        let relay = new_relay(0);
+        let public_keys = (2..=3u8)
+            .map(|i| SecretKey::try_from([i; 32].as_ref()).unwrap().public_key())
+            .collect::<Vec<_>>();
+        let registration = |public_key: &BlsPublicKey| {
+            let mut registration = SignedValidatorRegistration::default();
+            registration.message.public_key = public_key.clone();
+            registration
+        };
+        relay.validator_registry.restore_registrations(vec![registration(&public_keys[0])]);
+
+        // the store is updated by another process, replacing the only registration
+        relay.registration_store.remove_all(vec![public_keys[0].clone()]).await.unwrap();
+        relay.registration_store.put(registration(&public_keys[1])).await.unwrap();
+        assert_eq!(relay.reload_registrations().await.unwrap(), 1);
+        assert!(relay.validator_registry.get_signed_registration(&public_keys[0]).is_none());
+        assert!(relay.validator_registry.get_signed_registration(&public_keys[1]).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_warmup_ends_after_warmup_slots() {
+        let relay = new_relay_with_warmup(2, 0);
+        relay.start_warmup(10);
+        assert!(relay.warmup_end_slot().is_none());
+
+        let relay = new_relay_with_warmup(2, 2);
+        relay.start_warmup(10);
+        assert_eq!(relay.warmup_end_slot(), Some(12));
+
+        // NOTE: the beacon node is unreachable, so proposer duties never load to end it early
+        let slots_per_epoch = relay.context.slots_per_epoch;
+        run_slots(&relay, MockClock::new(slots_per_epoch, 10, vec![11])).await;
+        assert_eq!(relay.warmup_end_slot(), Some(12));
+        run_slots(&relay, MockClock::new(slots_per_epoch, 11, vec![12])).await;
+        assert!(relay.warmup_end_slot().is_none());
+    }
+
+    fn sign_blinded_block(
+        signed_block: &mut SignedBlindedBeaconBlock,
+        secret_key: &SecretKey,
+        domain: Domain,
+    ) {
+        match signed_block {
+            SignedBlindedBeaconBlock::Capella(block) => {
+                block.signature = sign_with_domain(&mut block.message, secret_key, domain).unwrap();
+            }
+            SignedBlindedBeaconBlock::Deneb(block) => {
+                block.signature = sign_with_domain(&mut block.message, secret_key, domain).unwrap();
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    #[test]
+    fn test_verify_blinded_block_signature() {
+        let proposer = SecretKey::try_from([1u8; 32].as_ref()).unwrap();
+        let public_key = proposer.public_key();
+        let other = SecretKey::try_from([2u8; 32].as_ref()).unwrap().public_key();
+        let context = Context::for_mainnet();
+        let genesis_validators_root = Root::try_from([3u8; 32].as_ref()).unwrap();
+        let proposer_domain = |fork_version, genesis_validators_root| {
+            compute_domain(
+                DomainType::BeaconProposer,
+                Some(fork_version),
+                Some(genesis_validators_root),
+                &context,
+            )
+            .unwrap()
+        };
+
+        let capella_block = SignedBlindedBeaconBlock::Capella(capella::SignedBlindedBeaconBlock {
+            message: capella::BlindedBeaconBlock { slot: 10, ..Default::default() },
+            ..Default::default()
+        });
+        let deneb_block = SignedBlindedBeaconBlock::Deneb(deneb::SignedBlindedBeaconBlock {
+            message: deneb::BlindedBeaconBlock { slot: 11, ..Default::default() },
+            ..Default::default()
+        });
+        let blocks = [
+            (context.capella_fork_version, capella_block),
+            (context.deneb_fork_version, deneb_block),
+        ];
+
+        for (fork_version, mut signed_block) in blocks {
+            let slot = signed_block.message().slot();
+            let domain = proposer_domain(fork_version, genesis_validators_root);
+            sign_blinded_block(&mut signed_block, &proposer, domain);
+            verify_blinded_block_signature(&mut signed_block, &public_key, domain)
+                .expect("is valid signature");
+
+            // by a key other than the proposer's
+            let err =
+                verify_blinded_block_signature(&mut signed_block, &other, domain).unwrap_err();
+            assert!(matches!(
+                err,
+                Error::Relay(RelayError::InvalidProposerSignature { slot: err_slot, public_key })
+                    if err_slot == slot && public_key == other
+            ));
+
+            // over the proposer domain of another chain
+            let other_root = Root::try_from([4u8; 32].as_ref()).unwrap();
+            let other_domain = proposer_domain(fork_version, other_root);
+            let result =
+                verify_blinded_block_signature(&mut signed_block, &public_key, other_domain);
+            assert!(result.is_err());
+
+            // with a body changed after signing
+            match &mut signed_block {
+                SignedBlindedBeaconBlock::Capella(block) => {
+                    block.message.body.execution_payload_header.gas_limit += 1
+                }
+                SignedBlindedBeaconBlock::Deneb(block) => {
+                    block.message.body.execution_payload_header.gas_limit += 1
+                }
+                _ => unreachable!(),
+            }
+            let err =
+                verify_blinded_block_signature(&mut signed_block, &public_key, domain).unwrap_err();
+            assert!(matches!(err, Error::Relay(RelayError::InvalidProposerSignature { .. })));
+        }
+    }
+
+    #[test]
+    fn test_builder_reputation_resets_with_policy() {
+        let relay = new_relay(2);
+        let builder = SecretKey::try_from([2u8; 32].as_ref()).unwrap().public_key();
+        // failures are not counted without a reputation policy
+        relay.record_builder_failure(&builder, BuilderFailure::InvalidSignature);
+        assert!(relay.validate_builder_reputation(&builder).is_ok());
+
+        let builder_reputation =
+            Some(ReputationPolicy { window: Duration::from_secs(60), failure_threshold: 2 });
+        let policy = Policy { builder_reputation, ..Default::default() };
+        relay.set_policy(policy.clone());
+        relay.record_builder_failure(&builder, BuilderFailure::InvalidSignature);
+        assert!(relay.validate_builder_reputation(&builder).is_ok());
+        relay.record_builder_failure(&builder, BuilderFailure::FailedSimulation);
+        let err = relay.validate_builder_reputation(&builder).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Relay(RelayError::BuilderBanned { public_key, retry_after_secs: 60 })
+                if public_key == builder
+        ));
+
+        // reloading the policy reinstates the builder
+        relay.set_policy(policy);
+        assert!(relay.validate_builder_reputation(&builder).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_submissions_counted_per_builder() {
+        let relay = new_relay(2);
+        let allowed_builders = Some(HashSet::new());
+        relay.set_policy(Policy { allowed_builders, ..Default::default() });
+        let builder = SecretKey::try_from([2u8; 32].as_ref()).unwrap().public_key();
+        let mut signed_submission = SignedBidSubmission {
+            message: BidTrace { builder_public_key: builder.clone(), ..Default::default() },
+            execution_payload: ExecutionPayload::Bellatrix(Default::default()),
+            blobs_bundle: None,
+            signature: Default::default(),
+        };
+        for _ in 0..2 {
+            assert!(relay.submit_bid(&mut signed_submission).await.is_err());
+        }
+
+        let activity = relay.get_builder_activity();
+        assert_eq!(activity.len(), 1);
+        assert_eq!(activity[0].builder_public_key, builder);
+        assert_eq!(
+            (activity[0].submissions, activity[0].accepted, activity[0].rejected),
+            (2, 0, 2)
+        );
+        assert_eq!(Some(activity[0].last_seen_slot), relay.clock.current_slot());
+    }
+
+    #[test]
+    fn test_check_submission_fork() {
+        assert!(check_submission_fork(Fork::Capella, Fork::Capella).is_ok());
+        assert!(matches!(
+            check_submission_fork(Fork::Capella, Fork::Bellatrix),
+            Err(Error::InvalidFork { expected: Fork::Capella, provided: Fork::Bellatrix })
+        ));
+        // bids are never served for Deneb payloads, which could not be revealed
+        assert!(matches!(
+            check_submission_fork(Fork::Deneb, Fork::Deneb),
+            Err(Error::Relay(RelayError::UnsupportedFork(Fork::Deneb)))
+        ));
+    }
+
+    #[test]
+    fn test_check_assigned_proposer() {
+        let proposer = SecretKey::try_from([1u8; 32].as_ref()).unwrap().public_key();
+        let other = SecretKey::try_from([2u8; 32].as_ref()).unwrap().public_key();
+
+        assert!(check_assigned_proposer(10, Some(proposer.clone()), &proposer).is_ok());
+
+        let err = check_assigned_proposer(10, Some(proposer.clone()), &other).unwrap_err();
+        assert!(matches!(
+            err,
+            RelayError::UnassignedProposer { slot: 10, expected, provided }
+                if expected == proposer && provided == other
+        ));
+
+        let err = check_assigned_proposer(10, None, &proposer).unwrap_err();
+        assert!(matches!(err, RelayError::MissingProposerDuties(10)));
+    }
+
+    #[test]
+    fn test_slot_window_follows_provided_clock() {
+        let clock = MockClock::new(32, 100, vec![]).resumed_at(100);
+        let relay = new_relay_with_clock(0, 0, Arc::new(clock));
+        assert!(relay.validate_slot_in_window(100).is_ok());
+        assert!(relay.validate_slot_in_window(101).is_ok());
+        let result = relay.validate_slot_in_window(102);
+        assert!(matches!(
+            result,
+            Err(Error::Relay(RelayError::SlotOutsideWindow { slot: 102, current_slot: 100 }))
+        ));
+
+        // a clock that has not started yet does not report a current slot
+        let relay = new_relay_with_clock(0, 0, Arc::new(MockClock::new(32, 0, vec![])));
+        assert!(matches!(relay.validate_slot_in_window(100), Err(Error::NotReady)));
+    }
+
+    #[test]
+    fn test_submissions_too_far_ahead_rejected() {
+        let clock = MockClock::new(32, 100, vec![]).resumed_at(100);
+        let relay = new_relay_with_clock(0, 0, Arc::new(clock));
+        // past slots are left to the submission cutoff
+        for slot in [90, 100, 102] {
+            assert!(relay.validate_submission_slot(slot).is_ok(), "{slot}");
+        }
+        for slot in [103, 10_000] {
+            let result = relay.validate_submission_slot(slot);
+            assert!(
+                matches!(
+                    result,
+                    Err(Error::Relay(RelayError::SubmissionTooFarAhead {
+                        current_slot: 100,
+                        max_slots_ahead: 2,
+                        ..
+                    }))
+                ),
+                "{slot}"
+            );
+        }
+        assert!(check_submission_slot(Slot::MAX, Slot::MAX - 1, 2).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_open_bid_rejected_at_payload_request_limit() {
+        let relay = new_relay(0);
+        let permit = relay.acquire_payload_request_permit().unwrap();
+        let mut signed_block = SignedBlindedBeaconBlock::Capella(Default::default());
+        let result = relay.open_bid(&mut signed_block).await;
+        assert!(matches!(result, Err(Error::TooManyPayloadRequests)));
+
+        // the request is handled once a place is released, failing as the relay is not ready
+        drop(permit);
+        let result = relay.open_bid(&mut signed_block).await;
+        assert!(matches!(result, Err(Error::NotReady)));
+    }
+
+    #[tokio::test]
+    async fn test_reveal_within_times_out_slow_payload_source() {
+        let timeout = Duration::from_millis(10);
+        let payload = || AuctionContents::Capella(ExecutionPayload::Capella(Default::default()));
+
+        let slow_source = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(payload())
+        };
+        let result = reveal_within(timeout, slow_source).await;
+        assert!(matches!(result, Err(Error::Relay(RelayError::PayloadTimeout(10)))));
+
+        let result = reveal_within(timeout, async { Ok(payload()) }).await;
+        assert!(matches!(result, Ok(AuctionContents::Capella(..))));
+    }
+
+    #[tokio::test]
+    async fn test_publish_bounded_by_payload_timeout() {
+        use axum::{routing::post, Router};
+
+        let requests = Arc::new(AtomicU64::new(0));
+        let handle_publish = {
+            let requests = requests.clone();
+            move || {
+                requests.fetch_add(1, Ordering::SeqCst);
+                async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    StatusCode::OK
+                }
+            }
+        };
+        let router = Router::new().route("/eth/v2/beacon/blocks", post(handle_publish));
+        let url = spawn_mock_beacon_node(router);
+        let options =
+            RelayOptions { payload_timeout: Duration::from_millis(50), ..Default::default() };
+        let clock = Arc::new(Context::for_mainnet().clock_at(0));
+        let relay = new_relay_with_options(Client::new(url), options, clock);
+        let unblinded_block = UnblindedBlock {
+            signed_block: SignedBeaconBlock::Capella(Default::default()),
+            block_root: Default::default(),
+            auction_request: AuctionRequest { slot: 10, ..Default::default() },
+            auction_context: bid_from(1, 10, 0),
+            selection_reason: SelectionReason::OnlyBid,
+            auction_contents: AuctionContents::Capella(ExecutionPayload::Capella(
+                Default::default(),
+            )),
+        };
+
+        let start = tokio::time::Instant::now();
+        let publish = relay.publish_unblinded_block(unblinded_block);
+        let result = reveal_within(relay.options.payload_timeout, publish).await;
+        assert!(matches!(result, Err(Error::Relay(RelayError::PayloadTimeout(50)))));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(requests.load(Ordering::SeqCst) >= 1);
+        // the payload is not delivered for a block whose publish did not complete
+        let page = relay.get_delivered_payloads_page(&Default::default(), None, usize::MAX);
+        assert!(page.entries.is_empty());
+    }
+
+    #[test]
+    fn test_check_fee_recipient() {
+        let proposer = SecretKey::try_from([1u8; 32].as_ref()).unwrap().public_key();
+        let other_proposer = SecretKey::try_from([2u8; 32].as_ref()).unwrap().public_key();
+        let address = |byte: u8| ExecutionAddress::try_from([byte; 20].as_ref()).unwrap();
+        let registered = address(1);
+        let splitter = address(2);
+
+        // strict matching applies without any alternates
+        let alternates = HashMap::new();
+        assert!(check_fee_recipient(&proposer, &registered, &registered, &alternates).is_ok());
+        let err = check_fee_recipient(&proposer, &splitter, &registered, &alternates).unwrap_err();
+        assert!(matches!(
+            err,
+            RelayError::InvalidFeeRecipient(public_key, fee_recipient)
+                if public_key == proposer && fee_recipient == registered
+        ));
+
+        // an allowed alternate is accepted alongside the registered fee recipient
+        let alternates = HashMap::from([(proposer.clone(), HashSet::from([splitter.clone()]))]);
+        assert!(check_fee_recipient(&proposer, &registered, &registered, &alternates).is_ok());
+        assert!(check_fee_recipient(&proposer, &splitter, &registered, &alternates).is_ok());
+
+        // but not for other proposers, nor are other alternates
+        let result = check_fee_recipient(&other_proposer, &splitter, &registered, &alternates);
+        assert!(matches!(result, Err(RelayError::InvalidFeeRecipient(..))));
+        let result = check_fee_recipient(&proposer, &address(3), &registered, &alternates);
+        assert!(matches!(result, Err(RelayError::InvalidFeeRecipient(..))));
+    }
+
+    #[test]
+    fn test_slot_start_follows_genesis_time_override() {
+        let beacon_node = Client::new(Url::parse("http://127.0.0.1:1").unwrap());
+        let clock = Arc::new(Context::for_mainnet().clock_at(0));
+        let relay = new_relay(0);
+        assert!(matches!(relay.slot_start_ms(10), Err(Error::NotReady)));
+        let genesis = Genesis { time: 1_000, validators_root: Root::default() };
+        relay.genesis.set(genesis.clone()).unwrap();
+        assert_eq!(relay.slot_start_ms(10).unwrap(), 1_120_000);
+
+        let options = RelayOptions { genesis_time_override: Some(2_000), ..Default::default() };
+        let relay = new_relay_with_options(beacon_node, options, clock);
+        relay.genesis.set(genesis).unwrap();
+        assert_eq!(relay.slot_start_ms(10).unwrap(), 2_120_000);
+    }
+
+    #[test]
+    fn test_check_submission_cutoff() {
+        let slot_start_ms = 1_700_000_000_000;
+        let cutoff_ms = 3000;
+        for now_ms in [slot_start_ms - 4000, slot_start_ms, slot_start_ms + cutoff_ms - 1] {
+            assert!(check_submission_cutoff(10, slot_start_ms, now_ms, cutoff_ms).is_ok());
+        }
+
+        let now_ms = slot_start_ms + cutoff_ms + 1;
+        let err = check_submission_cutoff(10, slot_start_ms, now_ms, cutoff_ms).unwrap_err();
+        assert!(matches!(
+            err,
+            RelayError::SubmissionAfterCutoff { slot: 10, elapsed_ms: 3001, cutoff_ms: 3000 }
+        ));
+    }
+
+    fn bid_from(builder: u8, value: u64, receipt_order: u64) -> Arc<AuctionContext> {
+        let public_key = SecretKey::try_from([builder; 32].as_ref()).unwrap().public_key();
+        let header = ExecutionPayloadHeader::Capella(Default::default());
+        let builder_bid = builder_bid::capella::BuilderBid {
+            header,
+            value: U256::from(value),
+            public_key: public_key.clone(),
+        };
+        Arc::new(AuctionContext {
+            bid_trace: BidTrace {
+                builder_public_key: public_key,
+                value: U256::from(value),
+                ..Default::default()
+            },
+            signed_builder_bid: SignedBuilderBid {
+                message: BuilderBid::Capella(builder_bid),
+                signature: Default::default(),
+            },
+            payload: BidPayload::Local {
+                execution_payload: ExecutionPayload::Capella(Default::default()),
+            },
+            receipt_order,
+            compliance: None,
+        })
+    }
+
+    fn bid_with_compliance(
+        builder: u8,
+        value: u64,
+        receipt_order: u64,
+        compliance: Compliance,
+    ) -> Arc<AuctionContext> {
+        let mut bid = Arc::try_unwrap(bid_from(builder, value, receipt_order)).unwrap();
+        bid.compliance = Some(compliance);
+        Arc::new(bid)
+    }
+
+    fn auction_with(bids: Vec<Arc<AuctionContext>>) -> Auction {
+        let bids =
+            bids.into_iter().map(|bid| (bid.bid_trace.builder_public_key.clone(), bid)).collect();
+        Auction { bids }
+    }
+
+    #[test]
+    fn test_best_bid_selection_reason() {
+        assert!(Auction::default().best_bid_with_reason().is_none());
+
+        let auction = auction_with(vec![bid_from(1, 10, 0)]);
+        let (best_bid, reason) = auction.best_bid_with_reason().unwrap();
+        assert_eq!(best_bid.receipt_order, 0);
+        assert_eq!(reason, SelectionReason::OnlyBid);
+
+        let auction = auction_with(vec![bid_from(1, 10, 0), bid_from(2, 20, 1)]);
+        let (best_bid, reason) = auction.best_bid_with_reason().unwrap();
+        assert_eq!(best_bid.receipt_order, 1);
+        assert_eq!(reason, SelectionReason::HighestValue);
+
+        // equal values arriving at different times always select the earliest,
+        // regardless of the order the bids are held in
+        for bids in [
+            vec![bid_from(1, 20, 2), bid_from(2, 20, 1), bid_from(3, 10, 0)],
+            vec![bid_from(3, 10, 0), bid_from(2, 20, 1), bid_from(1, 20, 2)],
+        ] {
+            let auction = auction_with(bids);
+            let (best_bid, reason) = auction.best_bid_with_reason().unwrap();
+            assert_eq!(best_bid.receipt_order, 1);
+            assert_eq!(reason, SelectionReason::TieEarliest);
+            assert_eq!(auction.best_bid().unwrap().receipt_order, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bid_store_inserts_queued_bids() {
+        let relay = new_relay(2);
+        let shutdown = CancellationToken::new();
+        let bid_store = relay.spawn_bid_store(shutdown.clone());
+        let auction_request = AuctionRequest { slot: 10, ..Default::default() };
+        let pending_bid = |builder, value| {
+            let AuctionContext { bid_trace, signed_builder_bid, payload, .. } =
+                Arc::try_unwrap(bid_from(builder, value, 0)).unwrap();
+            let auction_request = auction_request.clone();
+            PendingBid {
+                auction_request,
+                bid_trace,
+                signed_builder_bid,
+                payload,
+                compliance: None,
+                simulation: None,
+            }
+        };
+
+        relay.bid_queue.try_enqueue(pending_bid(1, 10)).unwrap();
+        relay.bid_queue.try_enqueue(pending_bid(2, 20)).unwrap();
+        while relay.get_received_bids_page(&Default::default(), None, 2).entries.len() < 2 {
+            tokio::task::yield_now().await;
+        }
+        let (best_bid, _) = relay.get_auction_context(&auction_request).unwrap();
+        assert_eq!(best_bid.bid_trace.value, U256::from(20));
+
+        shutdown.cancel();
+        bid_store.await.unwrap();
+    }
+
+    #[test]
+    fn test_optimistic_builders() {
+        let relay = new_relay(2);
+        let builder = bid_from(1, 10, 0).bid_trace.builder_public_key.clone();
+        assert!(!relay.is_optimistic(&builder));
+
+        let optimistic_builders = Some(HashSet::from([builder.clone()]));
+        let policy = Policy { optimistic_builders, ..Default::default() };
+        relay.set_policy(policy.clone());
+        assert!(relay.is_optimistic(&builder));
+        relay.state.lock().demoted_builders.insert(builder.clone());
+        assert!(!relay.is_optimistic(&builder));
+
+        // replacing the policy reinstates demoted builders
+        relay.set_policy(policy);
+        assert!(relay.is_optimistic(&builder));
+    }
+
+    #[test]
+    fn test_failed_optimistic_simulation_withdraws_bid() {
+        let relay = new_relay(2);
+        let optimistic_bid = bid_from(1, 20, 1);
+        let builder = optimistic_bid.bid_trace.builder_public_key.clone();
+        let builder_reputation =
+            Some(ReputationPolicy { window: Duration::from_secs(60), failure_threshold: 1 });
+        let optimistic_builders = Some(HashSet::from([builder.clone()]));
+        relay.set_policy(Policy { builder_reputation, optimistic_builders, ..Default::default() });
+
+        let auction_request = AuctionRequest { slot: 10, ..Default::default() };
+        {
+            let mut state = relay.state.lock();
+            let auction = auction_with(vec![optimistic_bid.clone(), bid_from(2, 10, 0)]);
+            state.auctions.insert(auction_request.clone(), auction);
+            let served_bid = ServedBid {
+                auction_context: optimistic_bid.clone(),
+                selection_reason: SelectionReason::HighestValue,
+            };
+            let block_hash = optimistic_bid.bid_trace.block_hash.clone();
+            state
+                .served_bids
+                .entry(auction_request.clone())
+                .or_default()
+                .insert(block_hash, served_bid);
+        }
+
+        let err = Error::Relay(RelayError::InvalidBlock("invalid state root".into()));
+        relay.on_optimistic_simulation_failed(&auction_request, &optimistic_bid, err);
+        let (best_bid, _) = relay.get_auction_context(&auction_request).unwrap();
+        assert_eq!(best_bid.bid_trace.value, U256::from(10));
+        assert!(relay.state.lock().served_bids[&auction_request].is_empty());
+        assert!(!relay.is_optimistic(&builder));
+        assert!(relay.validate_builder_reputation(&builder).is_err());
+    }
+
+    #[test]
+    fn test_self_test() {
+        assert!(new_relay(2).self_test().is_ok());
+    }
+
+    #[test]
+    fn test_is_eligible() {
+        let (compliant, uncensored) = (Some(Compliance::Compliant), Some(Compliance::Uncensored));
+        // without a preference, every bid is eligible
+        for builder in [None, compliant, uncensored] {
+            assert!(is_eligible(None, builder, None));
+        }
+        assert!(is_eligible(compliant, compliant, None));
+        assert!(!is_eligible(compliant, uncensored, None));
+        assert!(!is_eligible(uncensored, compliant, uncensored));
+
+        // builders of unknown compliance are only assumed to be of the configured one
+        assert!(!is_eligible(compliant, None, None));
+        assert!(!is_eligible(uncensored, None, None));
+        assert!(is_eligible(uncensored, None, uncensored));
+        assert!(!is_eligible(compliant, None, uncensored));
+    }
+
+    #[test]
+    fn test_best_eligible_bid_selection() {
+        let auction = auction_with(vec![
+            bid_with_compliance(1, 30, 0, Compliance::Uncensored),
+            bid_with_compliance(2, 20, 1, Compliance::Compliant),
+            bid_from(3, 10, 2),
+        ]);
+        let compliant = |bid: &AuctionContext| bid.compliance == Some(Compliance::Compliant);
+        let (best_bid, reason) = auction.best_eligible_bid_with_reason(compliant).unwrap();
+        assert_eq!(best_bid.receipt_order, 1);
+        // only eligible bids are weighed
+        assert_eq!(reason, SelectionReason::OnlyBid);
+        assert_eq!(auction.best_bid().unwrap().receipt_order, 0);
+        assert!(auction.best_eligible_bid_with_reason(|_| false).is_none());
+    }
+
+    #[test]
+    fn test_get_served_bid() {
+        let relay = new_relay(2);
+        let auction_request = AuctionRequest { slot: 10, ..Default::default() };
+        let served = bid_from(1, 10, 0);
+        relay.record_served_bid(&auction_request, &served, SelectionReason::OnlyBid);
+        let get_served_bid = |auction_request: &AuctionRequest, signed_block| {
+            let signed_block = SignedBlindedBeaconBlock::Capella(signed_block);
+            let block = signed_block.message();
+            let body = block.body();
+            relay.get_served_bid(auction_request, body.execution_payload_header())
+        };
+
+        let mut signed_block = capella::SignedBlindedBeaconBlock::default();
+        let served_bid = get_served_bid(&auction_request, signed_block.clone()).unwrap();
+        assert_eq!(served_bid.auction_context.receipt_order, served.receipt_order);
+        assert_eq!(served_bid.selection_reason, SelectionReason::OnlyBid);
+
+        // a header with the block hash of the served bid that otherwise differs from it
+        signed_block.message.body.execution_payload_header.gas_limit += 1;
+        let err = get_served_bid(&auction_request, signed_block).unwrap_err();
+        assert!(matches!(err, RelayError::InvalidSignedBlindedBeaconBlock));
+
+        // a header for a block the relay never offered
+        let mut signed_block = capella::SignedBlindedBeaconBlock::default();
+        let block_hash = Hash32::try_from([1u8; 32].as_ref()).unwrap();
+        signed_block.message.body.execution_payload_header.block_hash = block_hash.clone();
+        let err = get_served_bid(&auction_request, signed_block).unwrap_err();
+        assert!(matches!(
+            err,
+            RelayError::BidNotServed(request, hash)
+                if request == auction_request && hash == block_hash
+        ));
+
+        // or for another auction
+        let other_request = AuctionRequest { slot: 11, ..Default::default() };
+        let err = get_served_bid(&other_request, Default::default()).unwrap_err();
+        assert!(matches!(err, RelayError::BidNotServed(..)));
+    }
+
+    #[tokio::test]
+    async fn test_served_payloads_evicted_after_auction() {
+        let relay = new_relay(0);
+        let auction_request = AuctionRequest { slot: 10, ..Default::default() };
+        relay.record_served_bid(&auction_request, &bid_from(1, 10, 0), SelectionReason::OnlyBid);
+        let signed_block = SignedBlindedBeaconBlock::Capella(Default::default());
+        let get_served_bid = || {
+            let block = signed_block.message();
+            let body = block.body();
+            relay.get_served_bid(&auction_request, body.execution_payload_header())
+        };
+
+        // the payload is held through the slot after the auction
+        let slots_per_epoch = relay.context.slots_per_epoch;
+        run_slots(&relay, MockClock::new(slots_per_epoch, 9, vec![10, 11])).await;
+        let served_bid = get_served_bid().unwrap();
+        assert!(matches!(served_bid.auction_context.payload, BidPayload::Local { .. }));
+
+        run_slots(&relay, MockClock::new(slots_per_epoch, 11, vec![12])).await;
+        assert!(matches!(get_served_bid(), Err(RelayError::BidNotServed(..))));
+    }
+
+    #[test]
+    fn test_drop_orphaned_bids() {
+        let relay = new_relay(2);
+        let orphaned_hash = Hash32::try_from([1u8; 32].as_ref()).unwrap();
+        let canonical_hash = Hash32::try_from([2u8; 32].as_ref()).unwrap();
+        let orphaned_request =
+            AuctionRequest { slot: 10, parent_hash: orphaned_hash.clone(), ..Default::default() };
+        let canonical_request =
+            AuctionRequest { slot: 10, parent_hash: canonical_hash, ..Default::default() };
+        {
+            let mut state = relay.state.lock();
+            for (builder, auction_request) in
+                [(1, orphaned_request.clone()), (2, canonical_request.clone())]
+            {
+                state.open_auctions.insert(auction_request.clone());
+                state
+                    .auctions
+                    .insert(auction_request, auction_with(vec![bid_from(builder, 10, 0)]));
+            }
+        }
+
+        let dropped = relay.drop_orphaned_bids(&HashSet::from([orphaned_hash]));
+        assert_eq!(dropped, 1);
+        assert!(relay.get_auction_context(&orphaned_request).is_none());
+        assert!(relay.get_auction_context(&canonical_request).is_some());
+        let state = relay.state.lock();
+        assert!(!state.open_auctions.contains(&orphaned_request));
+        assert!(state.open_auctions.contains(&canonical_request));
+    }
+
+    #[test]
+    fn test_delivered_payload_json() {
+        let delivered = DeliveredPayload {
+            bid_trace: BidTrace { slot: 1, ..Default::default() },
+            selection_reason: SelectionReason::TieEarliest,
+            upstream_relay: None,
+            delivered_onchain: None,
+            rechecked: false,
+        };
+        let value = serde_json::to_value(&delivered).unwrap();
+        assert_eq!(value["slot"], "1");
+        assert_eq!(value["selection_reason"], "tie_earliest");
+        assert!(value.get("upstream_relay").is_none());
+        assert!(value.get("delivered_onchain").is_none());
+        assert!(value.get("rechecked").is_none());
+
+        let delivered = DeliveredPayload { delivered_onchain: Some(false), ..delivered };
+        let value = serde_json::to_value(&delivered).unwrap();
+        assert_eq!(value["delivered_onchain"], false);
+    }
+
+    #[test]
+    fn test_record_missed_bid() {
+        let relay = new_relay(2);
+        // slots without a registered proposer never count as missed
+        relay.record_missed_bid(10, false);
+        assert_eq!(relay.recent_missed_bids(), 0);
+        relay.record_missed_bid(11, true);
+        assert_eq!(relay.recent_missed_bids(), 1);
+
+        let auction_request = AuctionRequest { slot: 12, ..Default::default() };
+        let served_bid = ServedBid {
+            auction_context: bid_from(1, 10, 0),
+            selection_reason: SelectionReason::OnlyBid,
+        };
+        relay
+            .state
+            .lock()
+            .served_bids
+            .entry(auction_request)
+            .or_default()
+            .insert(Hash32::default(), served_bid);
+        relay.record_missed_bid(12, true);
+        assert_eq!(relay.recent_missed_bids(), 1);
+
+        // misses are only counted within the window of recent slots
+        relay.record_missed_bid(11 + MISSED_BID_WINDOW_SLOTS, true);
+        assert_eq!(relay.recent_missed_bids(), 1);
+    }
+
+    #[test]
+    fn test_insert_upstream_bid() {
+        let relay = new_relay(2);
+        let upstream_key = SecretKey::try_from([3u8; 32].as_ref()).unwrap().public_key();
+        let encoded_key = serde_json::to_value(&upstream_key).unwrap();
+        let url = format!("http://{}@127.0.0.1:1", encoded_key.as_str().unwrap());
+        let endpoint = RelayEndpoint::try_from(Url::parse(&url).unwrap()).unwrap();
+        let upstream = Arc::new(UpstreamRelay::from(endpoint));
+        let slot = relay.context.capella_fork_epoch * relay.context.slots_per_epoch;
+        let auction_request = AuctionRequest { slot, ..Default::default() };
+        let bid = bid_from(3, 10, 0).signed_builder_bid.message.clone();
+        let fee_recipient = ExecutionAddress::default();
+        let insert = |auction_request: &AuctionRequest| {
+            relay.insert_upstream_bid(auction_request, upstream.clone(), &bid, &fee_recipient)
+        };
+
+        insert(&auction_request).unwrap();
+        let (auction_context, _) = relay.get_auction_context(&auction_request).unwrap();
+        assert!(matches!(auction_context.payload, BidPayload::Upstream(..)));
+        assert_eq!(auction_context.bid_trace.builder_public_key, upstream_key);
+        assert_eq!(auction_context.bid_trace.value, U256::from(10));
+        // the bid is re-signed with the key of the relay
+        let mut signed_bid = auction_context.signed_builder_bid.clone();
+        assert_eq!(signed_bid.message.public_key(), relay.public_key());
+        let signing_root =
+            compute_signing_root(&mut signed_bid.message, relay.domains.builder()).unwrap();
+        verify_signature(relay.public_key(), signing_root.as_ref(), &signed_bid.signature).unwrap();
+
+        // mirroring the same bid again keeps its place in any tie
+        insert(&auction_request).unwrap();
+        let (mirrored, _) = relay.get_auction_context(&auction_request).unwrap();
+        assert_eq!(mirrored.receipt_order, auction_context.receipt_order);
+
+        let other_request = AuctionRequest { slot: 0, ..Default::default() };
+        assert!(matches!(insert(&other_request), Err(Error::InvalidFork { .. })));
+
+        relay.record_delivered_payload(&auction_context, SelectionReason::OnlyBid);
+        let delivered = relay.get_delivered_payloads_page(&Default::default(), None, 1).entries;
+        assert_eq!(delivered[0].upstream_relay, Some(upstream.to_string()));
+    }
+
+    #[test]
+    fn test_slot_window() {
+        let window = SlotWindow::default();
+        assert!(!window.contains(100, 99));
+        assert!(window.contains(100, 100));
+        assert!(window.contains(100, 101));
+        assert!(!window.contains(100, 102));
+
+        let window = SlotWindow { behind: 1, ahead: 2 };
+        assert!(!window.contains(100, 98));
+        assert!(window.contains(100, 99));
+        assert!(window.contains(100, 102));
+        assert!(!window.contains(100, 103));
+        assert!(window.contains(0, 0));
     }
 }
@@ -1,92 +1,1304 @@
-use crate::relay::Relay;
-use backoff::ExponentialBackoff;
-use beacon_api_client::{mainnet::Client, PayloadAttributesTopic};
+#[cfg(feature = "sqlite")]
+use crate::registration_store::SqliteRegistrationStore;
+use crate::{
+    admin,
+    beacon_sync::{self, BeaconNodeInfo},
+    block_validator::{BlockValidator, ExecutionBlockValidator},
+    builder_reputation::ReputationPolicy,
+    clock_drift::{self, ClockDrift},
+    data_api, events, head_events,
+    health::Health,
+    registration_pool::RegistrationPool,
+    registration_store::{InMemoryRegistrationStore, RegistrationStore},
+    relay::{ParentHashCheck, Policy, Relay, RelayComponents, RelayOptions, SlotWindow},
+    slot_loop::{run_slot_loop, SlotClock},
+    status,
+    upstream::UpstreamRelays,
+};
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use beacon_api_client::{mainnet::Client, Error as ApiError, PayloadAttributesTopic};
 use ethereum_consensus::{
     crypto::SecretKey,
     networks::{self, Network},
-    primitives::BlsPublicKey,
+    primitives::{BlsPublicKey, Epoch, ExecutionAddress, Root, Slot, U256},
+    serde::try_bytes_from_hex_str,
     state_transition::Context,
 };
-use futures::StreamExt;
-use mev_rs::{blinded_block_relayer::Server as BlindedBlockRelayerServer, Error};
+use futures::{future, StreamExt};
+use mev_rs::{
+    blinded_block_relayer::Server as BlindedBlockRelayerServer,
+    connection_limit::ConnectionLimit,
+    parse_network,
+    rate_limit::RateLimiter,
+    tls::{load_server_config, ServerConfig},
+    types::Compliance,
+    BeaconNodePool, Error, RelayEndpoint,
+};
 use serde::Deserialize;
-use std::{future::Future, net::Ipv4Addr, pin::Pin, task::Poll};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::Poll,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio::task::{JoinError, JoinHandle};
-use tracing::{error, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
 use url::Url;
 
+/// One or more beacon node URLs, given in order of preference
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum BeaconNodeUrl {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl BeaconNodeUrl {
+    pub fn urls(&self) -> &[String] {
+        match self {
+            Self::Single(url) => std::slice::from_ref(url),
+            Self::Multiple(urls) => urls,
+        }
+    }
+}
+
+/// Paths to the PEM-encoded certificate chain and private key to terminate TLS with
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TlsFiles {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// Epochs of individual forks that replace those in the fork schedule of the network
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ForkOverrides {
+    pub altair_fork_epoch: Option<Epoch>,
+    pub bellatrix_fork_epoch: Option<Epoch>,
+    pub capella_fork_epoch: Option<Epoch>,
+    pub deneb_fork_epoch: Option<Epoch>,
+}
+
+/// An additional relay served alongside the relay under a path prefix, with a secret key of its
+/// own and the settings of the relay unless overridden
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RelayIdentity {
+    // path prefix the relay is served under, e.g. `alpha` for `/alpha/eth/v1/builder/status`
+    pub name: String,
+    // exactly one of `secret_key`, `secret_key_path` or `secret_key_env` must be provided
+    pub secret_key: Option<SecretKey>,
+    pub secret_key_path: Option<PathBuf>,
+    pub secret_key_env: Option<String>,
+    // if provided, replace the corresponding settings of the relay for this identity
+    #[serde(default, alias = "accepted_builders")]
+    pub allowed_builders: Option<Vec<BlsPublicKey>>,
+    pub enable_cancellations: Option<bool>,
+    pub min_bid_value: Option<U256>,
+    pub submission_cutoff_ms: Option<u64>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Config {
-    pub host: Ipv4Addr,
+    // if provided, overrides the network the relay is run for; one of `mainnet`, `sepolia`,
+    // `goerli`, `holesky` or a path to the config file of a custom network, e.g. `devnet.yaml`
+    pub network: Option<String>,
+    // epochs of individual forks to apply on top of the fork schedule of the network
+    #[serde(default)]
+    pub fork_overrides: ForkOverrides,
+    // an IPv4 or IPv6 address, e.g. `0.0.0.0` or `::`
+    pub host: IpAddr,
     pub port: u16,
-    pub beacon_node_url: String,
-    pub secret_key: SecretKey,
-    pub accepted_builders: Vec<BlsPublicKey>,
+    // if both are provided, terminate TLS with this certificate chain and private key
+    // (PEM-encoded) rather than serving plain HTTP
+    pub tls_cert_path: Option<PathBuf>,
+    pub tls_key_path: Option<PathBuf>,
+    pub beacon_node_url: BeaconNodeUrl,
+    // exactly one of `secret_key`, `secret_key_path` or `secret_key_env` must be provided
+    pub secret_key: Option<SecretKey>,
+    // path to a file containing the hex-encoded secret key
+    pub secret_key_path: Option<PathBuf>,
+    // name of an environment variable containing the hex-encoded secret key
+    pub secret_key_env: Option<String>,
+    // if set, sign a bid with the secret key at startup and fail to start unless it verifies
+    // against the public key and builder domain of the relay
+    #[serde(default)]
+    pub self_test: bool,
+    // if provided, only accept bids from these builders; otherwise accept bids from any builder
+    #[serde(default, alias = "accepted_builders")]
+    pub allowed_builders: Option<Vec<BlsPublicKey>>,
+    // if provided, serve Prometheus metrics on this port
+    pub metrics_port: Option<u16>,
+    // if provided, serve metrics over TLS with these files, which may be those of the relay
+    pub metrics_tls: Option<TlsFiles>,
+    // if provided, serve health checks on this port
+    pub health_port: Option<u16>,
+    // if provided, serve health checks over TLS with these files, which may be those of the relay
+    pub health_tls: Option<TlsFiles>,
+    // format of the log output of the relay, either `pretty` (the default) or `json`
+    pub log_format: Option<String>,
+    // if set, log the method, route, status and latency of each request to the relay API and
+    // record its latency in the `relay_request_duration_seconds` histogram
+    #[serde(default)]
+    pub log_requests: bool,
+    // if set, compress the responses of the data API with gzip or brotli for requests that accept
+    // either in `Accept-Encoding`
+    #[serde(default)]
+    pub compress_data_responses: bool,
+    // if provided, filter log output with this directive (e.g. `info` or `mev_relay_rs=debug`)
+    // instead of `RUST_LOG`
+    pub log_level: Option<String>,
+    // number of seconds without contact before the beacon node is considered unhealthy
+    pub beacon_node_unhealthy_after_secs: Option<u64>,
+    // timeout for each request made to a beacon node, in milliseconds
+    pub beacon_node_timeout_ms: Option<u64>,
+    // number of requests in a row that must fail to reach a beacon node before its
+    // connection is rebuilt
+    pub beacon_node_reconnect_after_failures: Option<u32>,
+    // number of recent slots to retain delivered payload records for
+    pub delivered_payload_retention_slots: Option<Slot>,
+    // if provided, check this many slots after each delivered payload, and again an epoch later,
+    // whether its block was published
+    pub delivered_payload_check_slots: Option<Slot>,
+    // number of recent slots to retain records of received builder submissions for
+    pub received_bid_retention_slots: Option<Slot>,
+    // if set, a builder's later submission replaces its earlier one even if lower in value
+    #[serde(default)]
+    pub enable_cancellations: bool,
+    // number of slots before the current slot for which bids are still served
+    pub header_slots_behind: Option<Slot>,
+    // number of slots after the current slot for which bids are already served
+    pub header_slots_ahead: Option<Slot>,
+    // number of slots after the current slot for which builder submissions are accepted
+    pub max_submission_slots_ahead: Option<Slot>,
+    // number of slots after startup during which bids are not served, unless proposer duties
+    // for the current epoch are loaded sooner
+    pub warmup_slots: Option<Slot>,
+    // if provided, prefetch the proposer duties of the next epoch this many slots before it
+    // starts, retrying at each later slot and otherwise fetching them at the epoch boundary
+    pub proposer_duties_prefetch_slots: Option<Slot>,
+    // number of serialized bids to cache for repeated requests from proposers
+    pub header_cache_size: Option<usize>,
+    // time allowed to reveal the payload of a bid to its proposer, in milliseconds, after which
+    // the proposer is sent an error so that it can fall back in time, even while its block is
+    // being published
+    pub payload_timeout_ms: Option<u64>,
+    // number of requests to reveal a payload handled at once, beyond which requests are
+    // rejected with `503` rather than slowing down those in flight
+    pub max_payload_requests: Option<usize>,
+    // if provided, number of builders expected to bid for each slot, for which the auctions of
+    // each epoch are allocated as it starts rather than on the first submissions of each slot
+    pub expected_builders: Option<usize>,
+    // number of slots the local clock may differ from the beacon node's head before warning;
+    // a head behind the local clock is allowed an extra epoch, as slots may have been missed
+    pub clock_drift_tolerance_slots: Option<u64>,
+    // if provided, ignore bids with a value (in wei) below this amount
+    pub min_bid_value: Option<U256>,
+    // if provided, ban builders with this many failed submissions within the failure window
+    // until the window has passed
+    pub builder_failure_threshold: Option<u32>,
+    // length of the window builder failures are counted in, in seconds
+    pub builder_failure_window_secs: Option<u64>,
+    // if provided, reject submissions received this many milliseconds after the start of the
+    // slot they are for, as proposers can no longer act on them in time
+    pub submission_cutoff_ms: Option<u64>,
+    // fee recipients, e.g. a splitter, bids may pay instead of the registered fee recipient of
+    // each proposer; proposers without any must be paid at their registered fee recipient
+    #[serde(default)]
+    pub alternate_fee_recipients: HashMap<BlsPublicKey, Vec<ExecutionAddress>>,
+    // compliance assumed of builders that do not declare one when serving proposers with a
+    // compliance preference; if absent, their bids are only served to proposers without one
+    pub unknown_builder_compliance: Option<Compliance>,
+    // if set, serve the bids of `optimistic_builders` as soon as their submissions are validated
+    // and simulate them afterwards, withdrawing the bid and demoting the builder until the next
+    // reload if its block is found invalid; requires `block_validation_url`
+    #[serde(default)]
+    pub optimistic_relaying: bool,
+    // builders eligible for optimistic relaying, e.g. those that have posted collateral
+    #[serde(default)]
+    pub optimistic_builders: Vec<BlsPublicKey>,
+    // if present, check the parent of each requested header is the execution block at the head
+    // of the beacon node, either serving bids on another parent with a warning or withholding them
+    pub parent_hash_check: Option<ParentHashCheck>,
+    // if set, ingest registrations and bids without ever serving bids or revealing payloads
+    #[serde(default)]
+    pub observation_mode: bool,
+    // if set, follow the head events of the beacon node to drop bids built on heads
+    // orphaned by a reorg
+    #[serde(default)]
+    pub subscribe_head_events: bool,
+    // number of attempts to initialize the relay from the beacon node before giving up
+    pub initialize_max_attempts: Option<u32>,
+    // if set, refuse to start while the beacon node reports it is still syncing
+    #[serde(default)]
+    pub require_synced_beacon: bool,
+    // if set, start even if the beacon node reports a genesis validators root other than that
+    // of the network, e.g. for a devnet reusing the config of a public network
+    #[serde(default)]
+    pub allow_genesis_mismatch: bool,
+    // if provided, use this genesis time (in seconds since the unix epoch) for the slot clock
+    pub genesis_time_override: Option<u64>,
+    // if provided, limit validator registrations to this many requests per second per source IP
+    pub registration_rate_limit_per_sec: Option<u32>,
+    // largest body of a validator registration request accepted, in bytes
+    pub max_registration_bytes: Option<usize>,
+    // largest body of a builder submission accepted, in bytes
+    pub max_submission_bytes: Option<usize>,
+    // if provided, close connections to the relay API accepted past this many open at once;
+    // metrics and health checks are served on listeners of their own and are not limited
+    pub max_connections: Option<usize>,
+    // if provided, send TCP keep-alive probes on connections to the relay API once idle for
+    // this many seconds
+    pub tcp_keepalive_secs: Option<u64>,
+    // number of batches of validator registrations to process concurrently
+    pub registration_workers: Option<usize>,
+    // number of batches of validator registrations to accept before rejecting new ones,
+    // including those being processed
+    pub registration_queue_depth: Option<usize>,
+    // if set, respond to validator registrations with `202` once queued rather than
+    // `200` once processed
+    #[serde(default)]
+    pub acknowledge_queued_registrations: bool,
+    // if unset, respond to validator registrations with `405`, e.g. when a separate service
+    // registers validators, and only serve the registrations restored from the registration store
+    #[serde(default = "default_accept_registrations")]
+    pub accept_registrations: bool,
+    // if provided, retain at most this many validator registrations, evicting the oldest first
+    pub max_registrations: Option<usize>,
+    // if provided, persist validator registrations to a SQLite database at this path
+    #[cfg(feature = "sqlite")]
+    pub registration_store_path: Option<PathBuf>,
+    // if provided, serve the admin API, e.g. to reload registrations updated in the registration
+    // store by another process, to requests with this bearer token
+    pub admin_token: Option<String>,
+    // if provided, authenticate admin requests by their signature with the admin token in the
+    // `X-Admin-Signature` header, over their time in the `X-Admin-Timestamp` header in seconds
+    // since the unix epoch, rather than by the token itself; requests further than this many
+    // seconds from the current time, or signed the same as an earlier request, are rejected
+    pub admin_request_window_secs: Option<u64>,
+    // if provided, simulate builder submissions against the execution client at this URL;
+    // otherwise only check their withdrawals are those expected by the beacon node
+    pub block_validation_url: Option<String>,
+    // timeout for each block validation request, in milliseconds
+    pub block_validation_timeout_ms: Option<u64>,
+    // if provided, forward validator registrations to these relays and mirror their bids into
+    // local auctions; each URL carries the public key of the relay as its user, e.g.
+    // `https://0xabc...@relay.example.com`
+    #[serde(default)]
+    pub upstream_relays: Vec<String>,
+    // if provided, also serve a relay for each identity under the path prefix of its name,
+    // sharing the beacon nodes and the settings not overridden by the identity
+    #[serde(default)]
+    pub identities: Vec<RelayIdentity>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            host: Ipv4Addr::LOCALHOST,
+            network: None,
+            fork_overrides: ForkOverrides::default(),
+            host: Ipv4Addr::LOCALHOST.into(),
             port: 28545,
-            beacon_node_url: "http://127.0.0.1:5052".into(),
-            secret_key: Default::default(),
-            accepted_builders: Default::default(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            beacon_node_url: BeaconNodeUrl::Single("http://127.0.0.1:5052".into()),
+            secret_key: None,
+            secret_key_path: None,
+            secret_key_env: None,
+            self_test: false,
+            allowed_builders: None,
+            metrics_port: None,
+            metrics_tls: None,
+            health_port: None,
+            health_tls: None,
+            log_format: None,
+            log_requests: false,
+            compress_data_responses: false,
+            log_level: None,
+            beacon_node_unhealthy_after_secs: None,
+            beacon_node_timeout_ms: None,
+            beacon_node_reconnect_after_failures: None,
+            delivered_payload_retention_slots: None,
+            delivered_payload_check_slots: None,
+            received_bid_retention_slots: None,
+            enable_cancellations: false,
+            header_slots_behind: None,
+            header_slots_ahead: None,
+            max_submission_slots_ahead: None,
+            warmup_slots: None,
+            proposer_duties_prefetch_slots: None,
+            header_cache_size: None,
+            payload_timeout_ms: None,
+            max_payload_requests: None,
+            expected_builders: None,
+            clock_drift_tolerance_slots: None,
+            min_bid_value: None,
+            builder_failure_threshold: None,
+            builder_failure_window_secs: None,
+            submission_cutoff_ms: None,
+            alternate_fee_recipients: HashMap::new(),
+            unknown_builder_compliance: None,
+            optimistic_relaying: false,
+            optimistic_builders: vec![],
+            parent_hash_check: None,
+            observation_mode: false,
+            subscribe_head_events: false,
+            initialize_max_attempts: None,
+            require_synced_beacon: false,
+            allow_genesis_mismatch: false,
+            genesis_time_override: None,
+            registration_rate_limit_per_sec: None,
+            max_registration_bytes: None,
+            max_submission_bytes: None,
+            max_connections: None,
+            tcp_keepalive_secs: None,
+            registration_workers: None,
+            registration_queue_depth: None,
+            acknowledge_queued_registrations: false,
+            accept_registrations: true,
+            max_registrations: None,
+            #[cfg(feature = "sqlite")]
+            registration_store_path: None,
+            admin_token: None,
+            admin_request_window_secs: None,
+            block_validation_url: None,
+            block_validation_timeout_ms: None,
+            upstream_relays: vec![],
+            identities: vec![],
         }
     }
 }
 
+const DEFAULT_BEACON_NODE_UNHEALTHY_AFTER_SECS: u64 = 60;
+const DEFAULT_BEACON_NODE_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_BEACON_NODE_RECONNECT_AFTER_FAILURES: u32 = 3;
+const DEFAULT_INITIALIZE_MAX_ATTEMPTS: u32 = 10;
+const DEFAULT_BLOCK_VALIDATION_TIMEOUT_MS: u64 = 2000;
+const DEFAULT_CLOCK_DRIFT_TOLERANCE_SLOTS: u64 = 2;
+// NOTE: one epoch on mainnet
+const DEFAULT_BUILDER_FAILURE_WINDOW_SECS: u64 = 384;
+// NOTE: a single request may register a large fleet of validators
+const DEFAULT_MAX_REGISTRATION_BYTES: usize = 32 * 1024 * 1024;
+const DEFAULT_MAX_SUBMISSION_BYTES: usize = 8 * 1024 * 1024;
+const DEFAULT_REGISTRATION_WORKERS: usize = 4;
+const DEFAULT_REGISTRATION_QUEUE_DEPTH: usize = 64;
+// Upper bound on the delay between attempts to initialize the relay.
+const INITIALIZE_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+fn default_accept_registrations() -> bool {
+    true
+}
+
+// Attempts to initialize the `relay` up to `max_attempts` times, backing off between attempts.
+async fn initialize_with_retries(relay: &Relay, max_attempts: u32) -> Result<(), Error> {
+    let mut backoff = ExponentialBackoff {
+        max_interval: INITIALIZE_MAX_BACKOFF,
+        max_elapsed_time: None,
+        ..Default::default()
+    };
+    let mut attempt = 1;
+    loop {
+        match relay.initialize().await {
+            Ok(()) => return Ok(()),
+            // NOTE: a beacon node on another chain stays on it, so there is no point retrying
+            Err(err) if attempt < max_attempts && !matches!(err, Error::GenesisMismatch { .. }) => {
+                let delay = backoff.next_backoff().unwrap_or(INITIALIZE_MAX_BACKOFF);
+                warn!(%err, attempt, max_attempts, ?delay, "could not initialize relay; retrying");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+impl Config {
+    // Settings of the relay that may be reloaded while it is running.
+    fn policy(&self) -> Policy {
+        Policy {
+            allowed_builders: self.allowed_builders.clone().map(HashSet::from_iter),
+            cancellations_enabled: self.enable_cancellations,
+            min_bid_value: self.min_bid_value,
+            builder_reputation: self.builder_failure_threshold.map(|failure_threshold| {
+                ReputationPolicy {
+                    window: Duration::from_secs(
+                        self.builder_failure_window_secs
+                            .unwrap_or(DEFAULT_BUILDER_FAILURE_WINDOW_SECS),
+                    ),
+                    failure_threshold,
+                }
+            }),
+            submission_cutoff_ms: self.submission_cutoff_ms,
+            alternate_fee_recipients: self
+                .alternate_fee_recipients
+                .iter()
+                .filter(|(_, alternates)| !alternates.is_empty())
+                .map(|(public_key, alternates)| {
+                    (public_key.clone(), HashSet::from_iter(alternates.iter().cloned()))
+                })
+                .collect(),
+            unknown_builder_compliance: self.unknown_builder_compliance,
+            optimistic_builders: self
+                .optimistic_relaying
+                .then(|| HashSet::from_iter(self.optimistic_builders.iter().cloned())),
+            parent_hash_check: self.parent_hash_check,
+        }
+    }
+
+    // Settings of the relay fixed for as long as it runs, other than those of the network.
+    fn relay_options(&self) -> RelayOptions {
+        let default = RelayOptions::default();
+        RelayOptions {
+            delivered_payload_retention_slots: self
+                .delivered_payload_retention_slots
+                .unwrap_or(default.delivered_payload_retention_slots),
+            received_bid_retention_slots: self
+                .received_bid_retention_slots
+                .unwrap_or(default.received_bid_retention_slots),
+            observation_mode: self.observation_mode,
+            max_registrations: self.max_registrations,
+            header_slot_window: SlotWindow {
+                behind: self.header_slots_behind.unwrap_or(default.header_slot_window.behind),
+                ahead: self.header_slots_ahead.unwrap_or(default.header_slot_window.ahead),
+            },
+            max_submission_slots_ahead: self
+                .max_submission_slots_ahead
+                .unwrap_or(default.max_submission_slots_ahead),
+            warmup_slots: self.warmup_slots.unwrap_or(default.warmup_slots),
+            proposer_duties_prefetch_slots: self.proposer_duties_prefetch_slots,
+            header_cache_size: self.header_cache_size.unwrap_or(default.header_cache_size),
+            payload_timeout: self
+                .payload_timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default.payload_timeout),
+            max_payload_requests: self.max_payload_requests.unwrap_or(default.max_payload_requests),
+            expected_builders: self.expected_builders,
+            expected_genesis_validators_root: None,
+            allow_genesis_mismatch: self.allow_genesis_mismatch,
+            delivered_payload_check_slots: self.delivered_payload_check_slots,
+            genesis_time_override: self.genesis_time_override,
+        }
+    }
+
+    // Settings of the relay serving `identity`, which replace those of the relay if provided.
+    fn policy_for(&self, identity: &RelayIdentity) -> Policy {
+        let mut policy = self.policy();
+        if let Some(allowed_builders) = identity.allowed_builders.clone() {
+            policy.allowed_builders = Some(HashSet::from_iter(allowed_builders));
+        }
+        if let Some(enable_cancellations) = identity.enable_cancellations {
+            policy.cancellations_enabled = enable_cancellations;
+        }
+        if identity.min_bid_value.is_some() {
+            policy.min_bid_value = identity.min_bid_value;
+        }
+        if identity.submission_cutoff_ms.is_some() {
+            policy.submission_cutoff_ms = identity.submission_cutoff_ms;
+        }
+        policy
+    }
+}
+
+// Path prefixes under which identities would shadow the routes of the relay.
+const RESERVED_IDENTITY_NAMES: &[&str] = &["eth", "relay", "status", "events"];
+
+// Ensures the name of each identity can be served as a distinct path prefix.
+fn validate_identity_names(identities: &[RelayIdentity]) -> Result<(), Error> {
+    for (i, identity) in identities.iter().enumerate() {
+        let name = identity.name.as_str();
+        let is_path_segment = !name.is_empty() &&
+            name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !is_path_segment {
+            return Err(Error::InvalidRelayIdentity(format!(
+                "`{name}` is not a name of letters, digits, `-` or `_`"
+            )))
+        }
+        if RESERVED_IDENTITY_NAMES.contains(&name) {
+            return Err(Error::InvalidRelayIdentity(format!(
+                "`{name}` is reserved for the routes of the relay"
+            )))
+        }
+        if identities[..i].iter().any(|other| other.name == name) {
+            return Err(Error::InvalidRelayIdentity(format!("`{name}` is not unique")))
+        }
+    }
+    Ok(())
+}
+
+// Returns the genesis validators root of the `network`, if it is a public network.
+fn genesis_validators_root_for(network: &Network) -> Option<Root> {
+    let encoded = match network {
+        Network::Mainnet => "0x4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95",
+        Network::Sepolia => "0xd8ea171f3c94aea21ebc42a1ed61052acf3f9209c00e4efbaaddac09ed9b8078",
+        Network::Goerli => "0x043db0d9a83813551ee2f33450d23797757d430911a9320530ad8a0eabc43efb",
+        Network::Holesky => "0x9143aa7c615a7f7115e2b6aac319c03529df8242ae705fba9df39b79c59fa8b1",
+        // NOTE: the genesis of a custom network is only known from its beacon node
+        _ => return None,
+    };
+    let bytes = try_bytes_from_hex_str(encoded).expect("is valid hex");
+    Some(Root::try_from(bytes.as_slice()).expect("is a valid root"))
+}
+
+fn context_for(network: &Network) -> Result<Context, Error> {
+    let context = match network {
+        // NOTE: the fork schedule of a custom network is loaded from its config file
+        Network::Custom(path) => Context::try_from_file(path)?,
+        network => Context::try_from(network.clone())?,
+    };
+    Ok(context)
+}
+
+// Applies the `overrides` to the fork schedule of the `context`, which must keep the forks in
+// order, allowing later forks to be scheduled in the same epoch as earlier ones.
+fn apply_fork_overrides(context: &mut Context, overrides: &ForkOverrides) -> Result<(), Error> {
+    let ForkOverrides {
+        altair_fork_epoch,
+        bellatrix_fork_epoch,
+        capella_fork_epoch,
+        deneb_fork_epoch,
+    } = overrides.clone();
+    let schedule = [
+        ("altair", altair_fork_epoch.unwrap_or(context.altair_fork_epoch)),
+        ("bellatrix", bellatrix_fork_epoch.unwrap_or(context.bellatrix_fork_epoch)),
+        ("capella", capella_fork_epoch.unwrap_or(context.capella_fork_epoch)),
+        ("deneb", deneb_fork_epoch.unwrap_or(context.deneb_fork_epoch)),
+    ];
+    for pair in schedule.windows(2) {
+        let [(earlier, earlier_epoch), (later, later_epoch)] = pair else { unreachable!() };
+        if later_epoch < earlier_epoch {
+            return Err(Error::InvalidForkSchedule(format!(
+                "{later} fork at epoch {later_epoch} is before the {earlier} fork at epoch \
+                 {earlier_epoch}"
+            )))
+        }
+    }
+    let [altair, bellatrix, capella, deneb] = schedule.map(|(_, epoch)| epoch);
+    context.altair_fork_epoch = altair;
+    context.bellatrix_fork_epoch = bellatrix;
+    context.capella_fork_epoch = capella;
+    context.deneb_fork_epoch = deneb;
+    if *overrides != ForkOverrides::default() {
+        info!(altair, bellatrix, capella, deneb, "applied fork epoch overrides");
+    }
+    Ok(())
+}
+
+fn parse_secret_key(source: &str, encoded: &str) -> Result<SecretKey, Error> {
+    let bytes = try_bytes_from_hex_str(encoded.trim())
+        .map_err(|err| Error::InvalidSecretKey(format!("could not decode {source} ({err})")))?;
+    SecretKey::try_from(bytes.as_slice())
+        .map_err(|err| Error::InvalidSecretKey(format!("could not parse {source} ({err})")))
+}
+
+fn resolve_secret_key(
+    secret_key: Option<SecretKey>,
+    secret_key_path: Option<&Path>,
+    secret_key_env: Option<&str>,
+) -> Result<SecretKey, Error> {
+    match (secret_key, secret_key_path, secret_key_env) {
+        (Some(secret_key), None, None) => Ok(secret_key),
+        (None, Some(path), None) => {
+            let source = format!("secret key from file `{}`", path.display());
+            let contents = std::fs::read_to_string(path).map_err(|err| {
+                Error::InvalidSecretKey(format!("could not read {source} ({err})"))
+            })?;
+            parse_secret_key(&source, &contents)
+        }
+        (None, None, Some(var)) => {
+            let source = format!("secret key from environment variable `{var}`");
+            let contents = std::env::var(var).map_err(|err| {
+                Error::InvalidSecretKey(format!("could not read {source} ({err})"))
+            })?;
+            parse_secret_key(&source, &contents)
+        }
+        (None, None, None) => Err(Error::InvalidSecretKey(
+            "one of `secret_key`, `secret_key_path` or `secret_key_env` must be provided".into(),
+        )),
+        _ => Err(Error::InvalidSecretKey(
+            "only one of `secret_key`, `secret_key_path` or `secret_key_env` may be provided"
+                .into(),
+        )),
+    }
+}
+
+fn resolve_tls_files(
+    cert_path: Option<PathBuf>,
+    key_path: Option<PathBuf>,
+) -> Result<Option<TlsFiles>, Error> {
+    match (cert_path, key_path) {
+        (Some(cert_path), Some(key_path)) => Ok(Some(TlsFiles { cert_path, key_path })),
+        (None, None) => Ok(None),
+        _ => Err(Error::InvalidTlsConfig(
+            "both `tls_cert_path` and `tls_key_path` must be provided to enable TLS".into(),
+        )),
+    }
+}
+
+// Loads the TLS config of each endpoint, sharing the config of the relay with any endpoint
+// configured with the same files.
+struct TlsConfigs {
+    relay: Option<Arc<ServerConfig>>,
+    metrics: Option<Arc<ServerConfig>>,
+    health: Option<Arc<ServerConfig>>,
+}
+
+impl TlsConfigs {
+    fn load(config: &Config) -> Result<Self, Error> {
+        let relay_files =
+            resolve_tls_files(config.tls_cert_path.clone(), config.tls_key_path.clone())?;
+        let relay = relay_files
+            .as_ref()
+            .map(|files| load_server_config(&files.cert_path, &files.key_path))
+            .transpose()?;
+        let load = |files: Option<&TlsFiles>| match files {
+            Some(files) if Some(files) == relay_files.as_ref() => Ok(relay.clone()),
+            Some(files) => load_server_config(&files.cert_path, &files.key_path).map(Some),
+            None => Ok(None),
+        };
+        let metrics = load(config.metrics_tls.as_ref())?;
+        let health = load(config.health_tls.as_ref())?;
+        Ok(Self { relay, metrics, health })
+    }
+}
+
+// Builds a client for the beacon node at `endpoint` with a connection pool of its own,
+// so that none of the connections of a previous client are reused.
+fn connect_beacon_node(endpoint: &Url, timeout: Duration) -> Result<Client, ApiError> {
+    let http = reqwest::Client::builder().timeout(timeout).build().map_err(ApiError::Http)?;
+    Ok(Client::new_with_client(http, endpoint.clone()))
+}
+
+fn parse_beacon_node_url(beacon_node_url: &str) -> Result<Url, Error> {
+    let endpoint = Url::parse(beacon_node_url)
+        .map_err(|err| Error::InvalidBeaconNodeUrl(format!("`{beacon_node_url}` ({err})")))?;
+    // NOTE: a scheme-less `host:port` parses as a URL with the host as its scheme
+    if endpoint.cannot_be_a_base() || !matches!(endpoint.scheme(), "http" | "https") {
+        return Err(Error::InvalidBeaconNodeUrl(format!(
+            "`{beacon_node_url}` (expected an `http` or `https` URL)"
+        )))
+    }
+    Ok(endpoint)
+}
+
+fn parse_upstream_relays(urls: &[String]) -> Result<Option<UpstreamRelays>, Error> {
+    if urls.is_empty() {
+        return Ok(None)
+    }
+    let endpoints = urls
+        .iter()
+        .map(|url| {
+            let parsed = Url::parse(url)
+                .map_err(|err| Error::InvalidUpstreamRelayUrl(format!("`{url}` ({err})")))?;
+            RelayEndpoint::try_from(parsed).map_err(|err| {
+                Error::InvalidUpstreamRelayUrl(format!("`{url}` has no valid public key ({err})"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    info!(count = endpoints.len(), "mirroring bids from upstream relays");
+    Ok(Some(UpstreamRelays::new(endpoints)))
+}
+
+// Builds the block validator each relay of the service simulates submissions against, if any.
+fn block_validator_for(config: &Config) -> Result<Option<Box<dyn BlockValidator>>, Error> {
+    let block_validation_timeout = Duration::from_millis(
+        config.block_validation_timeout_ms.unwrap_or(DEFAULT_BLOCK_VALIDATION_TIMEOUT_MS),
+    );
+    config
+        .block_validation_url
+        .as_deref()
+        .map(|url| {
+            let endpoint = Url::parse(url)
+                .map_err(|err| Error::InvalidBlockValidationUrl(format!("`{url}` ({err})")))?;
+            let block_validator = ExecutionBlockValidator::new(endpoint, block_validation_timeout)?;
+            Ok::<Box<dyn BlockValidator>, Error>(Box::new(block_validator))
+        })
+        .transpose()
+}
+
+fn registration_pool_for(config: &Config) -> RegistrationPool {
+    RegistrationPool::new(
+        config.registration_workers.unwrap_or(DEFAULT_REGISTRATION_WORKERS),
+        config.registration_queue_depth.unwrap_or(DEFAULT_REGISTRATION_QUEUE_DEPTH),
+        config.acknowledge_queued_registrations,
+    )
+}
+
+// Parts of an additional relay of the service, served under the path prefix of its `name`.
+struct Identity {
+    name: String,
+    secret_key: SecretKey,
+    policy: Policy,
+    registration_pool: RegistrationPool,
+    block_validator: Option<Box<dyn BlockValidator>>,
+}
+
 pub struct Service {
-    host: Ipv4Addr,
+    host: IpAddr,
     port: u16,
-    beacon_node: Client,
+    beacon_node: BeaconNodePool,
     network: Network,
+    context: Context,
     secret_key: SecretKey,
-    accepted_builders: Vec<BlsPublicKey>,
+    policy: Policy,
+    metrics_port: Option<u16>,
+    health_port: Option<u16>,
+    beacon_node_unhealthy_after: Duration,
+    options: RelayOptions,
+    clock_drift_tolerance_slots: u64,
+    subscribe_head_events: bool,
+    initialize_max_attempts: u32,
+    require_synced_beacon: bool,
+    genesis_time_override: Option<u64>,
+    registration_rate_limit_per_sec: Option<u32>,
+    max_registration_bytes: usize,
+    max_submission_bytes: usize,
+    max_connections: Option<usize>,
+    tcp_keepalive: Option<Duration>,
+    accept_registrations: bool,
+    log_requests: bool,
+    compress_data_responses: bool,
+    self_test: bool,
+    registration_store: Box<dyn RegistrationStore>,
+    registration_pool: RegistrationPool,
+    admin_token: Option<String>,
+    admin_request_window: Option<Duration>,
+    block_validator: Option<Box<dyn BlockValidator>>,
+    upstream_relays: Option<UpstreamRelays>,
+    identities: Vec<Identity>,
+    tls: TlsConfigs,
+    // if present, drives the relay instead of the system clock of the network
+    clock: Option<Arc<dyn SlotClock>>,
+}
+
+// Ensures the servers sharing the host of the relay are configured on distinct ports, so that
+// startup fails with a clear error before any server is bound. A port of 0 is assigned by the
+// OS and so never conflicts.
+fn validate_ports(
+    port: u16,
+    metrics_port: Option<u16>,
+    health_port: Option<u16>,
+) -> Result<(), Error> {
+    let ports = [("relay", Some(port)), ("metrics", metrics_port), ("health", health_port)];
+    let ports = ports
+        .into_iter()
+        .filter_map(|(server, port)| port.filter(|&port| port != 0).map(|port| (server, port)))
+        .collect::<Vec<_>>();
+    for (i, &(first, port)) in ports.iter().enumerate() {
+        if let Some(&(second, _)) = ports[i + 1..].iter().find(|(_, other)| *other == port) {
+            return Err(Error::PortConflict { port, first, second })
+        }
+    }
+    Ok(())
+}
+
+// Returns a port on `host` that was free when asked for, for a server configured to bind to
+// an ephemeral port, so that the port is known before the server is spawned.
+fn ephemeral_port(host: IpAddr) -> Result<u16, Error> {
+    let listener = std::net::TcpListener::bind((host, 0))
+        .map_err(|err| Error::NoEphemeralPort(err.to_string()))?;
+    let addr = listener.local_addr().map_err(|err| Error::NoEphemeralPort(err.to_string()))?;
+    Ok(addr.port())
+}
+
+/// Builds a [`Service`] without deserializing a [`Config`], for running the relay inside
+/// another program
+///
+/// At least one beacon node URL and the secret key of the relay must be set before building;
+/// the network defaults to mainnet and every other setting to that of [`Config::default`].
+///
+/// ```no_run
+/// use ethereum_consensus::{crypto::SecretKey, networks::Network};
+/// use mev_relay_rs::Service;
+/// use std::net::Ipv4Addr;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let secret_key = SecretKey::try_from([1u8; 32].as_ref())?;
+/// let handle = Service::builder()
+///     .network(Network::Sepolia)
+///     .host(Ipv4Addr::UNSPECIFIED.into())
+///     .port(28545)
+///     .beacon_node_url("http://127.0.0.1:5052")
+///     .secret_key(secret_key)
+///     .build()?
+///     .spawn()
+///     .await?;
+/// handle.shutdown().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ServiceBuilder {
+    network: Network,
+    config: Config,
+    beacon_node_urls: Vec<String>,
+}
+
+impl Default for ServiceBuilder {
+    fn default() -> Self {
+        Self { network: Network::Mainnet, config: Config::default(), beacon_node_urls: vec![] }
+    }
+}
+
+impl ServiceBuilder {
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn host(mut self, host: IpAddr) -> Self {
+        self.config.host = host;
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.port = port;
+        self
+    }
+
+    /// Adds a beacon node, after any added before in order of preference
+    pub fn beacon_node_url(mut self, url: impl Into<String>) -> Self {
+        self.beacon_node_urls.push(url.into());
+        self
+    }
+
+    /// Replaces the beacon nodes with `urls`, given in order of preference
+    pub fn beacon_node_urls(mut self, urls: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.beacon_node_urls = urls.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn secret_key(mut self, secret_key: SecretKey) -> Self {
+        self.config.secret_key = Some(secret_key);
+        self
+    }
+
+    /// Validates the settings and builds the service as [`Service::from`] would from the
+    /// equivalent config
+    pub fn build(self) -> Result<Service, Error> {
+        let Self { network, mut config, beacon_node_urls } = self;
+        if beacon_node_urls.is_empty() {
+            return Err(Error::InvalidBeaconNodeUrl("no beacon node URL provided".into()))
+        }
+        if config.secret_key.is_none() {
+            return Err(Error::InvalidSecretKey("no secret key provided".into()))
+        }
+        config.beacon_node_url = BeaconNodeUrl::Multiple(beacon_node_urls);
+        Service::from(network, config)
+    }
 }
 
 impl Service {
-    pub fn from(network: Network, config: Config) -> Self {
-        let endpoint: Url = config.beacon_node_url.parse().unwrap();
-        let beacon_node = Client::new(endpoint);
-        Self {
+    /// Returns a builder for a service with the default settings
+    pub fn builder() -> ServiceBuilder {
+        ServiceBuilder::default()
+    }
+
+    pub fn from(network: Network, config: Config) -> Result<Self, Error> {
+        validate_ports(config.port, config.metrics_port, config.health_port)?;
+        let network = match config.network.as_deref() {
+            Some(relay_network) => {
+                let relay_network = parse_network(relay_network)?;
+                info!(%network, %relay_network, "using network from relay config");
+                relay_network
+            }
+            None => network,
+        };
+        let mut context = context_for(&network)?;
+        apply_fork_overrides(&mut context, &config.fork_overrides)?;
+        let urls = config.beacon_node_url.urls();
+        if urls.is_empty() {
+            return Err(Error::InvalidBeaconNodeUrl("no beacon node URL provided".into()))
+        }
+        let timeout = Duration::from_millis(
+            config.beacon_node_timeout_ms.unwrap_or(DEFAULT_BEACON_NODE_TIMEOUT_MS),
+        );
+        let http = reqwest::Client::builder().timeout(timeout).build().map_err(ApiError::Http)?;
+        let clients = urls
+            .iter()
+            .map(|url| {
+                parse_beacon_node_url(url).map(|url| Client::new_with_client(http.clone(), url))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let reconnect_after_failures = config
+            .beacon_node_reconnect_after_failures
+            .unwrap_or(DEFAULT_BEACON_NODE_RECONNECT_AFTER_FAILURES);
+        let beacon_node = BeaconNodePool::new(clients)
+            .with_reconnection(reconnect_after_failures, move |endpoint| {
+                connect_beacon_node(endpoint, timeout)
+            });
+        if let Some(genesis_time) = config.genesis_time_override {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            if genesis_time > now {
+                return Err(Error::GenesisTimeInFuture(genesis_time))
+            }
+        }
+        #[cfg(feature = "sqlite")]
+        let registration_store: Box<dyn RegistrationStore> = match &config.registration_store_path {
+            Some(path) => Box::new(SqliteRegistrationStore::open(path)?),
+            None => Box::<InMemoryRegistrationStore>::default(),
+        };
+        #[cfg(not(feature = "sqlite"))]
+        let registration_store: Box<dyn RegistrationStore> =
+            Box::<InMemoryRegistrationStore>::default();
+        let block_validator = block_validator_for(&config)?;
+        if config.optimistic_relaying && block_validator.is_none() {
+            warn!("optimistic relaying has no effect as submissions are not simulated");
+        }
+        let upstream_relays = parse_upstream_relays(&config.upstream_relays)?;
+        let registration_pool = registration_pool_for(&config);
+        let tls = TlsConfigs::load(&config)?;
+        if config.admin_token.as_deref().map(|token| token.trim().is_empty()).unwrap_or(false) {
+            return Err(Error::EmptyAdminToken)
+        }
+        let policy = config.policy();
+        let options = RelayOptions {
+            expected_genesis_validators_root: genesis_validators_root_for(&network),
+            ..config.relay_options()
+        };
+        validate_identity_names(&config.identities)?;
+        let identities = config
+            .identities
+            .iter()
+            .map(|identity| {
+                let secret_key = resolve_secret_key(
+                    identity.secret_key.clone(),
+                    identity.secret_key_path.as_deref(),
+                    identity.secret_key_env.as_deref(),
+                )
+                .map_err(|err| {
+                    Error::InvalidRelayIdentity(format!("`{}` has {err}", identity.name))
+                })?;
+                Ok(Identity {
+                    name: identity.name.clone(),
+                    secret_key,
+                    policy: config.policy_for(identity),
+                    registration_pool: registration_pool_for(&config),
+                    block_validator: block_validator_for(&config)?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let secret_key = resolve_secret_key(
+            config.secret_key,
+            config.secret_key_path.as_deref(),
+            config.secret_key_env.as_deref(),
+        )?;
+        let mut public_keys = HashSet::from([secret_key.public_key()]);
+        for identity in &identities {
+            if !public_keys.insert(identity.secret_key.public_key()) {
+                return Err(Error::InvalidRelayIdentity(format!(
+                    "`{}` has the public key of another relay of the service",
+                    identity.name
+                )))
+            }
+        }
+        if !identities.is_empty() {
+            let names = identities.iter().map(|identity| identity.name.as_str());
+            info!(names = ?names.collect::<Vec<_>>(), "serving additional relay identities");
+        }
+        Ok(Self {
             host: config.host,
             port: config.port,
             beacon_node,
             network,
-            secret_key: config.secret_key,
-            accepted_builders: config.accepted_builders,
-        }
+            context,
+            secret_key,
+            policy,
+            metrics_port: config.metrics_port,
+            health_port: config.health_port,
+            beacon_node_unhealthy_after: Duration::from_secs(
+                config
+                    .beacon_node_unhealthy_after_secs
+                    .unwrap_or(DEFAULT_BEACON_NODE_UNHEALTHY_AFTER_SECS),
+            ),
+            options,
+            clock_drift_tolerance_slots: config
+                .clock_drift_tolerance_slots
+                .unwrap_or(DEFAULT_CLOCK_DRIFT_TOLERANCE_SLOTS),
+            subscribe_head_events: config.subscribe_head_events,
+            initialize_max_attempts: config
+                .initialize_max_attempts
+                .unwrap_or(DEFAULT_INITIALIZE_MAX_ATTEMPTS),
+            require_synced_beacon: config.require_synced_beacon,
+            genesis_time_override: config.genesis_time_override,
+            registration_rate_limit_per_sec: config.registration_rate_limit_per_sec,
+            max_registration_bytes: config
+                .max_registration_bytes
+                .unwrap_or(DEFAULT_MAX_REGISTRATION_BYTES),
+            max_submission_bytes: config
+                .max_submission_bytes
+                .unwrap_or(DEFAULT_MAX_SUBMISSION_BYTES),
+            max_connections: config.max_connections,
+            tcp_keepalive: config.tcp_keepalive_secs.map(Duration::from_secs),
+            accept_registrations: config.accept_registrations,
+            log_requests: config.log_requests,
+            compress_data_responses: config.compress_data_responses,
+            self_test: config.self_test,
+            registration_store,
+            registration_pool,
+            admin_token: config.admin_token,
+            admin_request_window: config.admin_request_window_secs.map(Duration::from_secs),
+            block_validator,
+            upstream_relays,
+            identities,
+            tls,
+            clock: None,
+        })
+    }
+
+    /// Drives the relay with `clock` instead of the system clock of the network, which also
+    /// supersedes any genesis time override
+    pub fn with_clock(mut self, clock: impl SlotClock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
     }
 
     /// Configures the [`Relay`] and the [`BlindedBlockProviderServer`] and spawns both to
     /// individual tasks
     pub async fn spawn(self) -> Result<ServiceHandle, Error> {
-        let Self { host, port, beacon_node, network, secret_key, accepted_builders } = self;
+        let Self {
+            host,
+            port,
+            beacon_node,
+            network,
+            context,
+            secret_key,
+            policy,
+            metrics_port,
+            health_port,
+            beacon_node_unhealthy_after,
+            options,
+            clock_drift_tolerance_slots,
+            subscribe_head_events,
+            initialize_max_attempts,
+            require_synced_beacon,
+            genesis_time_override,
+            registration_rate_limit_per_sec,
+            max_registration_bytes,
+            max_submission_bytes,
+            max_connections,
+            tcp_keepalive,
+            accept_registrations,
+            log_requests,
+            compress_data_responses,
+            self_test,
+            registration_store,
+            registration_pool,
+            admin_token,
+            admin_request_window,
+            block_validator,
+            upstream_relays,
+            identities,
+            tls,
+            clock,
+        } = self;
 
-        let context = Context::try_from(network)?;
-        let clock = context.clock().unwrap_or_else(|| {
-            let genesis_time = networks::typical_genesis_time(&context);
-            context.clock_at(genesis_time)
-        });
-        let relay = Relay::new(beacon_node.clone(), secret_key, accepted_builders, context);
+        // NOTE: a provided clock supersedes the genesis time override
+        let genesis_time_override = genesis_time_override.filter(|_| clock.is_none());
+        let options = RelayOptions { genesis_time_override, ..options };
+        let clock: Arc<dyn SlotClock> = match (clock, genesis_time_override) {
+            (Some(clock), _) => {
+                info!("using provided slot clock");
+                clock
+            }
+            (None, Some(genesis_time)) => {
+                info!(genesis_time, "using genesis time override for slot clock");
+                Arc::new(context.clock_at(genesis_time))
+            }
+            (None, None) => Arc::new(context.clock().unwrap_or_else(|| {
+                let genesis_time = networks::typical_genesis_time(&context);
+                context.clock_at(genesis_time)
+            })),
+        };
+        info!(
+            endpoint = %beacon_node.active_endpoint(),
+            count = beacon_node.len(),
+            "configured with beacon node(s)"
+        );
+        let slot_duration = Duration::from_secs(context.seconds_per_slot);
+        // NOTE: registrations to additional identities are not persisted and none of their
+        // bids are mirrored from upstream relays
+        let identities = identities
+            .into_iter()
+            .map(|identity| {
+                let components = RelayComponents {
+                    registration_store: Box::<InMemoryRegistrationStore>::default(),
+                    registration_pool: identity.registration_pool,
+                    block_validator: identity.block_validator,
+                    upstream_relays: None,
+                };
+                let relay = Relay::new(
+                    beacon_node.clone(),
+                    identity.secret_key,
+                    identity.policy,
+                    options.clone(),
+                    components,
+                    context.clone(),
+                    clock.clone(),
+                )?;
+                Ok((identity.name, relay))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let components = RelayComponents {
+            registration_store,
+            registration_pool,
+            block_validator,
+            upstream_relays,
+        };
+        let relay = Relay::new(
+            beacon_node.clone(),
+            secret_key,
+            policy,
+            options,
+            components,
+            context,
+            clock.clone(),
+        )?;
+        let relays = std::iter::once(relay.clone())
+            .chain(identities.iter().map(|(_, relay)| relay.clone()))
+            .collect::<Vec<_>>();
+        if self_test {
+            for relay in &relays {
+                match relay.self_test() {
+                    Ok(()) => info!(public_key = %relay.public_key(), "signing self-test passed"),
+                    Err(err) => {
+                        error!(%err, "signing self-test failed; refusing to start");
+                        return Err(err)
+                    }
+                }
+            }
+        }
+
+        let shutdown = CancellationToken::new();
+        let bid_store = first_of(
+            relays.iter().map(|relay| relay.spawn_bid_store(shutdown.clone())).collect(),
+            shutdown.clone(),
+        );
+
+        let clock_drift = Arc::new(ClockDrift::default());
+        let beacon_node_info = Arc::new(BeaconNodeInfo::default());
+        let clock_drift_monitor = clock_drift::spawn_monitor(
+            beacon_node.clone(),
+            clock.clone(),
+            slot_duration,
+            clock_drift_tolerance_slots,
+            clock_drift.clone(),
+            shutdown.clone(),
+        );
 
+        // NOTE: keep the configured port so that reloads compare against the config
+        let bound_port = if port == 0 { ephemeral_port(host)? } else { port };
+        let public_keys = relays.iter().map(|relay| relay.public_key().clone()).collect::<Vec<_>>();
+        // Returns the routes served for `relay` alongside its builder and relay APIs.
+        let routes_for = |relay: &Relay| {
+            data_api::router(relay.clone(), compress_data_responses)
+                .merge(events::router(relay.clone()))
+                .merge(status::router(
+                    relay.clone(),
+                    public_keys.clone(),
+                    clock.clone(),
+                    network.clone(),
+                    bound_port,
+                    accept_registrations,
+                    clock_drift.clone(),
+                    beacon_node.clone(),
+                    beacon_node_info.clone(),
+                ))
+        };
         let relay_for_api = relay.clone();
-        let server = BlindedBlockRelayerServer::new(host, port, relay_for_api).spawn();
+        let server_shutdown = shutdown.clone();
+        let mut server = BlindedBlockRelayerServer::new(host, bound_port, relay_for_api)
+            .with_max_registration_bytes(max_registration_bytes)
+            .with_max_submission_bytes(max_submission_bytes)
+            .with_routes(routes_for(&relay));
+        if let Some(token) = admin_token {
+            let admin = admin::router(relay.clone(), token, admin_request_window);
+            server = server.with_routes(admin);
+        }
+        for (name, identity) in &identities {
+            server = server.with_relay_at(name.clone(), identity.clone(), routes_for(identity));
+        }
+        if !accept_registrations {
+            // NOTE: proposer duties are still refreshed each epoch, so bids are served to any
+            // proposer restored from the registration store
+            info!("not accepting validator registrations; serving stored registrations only");
+            server = server.without_validator_registrations();
+        }
+        let registration_rate_limiter = registration_rate_limit_per_sec
+            .map(|rate_per_sec| Arc::new(RateLimiter::new(rate_per_sec)));
+        if let Some(limiter) = registration_rate_limiter.clone() {
+            server = server.with_registration_rate_limiter(limiter);
+        }
+        if log_requests {
+            server = server.with_request_logging(Arc::new(crate::metrics::record_request));
+        }
+        if let Some(config) = tls.relay {
+            server = server.with_tls(config);
+        }
+        let connection_limit = ConnectionLimit::new(max_connections)
+            .with_observer(Arc::new(crate::metrics::record_connection));
+        server = server.with_connection_limit(connection_limit);
+        if let Some(idle) = tcp_keepalive {
+            server = server.with_tcp_keepalive(idle);
+        }
+        let server =
+            server.spawn_with_graceful_shutdown(async move { server_shutdown.cancelled().await });
+
+        let metrics = metrics_port.and_then(|metrics_port| {
+            let metrics_shutdown = shutdown.clone();
+            crate::metrics::spawn_server(host, metrics_port, tls.metrics, async move {
+                metrics_shutdown.cancelled().await
+            })
+        });
+
+        let health = Arc::new(Health::new(beacon_node_unhealthy_after));
+        let health_server = health_port.map(|health_port| {
+            crate::health::spawn_server(
+                host,
+                health_port,
+                tls.health,
+                health.clone(),
+                beacon_node.clone(),
+                shutdown.clone(),
+            )
+        });
 
-        let relay_clone = relay.clone();
+        let head_events = subscribe_head_events.then(|| {
+            let subscriptions = relays
+                .iter()
+                .map(|relay| {
+                    head_events::spawn_subscription(
+                        relay.clone(),
+                        beacon_node.clone(),
+                        shutdown.clone(),
+                    )
+                })
+                .collect();
+            first_of(subscriptions, shutdown.clone())
+        });
+
+        let reloadable =
+            Reloadable { host, port, relay: relay.clone(), identities, registration_rate_limiter };
+
+        let relays_for_consensus = relays.clone();
+        let consensus_shutdown = shutdown.clone();
         let consensus = tokio::spawn(async move {
-            let relay = relay_clone;
+            let relays = relays_for_consensus;
 
-            let result = backoff::future::retry::<(), (), _, _, _>(
+            let events = backoff::future::retry::<(), (), _, _, _>(
                 ExponentialBackoff::default(),
                 || async {
                     let retry = backoff::Error::transient(());
-                    let mut stream = match beacon_node.get_events::<PayloadAttributesTopic>().await
-                    {
+                    let client = beacon_node.active();
+                    let mut stream = match client.get_events::<PayloadAttributesTopic>().await {
                         Ok(stream) => stream,
                         Err(err) => {
                             error!(%err, "could not open payload attributes stream");
+                            beacon_node.fail_over_if_unreachable(&err);
                             return Err(retry)
                         }
                     };
@@ -94,44 +1306,112 @@ impl Service {
                     while let Some(event) = stream.next().await {
                         match event {
                             Ok(event) => {
-                                if let Err(err) = relay.on_payload_attributes(event.data) {
-                                    warn!(%err, "could not process payload attributes");
-                                    continue
+                                for relay in &relays {
+                                    if let Err(err) =
+                                        relay.on_payload_attributes(event.data.clone())
+                                    {
+                                        warn!(%err, "could not process payload attributes");
+                                    }
                                 }
                             }
                             Err(err) => {
                                 warn!(%err, "error reading payload attributes stream");
+                                beacon_node.fail_over_if_unreachable(&err);
                                 return Err(retry)
                             }
                         }
                     }
                     Err(retry)
                 },
-            )
-            .await;
-            if result.is_err() {
-                error!("failed to read from event stream");
+            );
+            tokio::select! {
+                result = events => {
+                    if result.is_err() {
+                        error!("failed to read from event stream");
+                    }
+                }
+                _ = consensus_shutdown.cancelled() => {}
             }
         });
 
+        let relay_shutdown = shutdown.clone();
+        let relay_beacon_node = beacon_node.clone();
         let relay = tokio::spawn(async move {
-            let slots = clock.stream_slots();
-
-            tokio::pin!(slots);
-
-            let mut current_epoch = clock.current_epoch().expect("after genesis");
-            relay.on_epoch(current_epoch).await;
-            while let Some(slot) = slots.next().await {
-                let epoch = clock.epoch_for(slot);
-                if epoch > current_epoch {
-                    current_epoch = epoch;
-                    relay.on_epoch(epoch).await;
-                }
-                relay.on_slot(slot).await;
+            // NOTE: the server is already up and responds with 503 to proposers until
+            // the relay has been initialized
+            let initialize_all = future::try_join_all(
+                relays.iter().map(|relay| initialize_with_retries(relay, initialize_max_attempts)),
+            );
+            let initialized = tokio::select! {
+                result = initialize_all => result.map(|_| ()),
+                _ = relay_shutdown.cancelled() => return Ok(()),
+            };
+            if let Err(err) = initialized {
+                error!(%err, attempts = initialize_max_attempts, "could not initialize relay");
+                return Err(err)
             }
+            // NOTE: a syncing beacon node serves unreliable proposer duties
+            beacon_sync::check_beacon_node(
+                &relay_beacon_node,
+                require_synced_beacon,
+                &beacon_node_info,
+            )
+            .await?;
+            health.record_beacon_node_contact();
+            health.set_initialized();
+
+            // NOTE: an unexpected end of the slot stream has already been logged and
+            // surfaces as `ServiceExit::RelayLoopEnded` so that supervisors restart the relay;
+            // every relay follows the same clock, so their slot streams end together
+            future::join_all(
+                relays
+                    .iter()
+                    .map(|relay| run_slot_loop(relay, &*clock, slot_duration, &relay_shutdown)),
+            )
+            .await;
+            Ok(())
         });
 
-        Ok(ServiceHandle { relay, server, consensus })
+        Ok(ServiceHandle {
+            relay,
+            server,
+            consensus,
+            metrics,
+            health: health_server,
+            head_events,
+            clock_drift: clock_drift_monitor,
+            bid_store,
+            shutdown,
+            reloadable,
+        })
+    }
+}
+
+/// Reason a [`ServiceHandle`] completed, identifying the task that stopped
+#[derive(Debug, thiserror::Error)]
+pub enum ServiceExit {
+    #[error("relay task failed: {0}")]
+    RelayTaskFailed(JoinError),
+    #[error("could not initialize relay: {0}")]
+    RelayInitializationFailed(Error),
+    #[error("relay slot stream ended unexpectedly")]
+    RelayLoopEnded,
+    #[error("server task failed: {0}")]
+    ServerTaskFailed(JoinError),
+    #[error("server stopped unexpectedly")]
+    ServerStopped,
+    #[error("{task} task failed: {source}")]
+    TaskFailed { task: &'static str, source: JoinError },
+    #[error("{0} task stopped unexpectedly")]
+    TaskStopped(&'static str),
+}
+
+impl ServiceExit {
+    fn from_task(task: &'static str, result: Result<(), JoinError>) -> Self {
+        match result {
+            Ok(()) => Self::TaskStopped(task),
+            Err(source) => Self::TaskFailed { task, source },
+        }
     }
 }
 
@@ -141,26 +1421,450 @@ impl Service {
 #[pin_project::pin_project]
 pub struct ServiceHandle {
     #[pin]
-    relay: JoinHandle<()>,
+    relay: JoinHandle<Result<(), Error>>,
     #[pin]
     server: JoinHandle<()>,
     #[pin]
     consensus: JoinHandle<()>,
+    #[pin]
+    clock_drift: JoinHandle<()>,
+    #[pin]
+    bid_store: JoinHandle<()>,
+    metrics: Option<JoinHandle<()>>,
+    health: Option<JoinHandle<()>>,
+    head_events: Option<JoinHandle<()>>,
+    shutdown: CancellationToken,
+    reloadable: Reloadable,
+}
+
+const RELOADABLE_SETTINGS: &str = "allowed_builders, enable_cancellations, min_bid_value, \
+     builder_failure_threshold, builder_failure_window_secs, submission_cutoff_ms, \
+     alternate_fee_recipients, unknown_builder_compliance, optimistic_relaying, \
+     optimistic_builders, parent_hash_check, registration_rate_limit_per_sec";
+
+// Parts of a running service that can be updated from a new `Config`.
+struct Reloadable {
+    host: IpAddr,
+    port: u16,
+    relay: Relay,
+    identities: Vec<(String, Relay)>,
+    registration_rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Reloadable {
+    fn reload(&self, config: Config) {
+        if config.host != self.host || config.port != self.port {
+            warn!(
+                host = %config.host,
+                port = config.port,
+                "ignoring change to the address of the relay; a restart is required"
+            );
+        }
+        let secret_key_changed = config
+            .secret_key
+            .as_ref()
+            .map(|secret_key| &secret_key.public_key() != self.relay.public_key())
+            .unwrap_or(false);
+        if secret_key_changed {
+            warn!("ignoring change to the secret key of the relay; a restart is required");
+        }
+        match (self.registration_rate_limiter.as_ref(), config.registration_rate_limit_per_sec) {
+            (Some(limiter), Some(rate_per_sec)) => limiter.set_rate_per_sec(rate_per_sec),
+            (None, None) => {}
+            _ => warn!("ignoring change to enabling the registration rate limit; restart required"),
+        }
+        self.relay.set_policy(config.policy());
+        for (name, relay) in &self.identities {
+            match config.identities.iter().find(|identity| &identity.name == name) {
+                Some(identity) => relay.set_policy(config.policy_for(identity)),
+                None => warn!(name, "ignoring removal of relay identity; a restart is required"),
+            }
+        }
+        for identity in &config.identities {
+            if !self.identities.iter().any(|(name, _)| name == &identity.name) {
+                warn!(name = identity.name, "ignoring new relay identity; a restart is required");
+            }
+        }
+        // NOTE: settings like `host`, `port` or `secret_key` can not be safely hot-reloaded
+        info!(
+            reloaded = RELOADABLE_SETTINGS,
+            "reloaded relay policy; changes to any other settings are ignored until restart"
+        );
+    }
+}
+
+// Spawns a task completing once any of the `tasks` does, so that the tasks are supervised as one,
+// and after all of them once `shutdown` has been signalled.
+fn first_of(mut tasks: Vec<JoinHandle<()>>, shutdown: CancellationToken) -> JoinHandle<()> {
+    if tasks.len() == 1 {
+        return tasks.pop().expect("one task")
+    }
+    tokio::spawn(async move {
+        let (result, _, remaining) = future::select_all(tasks).await;
+        if shutdown.is_cancelled() {
+            future::join_all(remaining).await;
+        }
+        if let Err(err) = result {
+            if err.is_panic() {
+                std::panic::resume_unwind(err.into_panic())
+            }
+        }
+    })
+}
+
+async fn join_optional(handle: Option<JoinHandle<()>>) -> Result<(), JoinError> {
+    match handle {
+        Some(handle) => handle.await,
+        None => Ok(()),
+    }
+}
+
+fn poll_optional(
+    handle: &mut Option<JoinHandle<()>>,
+    cx: &mut std::task::Context<'_>,
+) -> Poll<Result<(), JoinError>> {
+    match handle.as_mut() {
+        Some(handle) => Pin::new(handle).poll(cx),
+        None => Poll::Pending,
+    }
+}
+
+impl ServiceHandle {
+    /// Updates the running relay with the policy from `config`, e.g. after the config
+    /// file has changed
+    ///
+    /// Only the builder allowlist, cancellations, the minimum bid value, the builder failure
+    /// limits, the submission cutoff, the alternate fee recipients, the compliance assumed of
+    /// builders without one, the optimistic builders and the registration rate limit are
+    /// reloaded; all other settings take effect on restart. Reloading also forgets any failures
+    /// counted against builders and reinstates demoted optimistic builders. The settings of each
+    /// relay identity are reloaded alike, but identities are only added or removed on restart.
+    pub fn reload(&self, config: Config) {
+        self.reloadable.reload(config)
+    }
+
+    /// Signals all spawned tasks to stop and waits for each of them to complete
+    pub async fn shutdown(self) -> Result<(), JoinError> {
+        info!("shutting down relay service...");
+        self.shutdown.cancel();
+        let (relay, server, consensus, clock_drift, bid_store, metrics, health, head_events) = tokio::join!(
+            self.relay,
+            self.server,
+            self.consensus,
+            self.clock_drift,
+            self.bid_store,
+            join_optional(self.metrics),
+            join_optional(self.health),
+            join_optional(self.head_events)
+        );
+        // NOTE: a failure to initialize the relay has already been logged
+        let _ = relay?;
+        server?;
+        consensus?;
+        clock_drift?;
+        bid_store?;
+        metrics?;
+        health?;
+        head_events
+    }
 }
 
 impl Future for ServiceHandle {
-    type Output = Result<(), JoinError>;
+    type Output = ServiceExit;
 
     fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
-        let relay = this.relay.poll(cx);
-        if relay.is_ready() {
-            return relay
+        if let Poll::Ready(result) = this.relay.poll(cx) {
+            let exit = match result {
+                Ok(Ok(())) => ServiceExit::RelayLoopEnded,
+                Ok(Err(err)) => ServiceExit::RelayInitializationFailed(err),
+                Err(err) => ServiceExit::RelayTaskFailed(err),
+            };
+            return Poll::Ready(exit)
+        }
+        if let Poll::Ready(result) = this.consensus.poll(cx) {
+            return Poll::Ready(ServiceExit::from_task("consensus", result))
+        }
+        if let Poll::Ready(result) = this.clock_drift.poll(cx) {
+            return Poll::Ready(ServiceExit::from_task("clock drift", result))
+        }
+        if let Poll::Ready(result) = this.bid_store.poll(cx) {
+            return Poll::Ready(ServiceExit::from_task("bid store", result))
+        }
+        if let Poll::Ready(result) = poll_optional(this.metrics, cx) {
+            return Poll::Ready(ServiceExit::from_task("metrics", result))
         }
-        let consensus = this.consensus.poll(cx);
-        if consensus.is_ready() {
-            return consensus
+        if let Poll::Ready(result) = poll_optional(this.health, cx) {
+            return Poll::Ready(ServiceExit::from_task("health", result))
         }
-        this.server.poll(cx)
+        if let Poll::Ready(result) = poll_optional(this.head_events, cx) {
+            return Poll::Ready(ServiceExit::from_task("head events", result))
+        }
+        this.server.poll(cx).map(|result| match result {
+            Ok(()) => ServiceExit::ServerStopped,
+            Err(err) => ServiceExit::ServerTaskFailed(err),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_consensus::Fork;
+
+    #[test]
+    fn test_parse_beacon_node_url() {
+        assert!(parse_beacon_node_url("http://127.0.0.1:5052").is_ok());
+        assert!(parse_beacon_node_url("https://beacon.example.com").is_ok());
+
+        for url in ["", "127.0.0.1:5052", "localhost:5052"] {
+            let err = parse_beacon_node_url(url).unwrap_err();
+            assert!(matches!(err, Error::InvalidBeaconNodeUrl(..)));
+        }
+    }
+
+    #[test]
+    fn test_validate_ports() {
+        assert!(validate_ports(18550, Some(18551), Some(18552)).is_ok());
+        assert!(validate_ports(18550, None, None).is_ok());
+        // ephemeral ports are assigned distinct ports by the OS
+        assert!(validate_ports(0, Some(0), Some(0)).is_ok());
+
+        let err = validate_ports(18550, Some(18550), None).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PortConflict { port: 18550, first: "relay", second: "metrics" }
+        ));
+        let err = validate_ports(18550, Some(18551), Some(18551)).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PortConflict { port: 18551, first: "metrics", second: "health" }
+        ));
+
+        let config = Config { health_port: Some(Config::default().port), ..Default::default() };
+        let result = Service::from(Network::Sepolia, config);
+        assert!(matches!(result, Err(Error::PortConflict { first: "relay", .. })));
+    }
+
+    #[test]
+    fn test_service_from_invalid_url() {
+        let beacon_node_url = BeaconNodeUrl::Single("localhost:5052".into());
+        let config = Config { beacon_node_url, ..Default::default() };
+        let result = Service::from(Network::Sepolia, config);
+        assert!(matches!(result, Err(Error::InvalidBeaconNodeUrl(..))));
+
+        let beacon_node_url =
+            BeaconNodeUrl::Multiple(vec!["http://127.0.0.1:5052".into(), "127.0.0.1:5053".into()]);
+        let config = Config { beacon_node_url, ..Default::default() };
+        let result = Service::from(Network::Sepolia, config);
+        assert!(matches!(result, Err(Error::InvalidBeaconNodeUrl(..))));
+    }
+
+    #[test]
+    fn test_service_from_future_genesis_time() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let config = Config { genesis_time_override: Some(now + 3600), ..Default::default() };
+        let result = Service::from(Network::Sepolia, config);
+        assert!(matches!(result, Err(Error::GenesisTimeInFuture(..))));
+    }
+
+    #[test]
+    fn test_service_from_invalid_tls_config() {
+        let config = Config { tls_cert_path: Some("cert.pem".into()), ..Default::default() };
+        let result = Service::from(Network::Sepolia, config);
+        assert!(matches!(result, Err(Error::InvalidTlsConfig(..))));
+
+        let missing = std::env::temp_dir().join("mev-relay-rs-test-missing.pem");
+        let config = Config {
+            tls_cert_path: Some(missing.clone()),
+            tls_key_path: Some(missing),
+            ..Default::default()
+        };
+        let result = Service::from(Network::Sepolia, config);
+        assert!(matches!(result, Err(Error::InvalidTlsConfig(..))));
+    }
+
+    #[test]
+    fn test_service_from_empty_admin_token() {
+        let config = Config { admin_token: Some(" ".into()), ..Default::default() };
+        let result = Service::from(Network::Sepolia, config);
+        assert!(matches!(result, Err(Error::EmptyAdminToken)));
+    }
+
+    #[test]
+    fn test_service_builder() {
+        let secret_key = || SecretKey::try_from([1u8; 32].as_ref()).unwrap();
+        let result = Service::builder().secret_key(secret_key()).build();
+        assert!(matches!(result, Err(Error::InvalidBeaconNodeUrl(..))));
+        let result = Service::builder().beacon_node_url("http://127.0.0.1:5052").build();
+        assert!(matches!(result, Err(Error::InvalidSecretKey(..))));
+        // settings are validated as those of a config
+        let result =
+            Service::builder().beacon_node_url("localhost:5052").secret_key(secret_key()).build();
+        assert!(matches!(result, Err(Error::InvalidBeaconNodeUrl(..))));
+
+        let service = Service::builder()
+            .network(Network::Sepolia)
+            .host(Ipv4Addr::UNSPECIFIED.into())
+            .port(18550)
+            .beacon_node_urls(["http://127.0.0.1:5052", "http://127.0.0.1:5053"])
+            .secret_key(secret_key())
+            .build()
+            .unwrap();
+        let config = Config {
+            host: Ipv4Addr::UNSPECIFIED.into(),
+            port: 18550,
+            beacon_node_url: BeaconNodeUrl::Multiple(vec![
+                "http://127.0.0.1:5052".into(),
+                "http://127.0.0.1:5053".into(),
+            ]),
+            secret_key: Some(secret_key()),
+            ..Default::default()
+        };
+        let expected = Service::from(Network::Sepolia, config).unwrap();
+        assert_eq!(service.host, expected.host);
+        assert_eq!(service.port, expected.port);
+        assert_eq!(service.network.to_string(), expected.network.to_string());
+        assert_eq!(service.secret_key.public_key(), expected.secret_key.public_key());
+        assert_eq!(service.options.header_cache_size, expected.options.header_cache_size);
+    }
+
+    #[test]
+    fn test_validate_identity_names() {
+        let identity = |name: &str| RelayIdentity { name: name.into(), ..Default::default() };
+        assert!(validate_identity_names(&[identity("alpha"), identity("beta-2_b")]).is_ok());
+        for names in [&["alpha", "alpha"][..], &[""], &["al/pha"], &["status"], &["eth"]] {
+            let identities = names.iter().map(|name| identity(name)).collect::<Vec<_>>();
+            let err = validate_identity_names(&identities).unwrap_err();
+            assert!(matches!(err, Error::InvalidRelayIdentity(..)), "{names:?}");
+        }
+    }
+
+    #[test]
+    fn test_policy_for_identity() {
+        let builder = SecretKey::try_from([2u8; 32].as_ref()).unwrap().public_key();
+        let config = Config {
+            enable_cancellations: true,
+            min_bid_value: Some(U256::from(10)),
+            submission_cutoff_ms: Some(3000),
+            ..Default::default()
+        };
+        // settings that are not overridden are those of the relay
+        let policy = config.policy_for(&RelayIdentity::default());
+        assert_eq!(policy.min_bid_value, config.min_bid_value);
+        assert!(policy.cancellations_enabled);
+
+        let identity = RelayIdentity {
+            allowed_builders: Some(vec![builder.clone()]),
+            enable_cancellations: Some(false),
+            min_bid_value: Some(U256::from(20)),
+            ..Default::default()
+        };
+        let policy = config.policy_for(&identity);
+        assert_eq!(policy.allowed_builders, Some(HashSet::from([builder])));
+        assert!(!policy.cancellations_enabled);
+        assert_eq!(policy.min_bid_value, Some(U256::from(20)));
+        assert_eq!(policy.submission_cutoff_ms, Some(3000));
+    }
+
+    #[test]
+    fn test_service_from_identities() {
+        let secret_key = |byte: u8| SecretKey::try_from([byte; 32].as_ref()).unwrap();
+        let identity = |name: &str, byte| RelayIdentity {
+            name: name.into(),
+            secret_key: Some(secret_key(byte)),
+            ..Default::default()
+        };
+        let config = |identities| Config {
+            secret_key: Some(secret_key(1)),
+            identities,
+            ..Default::default()
+        };
+
+        let service = Service::from(
+            Network::Sepolia,
+            config(vec![identity("alpha", 2), identity("beta", 3)]),
+        )
+        .unwrap();
+        let names = service.identities.iter().map(|identity| identity.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), vec!["alpha", "beta"]);
+
+        // every relay of the service must have a distinct public key
+        for identities in [vec![identity("alpha", 1)], vec![identity("a", 2), identity("b", 2)]] {
+            let result = Service::from(Network::Sepolia, config(identities));
+            assert!(matches!(result, Err(Error::InvalidRelayIdentity(..))));
+        }
+        let without_key = RelayIdentity { name: "alpha".into(), ..Default::default() };
+        let result = Service::from(Network::Sepolia, config(vec![without_key]));
+        assert!(matches!(result, Err(Error::InvalidRelayIdentity(..))));
+    }
+
+    #[test]
+    fn test_apply_fork_overrides() {
+        let mut context = context_for(&Network::Sepolia).unwrap();
+        let base = context.clone();
+        apply_fork_overrides(&mut context, &ForkOverrides::default()).unwrap();
+        assert_eq!(context.capella_fork_epoch, base.capella_fork_epoch);
+
+        let capella_fork_epoch = base.bellatrix_fork_epoch + 1;
+        let overrides =
+            ForkOverrides { capella_fork_epoch: Some(capella_fork_epoch), ..Default::default() };
+        apply_fork_overrides(&mut context, &overrides).unwrap();
+        assert_eq!(context.capella_fork_epoch, capella_fork_epoch);
+        assert_eq!(context.deneb_fork_epoch, base.deneb_fork_epoch);
+        let slot = capella_fork_epoch * context.slots_per_epoch;
+        assert_eq!(context.fork_for(slot), Fork::Capella);
+
+        // forks may share an epoch but not be out of order
+        let overrides = ForkOverrides {
+            capella_fork_epoch: Some(5),
+            deneb_fork_epoch: Some(5),
+            bellatrix_fork_epoch: Some(0),
+            altair_fork_epoch: Some(0),
+        };
+        apply_fork_overrides(&mut context, &overrides).unwrap();
+        let mut context = base.clone();
+        let overrides = ForkOverrides {
+            deneb_fork_epoch: Some(base.capella_fork_epoch - 1),
+            ..Default::default()
+        };
+        let err = apply_fork_overrides(&mut context, &overrides).unwrap_err();
+        assert!(matches!(err, Error::InvalidForkSchedule(..)));
+        assert_eq!(context.deneb_fork_epoch, base.deneb_fork_epoch);
+    }
+
+    #[test]
+    fn test_genesis_validators_root_for() {
+        for network in [Network::Mainnet, Network::Sepolia, Network::Goerli, Network::Holesky] {
+            assert!(genesis_validators_root_for(&network).is_some(), "{network}");
+        }
+        assert!(genesis_validators_root_for(&Network::Custom("config.yaml".into())).is_none());
+    }
+
+    #[test]
+    fn test_resolve_secret_key() {
+        let secret_key = SecretKey::try_from([1u8; 32].as_ref()).unwrap();
+        let encoded = format!("0x{}\n", "01".repeat(32));
+
+        let path = std::env::temp_dir().join("mev-relay-rs-test-secret-key");
+        std::fs::write(&path, &encoded).unwrap();
+        let resolved = resolve_secret_key(None, Some(&path), None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resolved.public_key(), secret_key.public_key());
+
+        let var = "MEV_RELAY_RS_TEST_SECRET_KEY";
+        std::env::set_var(var, &encoded);
+        let resolved = resolve_secret_key(None, None, Some(var)).unwrap();
+        assert_eq!(resolved.public_key(), secret_key.public_key());
+
+        let resolved = resolve_secret_key(Some(secret_key.clone()), None, None).unwrap();
+        assert_eq!(resolved.public_key(), secret_key.public_key());
+
+        let result = resolve_secret_key(None, None, None);
+        assert!(matches!(result, Err(Error::InvalidSecretKey(..))));
+        let result = resolve_secret_key(Some(secret_key), None, Some(var));
+        assert!(matches!(result, Err(Error::InvalidSecretKey(..))));
+        let result = resolve_secret_key(None, None, Some("MEV_RELAY_RS_TEST_MISSING_SECRET_KEY"));
+        assert!(matches!(result, Err(Error::InvalidSecretKey(..))));
     }
 }
@@ -0,0 +1,194 @@
+use crate::{metrics::CURRENT_EPOCH, relay::Relay};
+use ethereum_consensus::{
+    clock::SystemClock,
+    primitives::{Epoch, Slot},
+};
+use futures::{Stream, StreamExt};
+use metrics::gauge;
+use std::{
+    pin::Pin,
+    time::{Duration, Instant},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+/// Source of the slots that drive the relay
+///
+/// The relay is driven by the [`SystemClock`] of its network by default, but any other source
+/// of time, e.g. an external time authority, may drive it instead.
+pub trait SlotClock: Send + Sync {
+    /// Returns a stream yielding each slot as it starts
+    fn stream_slots(&self) -> Pin<Box<dyn Stream<Item = Slot> + Send + '_>>;
+
+    /// Returns the current epoch, if the chain has started
+    fn current_epoch(&self) -> Option<Epoch>;
+
+    /// Returns the current slot, if the chain has started
+    fn current_slot(&self) -> Option<Slot>;
+
+    fn epoch_for(&self, slot: Slot) -> Epoch;
+}
+
+impl SlotClock for SystemClock {
+    fn stream_slots(&self) -> Pin<Box<dyn Stream<Item = Slot> + Send + '_>> {
+        Box::pin(SystemClock::stream_slots(self))
+    }
+
+    fn current_epoch(&self) -> Option<Epoch> {
+        SystemClock::current_epoch(self)
+    }
+
+    fn current_slot(&self) -> Option<Slot> {
+        SystemClock::current_slot(self)
+    }
+
+    fn epoch_for(&self, slot: Slot) -> Epoch {
+        SystemClock::epoch_for(self, slot)
+    }
+}
+
+/// Work done by a run of the slot loop
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SlotLoopStats {
+    pub slots_processed: u64,
+    pub slots_skipped: u64,
+    pub epochs_processed: u64,
+}
+
+/// Reason the slot loop stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotLoopExit {
+    // the `shutdown` token was cancelled
+    Shutdown,
+    // the slot stream ended while the relay was still running
+    StreamEnded,
+}
+
+/// Drives the `relay` with each slot from `clock`, also notifying it of each new epoch,
+/// until the slot stream ends or `shutdown` is cancelled
+///
+/// Slots yielded after they have already passed, e.g. in a burst as the host resumes from
+/// suspend, are skipped so that the relay only catches up once with the latest slot.
+///
+/// The slot stream of the [`SystemClock`] ticks forever, so it only ends if the clock is
+/// broken, e.g. after a bug in its slot arithmetic. The stream is not rebuilt in that case,
+/// as a rebuilt stream would likely end the same way and could spin without yielding any slot;
+/// instead the end is logged as an error and returned so that the relay stops and can be
+/// restarted by its supervisor.
+pub async fn run_slot_loop<C: SlotClock + ?Sized>(
+    relay: &Relay,
+    clock: &C,
+    slot_duration: Duration,
+    shutdown: &CancellationToken,
+) -> (SlotLoopExit, SlotLoopStats) {
+    let mut slots = clock.stream_slots();
+    let mut stats = SlotLoopStats::default();
+
+    let mut current_epoch = clock.current_epoch().expect("after genesis");
+    gauge!(CURRENT_EPOCH, current_epoch as f64);
+    relay.on_epoch(current_epoch).await;
+    relay.prewarm_auctions(current_epoch);
+    stats.epochs_processed += 1;
+    let mut last_slot = clock.current_slot();
+    loop {
+        // NOTE: only observe cancellation between slots so that
+        // any in-flight processing for a slot runs to completion
+        let slot = tokio::select! {
+            slot = slots.next() => slot,
+            _ = shutdown.cancelled() => return (SlotLoopExit::Shutdown, stats),
+        };
+        let Some(slot) = slot else {
+            error!(?stats, "slot stream ended unexpectedly; stopping relay");
+            return (SlotLoopExit::StreamEnded, stats)
+        };
+        if clock.current_slot().map(|current_slot| slot < current_slot).unwrap_or(false) {
+            debug!(slot, "skipping slot that has already passed");
+            stats.slots_skipped += 1;
+            continue
+        }
+        if let Some(last_slot) = last_slot.filter(|&last_slot| slot > last_slot + 1) {
+            // NOTE: any state for the slots in the gap is pruned when processing this slot
+            let gap = slot - last_slot - 1;
+            warn!(last_slot, slot, gap, "slot clock skipped slots; catching up with latest slot");
+        }
+        last_slot = Some(slot);
+        let epoch = clock.epoch_for(slot);
+        async {
+            let start = Instant::now();
+            if epoch > current_epoch {
+                info!("reached epoch boundary");
+                current_epoch = epoch;
+                gauge!(CURRENT_EPOCH, current_epoch as f64);
+                relay.on_epoch(epoch).await;
+                relay.prewarm_auctions(epoch);
+                stats.epochs_processed += 1;
+            }
+            relay.on_slot(slot).await;
+            stats.slots_processed += 1;
+            let elapsed = start.elapsed();
+            if elapsed > slot_duration {
+                warn!(?elapsed, "processing slot took longer than the slot duration");
+            }
+        }
+        .instrument(info_span!("slot", slot, epoch))
+        .await;
+    }
+}
+
+/// Yields a fixed sequence of slots without waiting for them to start
+#[cfg(test)]
+#[derive(Clone)]
+pub struct MockClock {
+    slots_per_epoch: u64,
+    current_slot: Slot,
+    slots: Vec<Slot>,
+    // if present, the slot reported as current while yielding, e.g. after resuming from suspend
+    resumed_at: Option<Slot>,
+    // called with each slot as it is yielded, before the slot is processed
+    on_yield: Option<std::sync::Arc<dyn Fn(Slot) + Send + Sync>>,
+}
+
+#[cfg(test)]
+impl MockClock {
+    /// Starts at `current_slot` and then yields each of `slots` in turn
+    pub fn new(slots_per_epoch: u64, current_slot: Slot, slots: Vec<Slot>) -> Self {
+        Self { slots_per_epoch, current_slot, slots, resumed_at: None, on_yield: None }
+    }
+
+    /// Calls `f` with each slot as it is yielded, e.g. to change state between slots
+    pub fn on_yield(mut self, f: impl Fn(Slot) + Send + Sync + 'static) -> Self {
+        self.on_yield = Some(std::sync::Arc::new(f));
+        self
+    }
+
+    /// Reports `slot` as the current slot while yielding, as if the host resumed from
+    /// suspend at `slot` with the slot stream yet to catch up
+    pub fn resumed_at(mut self, slot: Slot) -> Self {
+        self.resumed_at = Some(slot);
+        self
+    }
+}
+
+#[cfg(test)]
+impl SlotClock for MockClock {
+    fn stream_slots(&self) -> Pin<Box<dyn Stream<Item = Slot> + Send + '_>> {
+        let on_yield = self.on_yield.clone();
+        Box::pin(futures::stream::iter(self.slots.clone()).inspect(move |&slot| {
+            if let Some(f) = on_yield.as_ref() {
+                f(slot)
+            }
+        }))
+    }
+
+    fn current_epoch(&self) -> Option<Epoch> {
+        Some(self.epoch_for(self.current_slot))
+    }
+
+    fn current_slot(&self) -> Option<Slot> {
+        self.resumed_at
+    }
+
+    fn epoch_for(&self, slot: Slot) -> Epoch {
+        slot / self.slots_per_epoch
+    }
+}
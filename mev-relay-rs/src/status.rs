@@ -0,0 +1,219 @@
+use crate::{
+    beacon_sync::BeaconNodeInfo, clock_drift::ClockDrift, relay::Relay, slot_loop::SlotClock,
+};
+use axum::{extract::State, routing::get, Json, Router};
+use ethereum_consensus::{
+    networks::Network,
+    primitives::{BlsPublicKey, Epoch, Root, Slot},
+    state_transition::Context,
+    Fork,
+};
+use mev_rs::{BeaconNodePool, ConnectionStatus};
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Clone)]
+struct StatusContext {
+    relay: Relay,
+    public_keys: Vec<BlsPublicKey>,
+    clock: Arc<dyn SlotClock>,
+    network: Network,
+    port: u16,
+    accept_registrations: bool,
+    clock_drift: Arc<ClockDrift>,
+    beacon_node: BeaconNodePool,
+    beacon_node_info: Arc<BeaconNodeInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct BeaconNodeStatus {
+    endpoint: String,
+    active: bool,
+    connected: bool,
+    consecutive_failures: u32,
+    reconnections: u32,
+}
+
+impl From<ConnectionStatus> for BeaconNodeStatus {
+    fn from(status: ConnectionStatus) -> Self {
+        Self {
+            endpoint: status.endpoint.to_string(),
+            active: status.is_active,
+            connected: status.is_connected(),
+            consecutive_failures: status.consecutive_failures,
+            reconnections: status.reconnections,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Status {
+    // `None` if the clock is before genesis
+    current_slot: Option<Slot>,
+    current_epoch: Option<Epoch>,
+    // `None` until the relay has been initialized
+    genesis_time: Option<u64>,
+    genesis_validators_root: Option<Root>,
+    network: String,
+    // port the relay API is served on, which is assigned by the OS if configured as 0
+    port: u16,
+    public_key: BlsPublicKey,
+    // public keys of every relay served by the service, the relay at the root path first
+    public_keys: Vec<BlsPublicKey>,
+    // if `true`, the relay does not serve bids or reveal payloads
+    observation_mode: bool,
+    // if `false`, the relay rejects validator registrations, relying on its registration store
+    accept_registrations: bool,
+    // if `true`, the relay has only just started and does not serve bids yet
+    warming_up: bool,
+    // slot by which the warmup ends at the latest, if still warming up
+    warmup_end_slot: Option<Slot>,
+    // recent slots with a registered proposer to which no bid was served
+    recent_missed_bids: usize,
+    // slot of the beacon node's head minus the local slot, as of the last check
+    clock_drift_slots: Option<i64>,
+    // version of the beacon node as the relay started, if it reported one
+    beacon_node_version: Option<String>,
+    // whether the beacon node was syncing as the relay started; `None` if unknown
+    beacon_node_syncing: Option<bool>,
+    beacon_nodes: Vec<BeaconNodeStatus>,
+}
+
+async fn handle_status(State(context): State<StatusContext>) -> Json<Status> {
+    let StatusContext {
+        relay,
+        public_keys,
+        clock,
+        network,
+        port,
+        accept_registrations,
+        clock_drift,
+        beacon_node,
+        beacon_node_info,
+    } = context;
+    let warmup_end_slot = relay.warmup_end_slot();
+    Json(Status {
+        current_slot: clock.current_slot(),
+        current_epoch: clock.current_epoch(),
+        genesis_time: relay.genesis().map(|genesis| genesis.time),
+        genesis_validators_root: relay.genesis().map(|genesis| genesis.validators_root.clone()),
+        network: network.to_string(),
+        port,
+        public_key: relay.public_key().clone(),
+        public_keys,
+        observation_mode: relay.observation_mode(),
+        accept_registrations,
+        warming_up: warmup_end_slot.is_some(),
+        warmup_end_slot,
+        recent_missed_bids: relay.recent_missed_bids(),
+        clock_drift_slots: clock_drift.last_observed_slots(),
+        beacon_node_version: beacon_node_info.version().map(String::from),
+        beacon_node_syncing: beacon_node_info.is_syncing(),
+        beacon_nodes: beacon_node.connection_statuses().into_iter().map(From::from).collect(),
+    })
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct ScheduledFork {
+    fork: Fork,
+    epoch: Epoch,
+}
+
+#[derive(Debug, Serialize)]
+struct BuilderStatus {
+    network: String,
+    public_key: BlsPublicKey,
+    // version of the relay software
+    version: &'static str,
+    // `None` if the clock is before genesis
+    current_fork: Option<Fork>,
+    // forks scheduled after the current epoch, in activation order
+    upcoming_forks: Vec<ScheduledFork>,
+}
+
+// Returns the forks `context` schedules after `current_epoch`, or all scheduled forks if the
+// chain has not started yet.
+fn upcoming_forks(context: &Context, current_epoch: Option<Epoch>) -> Vec<ScheduledFork> {
+    [
+        (Fork::Altair, context.altair_fork_epoch),
+        (Fork::Bellatrix, context.bellatrix_fork_epoch),
+        (Fork::Capella, context.capella_fork_epoch),
+        (Fork::Deneb, context.deneb_fork_epoch),
+    ]
+    .into_iter()
+    // NOTE: forks that are not scheduled yet are set to activate at the maximum epoch
+    .filter(|&(_, epoch)| epoch != Epoch::MAX)
+    .filter(|&(_, epoch)| current_epoch.map(|current_epoch| epoch > current_epoch).unwrap_or(true))
+    .map(|(fork, epoch)| ScheduledFork { fork, epoch })
+    .collect()
+}
+
+// NOTE: forks are read from the context of the relay so that they match the forks it expects
+async fn handle_builder_status(State(context): State<StatusContext>) -> Json<BuilderStatus> {
+    let StatusContext { relay, clock, network, .. } = context;
+    let chain = relay.context();
+    Json(BuilderStatus {
+        network: network.to_string(),
+        public_key: relay.public_key().clone(),
+        version: env!("CARGO_PKG_VERSION"),
+        current_fork: clock.current_slot().map(|slot| chain.fork_for(slot)),
+        upcoming_forks: upcoming_forks(chain, clock.current_epoch()),
+    })
+}
+
+/// Returns routes exposing the status of the `relay` according to the `clock` driving it,
+/// and the forks and software version it supports for builder tooling
+///
+/// The `public_keys` of every relay served alongside the `relay` are listed in its status.
+pub fn router(
+    relay: Relay,
+    public_keys: Vec<BlsPublicKey>,
+    clock: Arc<dyn SlotClock>,
+    network: Network,
+    port: u16,
+    accept_registrations: bool,
+    clock_drift: Arc<ClockDrift>,
+    beacon_node: BeaconNodePool,
+    beacon_node_info: Arc<BeaconNodeInfo>,
+) -> Router {
+    Router::new()
+        .route("/status", get(handle_status))
+        .route("/relay/v1/builder/status", get(handle_builder_status))
+        .with_state(StatusContext {
+            relay,
+            public_keys,
+            clock,
+            network,
+            port,
+            accept_registrations,
+            clock_drift,
+            beacon_node,
+            beacon_node_info,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upcoming_forks() {
+        let mut context = Context::for_mainnet();
+        context.altair_fork_epoch = 0;
+        context.bellatrix_fork_epoch = 10;
+        context.capella_fork_epoch = 20;
+        context.deneb_fork_epoch = Epoch::MAX;
+
+        let scheduled = |fork, epoch| ScheduledFork { fork, epoch };
+        assert_eq!(
+            upcoming_forks(&context, None),
+            vec![
+                scheduled(Fork::Altair, 0),
+                scheduled(Fork::Bellatrix, 10),
+                scheduled(Fork::Capella, 20)
+            ]
+        );
+        assert_eq!(upcoming_forks(&context, Some(10)), vec![scheduled(Fork::Capella, 20)]);
+        assert!(upcoming_forks(&context, Some(20)).is_empty());
+    }
+}
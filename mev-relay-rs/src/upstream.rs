@@ -0,0 +1,183 @@
+use ethereum_consensus::primitives::{BlsPublicKey, Domain};
+use futures::{stream, StreamExt};
+use mev_rs::{
+    signing::{compute_signing_root, verify_signature},
+    types::{
+        AuctionContents, AuctionRequest, SignedBlindedBeaconBlock, SignedBuilderBid,
+        SignedValidatorRegistration,
+    },
+    Error, RelayEndpoint, RelayError,
+};
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, info, warn};
+
+// Amount of time each upstream relay has to return its bid, so that a slow upstream
+// does not hold up serving the local bids.
+const FETCH_BID_TIMEOUT: Duration = Duration::from_millis(500);
+// Amount of time an upstream relay has to reveal the payload of a bid it served.
+const OPEN_BID_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A relay whose bids are mirrored into the local auctions
+pub type UpstreamRelay = mev_rs::Relay;
+
+// Verifies the `signed_bid` served by the upstream relay with `public_key` is for the
+// `auction_request` and is signed by the relay over the builder `domain`.
+fn validate_upstream_bid(
+    signed_bid: &mut SignedBuilderBid,
+    auction_request: &AuctionRequest,
+    public_key: &BlsPublicKey,
+    domain: Domain,
+) -> Result<(), Error> {
+    let bid = &mut signed_bid.message;
+    if bid.public_key() != public_key {
+        let reason = format!("bid is signed for public key {:?}", bid.public_key());
+        return Err(RelayError::InvalidUpstreamBid(reason).into())
+    }
+    let parent_hash = bid.header().parent_hash();
+    if parent_hash != &auction_request.parent_hash {
+        let reason = format!("bid builds on parent hash {parent_hash:?}");
+        return Err(RelayError::InvalidUpstreamBid(reason).into())
+    }
+    let signing_root = compute_signing_root(bid, domain)?;
+    Ok(verify_signature(public_key, signing_root.as_ref(), &signed_bid.signature)?)
+}
+
+/// Relays that validator registrations are forwarded to and whose bids are mirrored into
+/// the local auctions, so that the relay acts as an aggregating front-end
+pub struct UpstreamRelays {
+    relays: Vec<Arc<UpstreamRelay>>,
+}
+
+impl UpstreamRelays {
+    pub fn new(endpoints: impl IntoIterator<Item = RelayEndpoint>) -> Self {
+        let relays = endpoints.into_iter().map(|endpoint| Arc::new(endpoint.into())).collect();
+        Self { relays }
+    }
+
+    /// Forwards the `registrations` to every upstream relay, logging any that fail
+    pub async fn register_validators(&self, registrations: &[SignedValidatorRegistration]) {
+        let count = registrations.len();
+        let responses = stream::iter(self.relays.iter())
+            .map(|relay| async move { (relay, relay.register_validators(registrations).await) })
+            .buffer_unordered(self.relays.len().max(1))
+            .collect::<Vec<_>>()
+            .await;
+        for (relay, response) in responses {
+            match response {
+                Ok(()) => debug!(%relay, count, "forwarded validator registrations"),
+                Err(err) => warn!(%err, %relay, count, "could not forward validator registrations"),
+            }
+        }
+    }
+
+    /// Fetches the bid of each upstream relay for the `auction_request`, skipping any upstream
+    /// that fails, times out or serves a bid that is not validly signed over the builder `domain`
+    pub async fn fetch_bids(
+        &self,
+        auction_request: &AuctionRequest,
+        domain: Domain,
+    ) -> Vec<(Arc<UpstreamRelay>, SignedBuilderBid)> {
+        stream::iter(self.relays.iter().cloned())
+            .map(|relay| async move {
+                let response =
+                    tokio::time::timeout(FETCH_BID_TIMEOUT, relay.fetch_best_bid(auction_request))
+                        .await;
+                (relay, response)
+            })
+            .buffer_unordered(self.relays.len().max(1))
+            .filter_map(|(relay, response)| async move {
+                let err = match response {
+                    Ok(Ok(mut signed_bid)) => {
+                        let public_key = &relay.public_key;
+                        match validate_upstream_bid(
+                            &mut signed_bid,
+                            auction_request,
+                            public_key,
+                            domain,
+                        ) {
+                            Ok(()) => return Some((relay, signed_bid)),
+                            Err(err) => err,
+                        }
+                    }
+                    Ok(Err(Error::NoBidPrepared(..))) => {
+                        debug!(%relay, %auction_request, "upstream relay had no bid prepared");
+                        return None
+                    }
+                    Ok(Err(err)) => err,
+                    Err(_) => RelayError::UpstreamRelayTimeout(relay.to_string()).into(),
+                };
+                warn!(%err, %relay, %auction_request, "skipping bid from upstream relay");
+                None
+            })
+            .collect()
+            .await
+    }
+}
+
+/// Asks the upstream `relay` a bid was mirrored from to reveal the payload for `signed_block`
+pub async fn open_bid(
+    relay: &UpstreamRelay,
+    signed_block: &SignedBlindedBeaconBlock,
+) -> Result<AuctionContents, Error> {
+    info!(%relay, slot = signed_block.message().slot(), "opening bid with upstream relay");
+    tokio::time::timeout(OPEN_BID_TIMEOUT, relay.open_bid(signed_block))
+        .await
+        .map_err(|_| RelayError::UpstreamRelayTimeout(relay.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_consensus::{crypto::SecretKey, primitives::Hash32};
+    use mev_rs::{
+        signing::sign_with_domain,
+        types::{builder_bid, BuilderBid, ExecutionPayloadHeader},
+    };
+
+    fn signed_bid(secret_key: &SecretKey, domain: Domain) -> SignedBuilderBid {
+        let mut message = BuilderBid::Capella(builder_bid::capella::BuilderBid {
+            header: ExecutionPayloadHeader::Capella(Default::default()),
+            value: Default::default(),
+            public_key: secret_key.public_key(),
+        });
+        let signature = sign_with_domain(&mut message, secret_key, domain).unwrap();
+        SignedBuilderBid { message, signature }
+    }
+
+    #[test]
+    fn test_validate_upstream_bid() {
+        let secret_key = SecretKey::try_from([1u8; 32].as_ref()).unwrap();
+        let public_key = secret_key.public_key();
+        let domain = [1u8; 32];
+        let auction_request = AuctionRequest::default();
+
+        let mut bid = signed_bid(&secret_key, domain);
+        assert!(validate_upstream_bid(&mut bid, &auction_request, &public_key, domain).is_ok());
+
+        let other = SecretKey::try_from([2u8; 32].as_ref()).unwrap().public_key();
+        let result = validate_upstream_bid(&mut bid, &auction_request, &other, domain);
+        assert!(matches!(result, Err(Error::Relay(RelayError::InvalidUpstreamBid(..)))));
+
+        let other_domain = [2u8; 32];
+        let result = validate_upstream_bid(&mut bid, &auction_request, &public_key, other_domain);
+        assert!(result.is_err());
+
+        let parent_hash = Hash32::try_from([3u8; 32].as_ref()).unwrap();
+        let auction_request = AuctionRequest { parent_hash, ..Default::default() };
+        let result = validate_upstream_bid(&mut bid, &auction_request, &public_key, domain);
+        assert!(matches!(result, Err(Error::Relay(RelayError::InvalidUpstreamBid(..)))));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_bids_skips_unreachable_upstream() {
+        let public_key = SecretKey::try_from([1u8; 32].as_ref()).unwrap().public_key();
+        let public_key = serde_json::to_value(&public_key).unwrap();
+        let url = format!("http://{}@127.0.0.1:1", public_key.as_str().unwrap());
+        let endpoint = RelayEndpoint::try_from(url.parse::<url::Url>().unwrap()).unwrap();
+        let upstream_relays = UpstreamRelays::new([endpoint]);
+
+        let domain = [1u8; 32];
+        let bids = upstream_relays.fetch_bids(&AuctionRequest::default(), domain).await;
+        assert!(bids.is_empty());
+    }
+}
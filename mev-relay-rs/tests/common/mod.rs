@@ -0,0 +1,69 @@
+use axum::{routing::get, Json, Router};
+use ethereum_consensus::primitives::Root;
+use serde_json::{json, Value};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use url::Url;
+
+/// Serves the subset of the beacon node API the relay depends on, with a fixed genesis and
+/// an empty validator set without any proposer duties
+#[derive(Debug, Clone)]
+pub struct MockBeaconNode {
+    pub genesis_time: u64,
+    pub genesis_validators_root: Root,
+}
+
+impl MockBeaconNode {
+    fn router(&self) -> Router {
+        let genesis = json!({
+            "data": {
+                "genesis_time": self.genesis_time.to_string(),
+                "genesis_validators_root": self.genesis_validators_root,
+                "genesis_fork_version": "0x00000000",
+            }
+        });
+        Router::new()
+            .route(
+                "/eth/v1/beacon/genesis",
+                get(move || {
+                    let genesis = genesis.clone();
+                    async move { Json(genesis) }
+                }),
+            )
+            .route(
+                "/eth/v1/validator/duties/proposer/:epoch",
+                get(|| async {
+                    Json(json!({
+                        "dependent_root": Root::default(),
+                        "execution_optimistic": false,
+                        "data": Value::Array(vec![]),
+                    }))
+                }),
+            )
+            .route(
+                "/eth/v1/beacon/states/:state_id/validators",
+                get(|| async {
+                    Json(json!({
+                        "execution_optimistic": false,
+                        "finalized": false,
+                        "data": Value::Array(vec![]),
+                    }))
+                }),
+            )
+    }
+
+    /// Spawns the mock on an ephemeral port, returning the URL it is served at
+    pub fn spawn(&self) -> Url {
+        let server = axum::Server::bind(&SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .serve(self.router().into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        Url::parse(&format!("http://{addr}")).unwrap()
+    }
+}
+
+/// Returns a port that was free at the time of the call, for services that must be told
+/// their port up front
+pub fn unused_port() -> u16 {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    listener.local_addr().unwrap().port()
+}
@@ -0,0 +1,366 @@
+mod common;
+
+use beacon_api_client::Client as ApiClient;
+use common::{unused_port, MockBeaconNode};
+use ethereum_consensus::{crypto::SecretKey, networks::Network, primitives::Root};
+use mev_relay_rs::{
+    sign_admin_request, BeaconNodeUrl, Config, RelayIdentity, Service, ServiceExit,
+};
+use mev_rs::{blinded_block_provider::Client as RelayClient, types::AuctionRequest, Error};
+use serde_json::Value;
+use std::{
+    net::Ipv4Addr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use url::Url;
+
+// Polls the `/status` of the relay at `url` until it reports the relay has been initialized.
+async fn wait_for_initialization(url: &Url) -> Value {
+    let status_url = url.join("/status").unwrap();
+    for _ in 0..100 {
+        if let Ok(response) = reqwest::get(status_url.clone()).await {
+            let status = response.json::<Value>().await.unwrap();
+            if !status["genesis_time"].is_null() {
+                return status
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("relay was not initialized in time")
+}
+
+// Returns the config of a relay on an unused port backed by the beacon node at `url`.
+fn config_for(beacon_node_url: &Url) -> Config {
+    Config {
+        host: Ipv4Addr::LOCALHOST.into(),
+        port: unused_port(),
+        beacon_node_url: BeaconNodeUrl::Single(beacon_node_url.to_string()),
+        secret_key: Some(SecretKey::try_from([1u8; 32].as_ref()).unwrap()),
+        // NOTE: the mock never schedules any proposers, so the warmup lasts its full length
+        warmup_slots: Some(32),
+        // NOTE: the mock serves an arbitrary genesis rather than that of the network
+        allow_genesis_mismatch: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_service_initializes_against_mock_beacon_node() {
+    let genesis_time = 1_655_733_600;
+    let genesis_validators_root = Root::try_from([7u8; 32].as_ref()).unwrap();
+    let beacon_node =
+        MockBeaconNode { genesis_time, genesis_validators_root: genesis_validators_root.clone() };
+    let config = config_for(&beacon_node.spawn());
+    let port = config.port;
+    let service = Service::from(Network::Sepolia, config).unwrap();
+    let handle = service.spawn().await.unwrap();
+
+    let relay_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+    let status = wait_for_initialization(&relay_url).await;
+    assert_eq!(status["accept_registrations"], true);
+    assert_eq!(status["port"], port);
+    assert_eq!(status["genesis_time"], genesis_time);
+    assert_eq!(
+        status["genesis_validators_root"],
+        serde_json::to_value(&genesis_validators_root).unwrap()
+    );
+
+    // bids are withheld while warming up after initialization
+    let builder_status_url = relay_url.join("/relay/v1/builder/status").unwrap();
+    let builder_status =
+        reqwest::get(builder_status_url).await.unwrap().json::<Value>().await.unwrap();
+    assert_eq!(builder_status["network"], "sepolia");
+    assert_eq!(builder_status["public_key"], status["public_key"]);
+    assert_eq!(builder_status["version"], env!("CARGO_PKG_VERSION"));
+    assert!(builder_status["current_fork"].is_string());
+
+    let relay = RelayClient::new(ApiClient::new(relay_url));
+    relay.check_status().await.unwrap();
+    let err = relay.fetch_best_bid(&AuctionRequest::default()).await.unwrap_err();
+    assert!(matches!(err, Error::NoBidPrepared(..)));
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_service_without_registrations() {
+    let beacon_node =
+        MockBeaconNode { genesis_time: 1_655_733_600, genesis_validators_root: Root::default() };
+    let config = Config { accept_registrations: false, ..config_for(&beacon_node.spawn()) };
+    let port = config.port;
+    let service = Service::from(Network::Sepolia, config).unwrap();
+    let handle = service.spawn().await.unwrap();
+
+    let relay_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+    let status = wait_for_initialization(&relay_url).await;
+    assert_eq!(status["accept_registrations"], false);
+
+    let registrations_url = relay_url.join("/eth/v1/builder/validators").unwrap();
+    let response = reqwest::Client::new()
+        .post(registrations_url)
+        .json(&Value::Array(vec![]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED);
+
+    // bids are still requested from the relay as usual
+    let relay = RelayClient::new(ApiClient::new(relay_url));
+    let err = relay.fetch_best_bid(&AuctionRequest::default()).await.unwrap_err();
+    assert!(matches!(err, Error::NoBidPrepared(..)));
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_service_spawns_from_builder() {
+    let beacon_node =
+        MockBeaconNode { genesis_time: 1_655_733_600, genesis_validators_root: Root::default() };
+    let port = unused_port();
+    let service = Service::builder()
+        .network(Network::Sepolia)
+        .host(Ipv4Addr::LOCALHOST.into())
+        .port(port)
+        .beacon_node_url(beacon_node.spawn())
+        .secret_key(SecretKey::try_from([1u8; 32].as_ref()).unwrap())
+        .build()
+        .unwrap();
+    let handle = service.spawn().await.unwrap();
+
+    let relay_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+    let status = wait_for_initialization(&relay_url).await;
+    assert_eq!(status["port"], port);
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_service_serves_identities_under_prefix() {
+    let beacon_node =
+        MockBeaconNode { genesis_time: 1_655_733_600, genesis_validators_root: Root::default() };
+    let identity = RelayIdentity {
+        name: "alpha".into(),
+        secret_key: Some(SecretKey::try_from([2u8; 32].as_ref()).unwrap()),
+        ..Default::default()
+    };
+    let config = Config { identities: vec![identity], ..config_for(&beacon_node.spawn()) };
+    let port = config.port;
+    let service = Service::from(Network::Sepolia, config).unwrap();
+    let handle = service.spawn().await.unwrap();
+
+    let relay_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+    let status = wait_for_initialization(&relay_url).await;
+    let public_keys = status["public_keys"].as_array().unwrap();
+    assert_eq!(public_keys.len(), 2);
+    assert_eq!(public_keys[0], status["public_key"]);
+
+    let identity_status_url = relay_url.join("/alpha/status").unwrap();
+    let identity_status =
+        reqwest::get(identity_status_url).await.unwrap().json::<Value>().await.unwrap();
+    assert_eq!(identity_status["public_key"], public_keys[1]);
+
+    let response = reqwest::get(relay_url.join("/alpha/eth/v1/builder/status").unwrap()).await;
+    assert_eq!(response.unwrap().status(), reqwest::StatusCode::OK);
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_admin_reload_registrations_requires_token() {
+    let beacon_node =
+        MockBeaconNode { genesis_time: 1_655_733_600, genesis_validators_root: Root::default() };
+    let config = Config { admin_token: Some("s3cret".into()), ..config_for(&beacon_node.spawn()) };
+    let port = config.port;
+    let service = Service::from(Network::Sepolia, config).unwrap();
+    let handle = service.spawn().await.unwrap();
+
+    let relay_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+    wait_for_initialization(&relay_url).await;
+
+    let reload_url = relay_url.join("/admin/reload_registrations").unwrap();
+    let client = reqwest::Client::new();
+    let response = client.post(reload_url.clone()).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let response = client.post(reload_url.clone()).bearer_auth("guess").send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client.post(reload_url).bearer_auth("s3cret").send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let reloaded = response.json::<Value>().await.unwrap();
+    assert_eq!(reloaded["count"], 0);
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_admin_requests_require_fresh_signature() {
+    let beacon_node =
+        MockBeaconNode { genesis_time: 1_655_733_600, genesis_validators_root: Root::default() };
+    let config = Config {
+        admin_token: Some("s3cret".into()),
+        admin_request_window_secs: Some(30),
+        ..config_for(&beacon_node.spawn())
+    };
+    let port = config.port;
+    let service = Service::from(Network::Sepolia, config).unwrap();
+    let handle = service.spawn().await.unwrap();
+
+    let relay_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+    wait_for_initialization(&relay_url).await;
+
+    let path = "/admin/reload_registrations";
+    let reload_url = relay_url.join(path).unwrap();
+    let client = reqwest::Client::new();
+    let send = |timestamp: u64, signature: String| {
+        client
+            .post(reload_url.clone())
+            .header("X-Admin-Timestamp", timestamp.to_string())
+            .header("X-Admin-Signature", signature)
+            .send()
+    };
+    let sign =
+        |token: &str, timestamp| sign_admin_request(token, &reqwest::Method::POST, path, timestamp);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    // the token alone, even with a fresh timestamp, is not enough
+    let response = client
+        .post(reload_url.clone())
+        .bearer_auth("s3cret")
+        .header("X-Admin-Timestamp", now.to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    for (timestamp, signature, expected) in [
+        (now, sign("s3cret", now), reqwest::StatusCode::OK),
+        (now - 60, sign("s3cret", now - 60), reqwest::StatusCode::UNAUTHORIZED),
+        (now + 60, sign("s3cret", now + 60), reqwest::StatusCode::UNAUTHORIZED),
+        (now, sign("guess", now), reqwest::StatusCode::UNAUTHORIZED),
+        // a captured signature with a new timestamp
+        (now - 1, sign("s3cret", now), reqwest::StatusCode::UNAUTHORIZED),
+        // a captured request replayed as is
+        (now, sign("s3cret", now), reqwest::StatusCode::UNAUTHORIZED),
+    ] {
+        let response = send(timestamp, signature).await.unwrap();
+        assert_eq!(response.status(), expected, "{}", timestamp as i64 - now as i64);
+    }
+    let response = send(now - 1, sign("s3cret", now - 1)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // the rest of the relay API does not require a signature
+    let status_url = relay_url.join("/eth/v1/builder/status").unwrap();
+    assert_eq!(client.get(status_url).send().await.unwrap().status(), reqwest::StatusCode::OK);
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_data_api_compresses_responses() {
+    let beacon_node =
+        MockBeaconNode { genesis_time: 1_655_733_600, genesis_validators_root: Root::default() };
+    let config = Config { compress_data_responses: true, ..config_for(&beacon_node.spawn()) };
+    let port = config.port;
+    let service = Service::from(Network::Sepolia, config).unwrap();
+    let handle = service.spawn().await.unwrap();
+
+    let relay_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+    wait_for_initialization(&relay_url).await;
+
+    let public_key = SecretKey::try_from([2u8; 32].as_ref()).unwrap().public_key();
+    let public_key = serde_json::to_value(&public_key).unwrap();
+    let mut registration_url = relay_url.join("/relay/v1/data/validator_registration").unwrap();
+    registration_url.query_pairs_mut().append_pair("pubkey", public_key.as_str().unwrap());
+    let client = reqwest::Client::new();
+    let content_encoding = |response: &reqwest::Response| {
+        response.headers().get(reqwest::header::CONTENT_ENCODING).cloned()
+    };
+
+    // the data API is compressed as negotiated by the request
+    for encoding in ["gzip", "br"] {
+        let response = client
+            .get(registration_url.clone())
+            .header(reqwest::header::ACCEPT_ENCODING, encoding)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        assert_eq!(content_encoding(&response).unwrap(), encoding);
+    }
+    let response = client.get(registration_url).send().await.unwrap();
+    assert!(content_encoding(&response).is_none());
+
+    // the builder API never is
+    let builder_status_url = relay_url.join("/relay/v1/builder/status").unwrap();
+    let response = client
+        .get(builder_status_url)
+        .header(reqwest::header::ACCEPT_ENCODING, "gzip")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert!(content_encoding(&response).is_none());
+
+    handle.shutdown().await.unwrap();
+}
+
+// Requests `url` with `client` until it succeeds, e.g. once a connection to the relay is free.
+async fn send_until_ok(client: &reqwest::Client, url: &Url) -> bool {
+    for _ in 0..20 {
+        if client.get(url.clone()).send().await.is_ok() {
+            return true
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_service_limits_connections() {
+    let beacon_node =
+        MockBeaconNode { genesis_time: 1_655_733_600, genesis_validators_root: Root::default() };
+    let config = Config {
+        max_connections: Some(1),
+        tcp_keepalive_secs: Some(60),
+        ..config_for(&beacon_node.spawn())
+    };
+    let port = config.port;
+    let service = Service::from(Network::Sepolia, config).unwrap();
+    let handle = service.spawn().await.unwrap();
+
+    let relay_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+    wait_for_initialization(&relay_url).await;
+    let status_url = relay_url.join("/eth/v1/builder/status").unwrap();
+
+    // the client keeps its connection alive, holding the only one allowed
+    let client = reqwest::Client::new();
+    assert!(send_until_ok(&client, &status_url).await);
+    assert!(reqwest::Client::new().get(status_url.clone()).send().await.is_err());
+    assert!(client.get(status_url.clone()).send().await.is_ok());
+
+    drop(client);
+    assert!(send_until_ok(&reqwest::Client::new(), &status_url).await);
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_service_refuses_beacon_node_on_another_chain() {
+    let genesis_validators_root = Root::try_from([7u8; 32].as_ref()).unwrap();
+    let beacon_node = MockBeaconNode { genesis_time: 1_655_733_600, genesis_validators_root };
+    let config = Config {
+        allow_genesis_mismatch: false,
+        initialize_max_attempts: Some(5),
+        ..config_for(&beacon_node.spawn())
+    };
+    let service = Service::from(Network::Sepolia, config).unwrap();
+    let handle = service.spawn().await.unwrap();
+
+    // fails on the first attempt rather than retrying
+    let exit = tokio::time::timeout(Duration::from_secs(2), handle).await.unwrap();
+    assert!(matches!(
+        exit,
+        ServiceExit::RelayInitializationFailed(Error::GenesisMismatch { provided, .. })
+            if provided == Root::try_from([7u8; 32].as_ref()).unwrap()
+    ));
+}
@@ -0,0 +1,249 @@
+use beacon_api_client::{mainnet::Client, Error as ApiError};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+use url::Url;
+
+// Amount of time to wait before retrying a call against the next beacon node.
+const FAILOVER_BACKOFF: Duration = Duration::from_millis(100);
+// Bounds on the amount of time to wait between rebuilding the client of an unreachable node.
+const RECONNECT_MIN_BACKOFF: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// Builds a new client for the beacon node at the given endpoint.
+type Connect = dyn Fn(&Url) -> Result<Client, ApiError> + Send + Sync;
+
+// Returns `true` if `err` indicates the beacon node could not be reached at all,
+// as opposed to an error response from a reachable node (e.g. a 4xx status).
+pub fn is_transport_error(err: &ApiError) -> bool {
+    match err {
+        ApiError::Http(err) => err.is_connect() || err.is_timeout(),
+        _ => false,
+    }
+}
+
+/// State of the connection to a single beacon node in a [`BeaconNodePool`]
+#[derive(Debug, Clone)]
+pub struct ConnectionStatus {
+    pub endpoint: Url,
+    pub is_active: bool,
+    /// Number of calls in a row that could not reach the node
+    pub consecutive_failures: u32,
+    /// Number of times the client was rebuilt since the node was last reachable
+    pub reconnections: u32,
+}
+
+impl ConnectionStatus {
+    pub fn is_connected(&self) -> bool {
+        self.consecutive_failures == 0
+    }
+}
+
+struct Node {
+    client: RwLock<Client>,
+    consecutive_failures: AtomicU32,
+    reconnections: AtomicU32,
+    // earliest time the client may next be rebuilt
+    next_reconnect: Mutex<Option<Instant>>,
+}
+
+impl Node {
+    fn new(client: Client) -> Self {
+        Self {
+            client: RwLock::new(client),
+            consecutive_failures: Default::default(),
+            reconnections: Default::default(),
+            next_reconnect: Default::default(),
+        }
+    }
+
+    fn client(&self) -> Client {
+        self.client.read().clone()
+    }
+
+    fn endpoint(&self) -> Url {
+        self.client.read().endpoint.clone()
+    }
+}
+
+struct Reconnection {
+    failure_threshold: u32,
+    connect: Box<Connect>,
+}
+
+/// A set of redundant beacon nodes.
+///
+/// Calls are made against the currently active node and fail over to the
+/// next node (in round-robin order) if the active node can not be reached.
+/// If configured with [`BeaconNodePool::with_reconnection`], the client of a node
+/// that can not be reached repeatedly is rebuilt, e.g. to recover from a restart
+/// of the node leaving only dead connections behind.
+#[derive(Clone)]
+pub struct BeaconNodePool {
+    nodes: Arc<Vec<Node>>,
+    active: Arc<AtomicUsize>,
+    reconnection: Option<Arc<Reconnection>>,
+}
+
+impl BeaconNodePool {
+    pub fn new(clients: Vec<Client>) -> Self {
+        assert!(!clients.is_empty(), "must provide at least one beacon node");
+        let nodes = clients.into_iter().map(Node::new).collect();
+        Self { nodes: Arc::new(nodes), active: Default::default(), reconnection: None }
+    }
+
+    /// Rebuilds the client of a node with `connect` once `failure_threshold` calls in a
+    /// row could not reach it, backing off between attempts while it stays unreachable
+    pub fn with_reconnection(
+        mut self,
+        failure_threshold: u32,
+        connect: impl Fn(&Url) -> Result<Client, ApiError> + Send + Sync + 'static,
+    ) -> Self {
+        let failure_threshold = failure_threshold.max(1);
+        self.reconnection =
+            Some(Arc::new(Reconnection { failure_threshold, connect: Box::new(connect) }));
+        self
+    }
+
+    fn active_index(&self) -> usize {
+        self.active.load(Ordering::Relaxed) % self.nodes.len()
+    }
+
+    /// Returns the client for the currently active beacon node
+    pub fn active(&self) -> Client {
+        self.nodes[self.active_index()].client()
+    }
+
+    /// Returns the endpoint of the currently active beacon node
+    pub fn active_endpoint(&self) -> Url {
+        self.nodes[self.active_index()].endpoint()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the state of the connection to each beacon node
+    pub fn connection_statuses(&self) -> Vec<ConnectionStatus> {
+        let active_index = self.active_index();
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| ConnectionStatus {
+                endpoint: node.endpoint(),
+                is_active: index == active_index,
+                consecutive_failures: node.consecutive_failures.load(Ordering::Relaxed),
+                reconnections: node.reconnections.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    // Advances the active node past `index`, unless another caller has already done so.
+    fn fail_over_from(&self, index: usize) {
+        let next = (index + 1) % self.nodes.len();
+        let _ = self.active.compare_exchange(index, next, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    fn record_success(&self, index: usize) {
+        let node = &self.nodes[index];
+        if node.consecutive_failures.swap(0, Ordering::Relaxed) == 0 {
+            return
+        }
+        *node.next_reconnect.lock() = None;
+        let reconnections = node.reconnections.swap(0, Ordering::Relaxed);
+        if reconnections > 0 {
+            info!(endpoint = %node.endpoint(), reconnections, "reconnected to beacon node");
+        }
+    }
+
+    fn record_failure(&self, index: usize) {
+        let node = &self.nodes[index];
+        let failures = node.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        let Some(reconnection) = self.reconnection.as_ref() else { return };
+        if failures < reconnection.failure_threshold {
+            return
+        }
+
+        let now = Instant::now();
+        {
+            let mut next_reconnect = node.next_reconnect.lock();
+            if next_reconnect.map(|next_reconnect| now < next_reconnect).unwrap_or(false) {
+                return
+            }
+            let reconnections = node.reconnections.fetch_add(1, Ordering::Relaxed);
+            let backoff = RECONNECT_MIN_BACKOFF
+                .saturating_mul(2u32.saturating_pow(reconnections))
+                .min(RECONNECT_MAX_BACKOFF);
+            *next_reconnect = Some(now + backoff);
+        }
+
+        let endpoint = node.endpoint();
+        match (reconnection.connect)(&endpoint) {
+            Ok(client) => {
+                *node.client.write() = client;
+                warn!(%endpoint, failures, "beacon node unreachable; rebuilt connection");
+            }
+            Err(err) => warn!(%err, %endpoint, "could not rebuild connection to beacon node"),
+        }
+    }
+
+    /// Advances to the next beacon node if `err` signals the active node is unreachable
+    pub fn fail_over_if_unreachable(&self, err: &ApiError) {
+        if is_transport_error(err) {
+            let index = self.active_index();
+            self.record_failure(index);
+            self.fail_over_from(index);
+            warn!(%err, endpoint = %self.active_endpoint(), "beacon node unreachable; failed over");
+        }
+    }
+
+    /// Executes `f` against the active beacon node, retrying against each other
+    /// beacon node in turn while the error is a transport error.
+    pub async fn call<T, F, Fut>(&self, f: F) -> Result<T, ApiError>
+    where
+        F: Fn(Client) -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let mut index = self.active_index();
+        let mut attempts = 1;
+        loop {
+            let client = self.nodes[index].client();
+            match f(client).await {
+                Err(err) if is_transport_error(&err) => {
+                    self.record_failure(index);
+                    if attempts >= self.nodes.len() {
+                        return Err(err)
+                    }
+                    self.fail_over_from(index);
+                    index = (index + 1) % self.nodes.len();
+                    attempts += 1;
+                    let endpoint = self.nodes[index].endpoint();
+                    warn!(%err, %endpoint, "beacon node unreachable; retrying with next beacon node");
+                    tokio::time::sleep(FAILOVER_BACKOFF).await;
+                }
+                // NOTE: any response, including an error response, shows the node is reachable
+                result => {
+                    self.record_success(index);
+                    return result
+                }
+            }
+        }
+    }
+}
+
+impl From<Client> for BeaconNodePool {
+    fn from(client: Client) -> Self {
+        Self::new(vec![client])
+    }
+}
@@ -7,14 +7,30 @@ use crate::{
 };
 use axum::http::{Method, StatusCode};
 use beacon_api_client::{
-    api_error_or_ok, mainnet::Client as BeaconApiClient, ApiResult, Error as ApiError,
-    VersionedValue, ETH_CONSENSUS_VERSION_HEADER,
+    api_error_or_ok, mainnet::Client as BeaconApiClient, Error as ApiError, VersionedValue,
+    ETH_CONSENSUS_VERSION_HEADER,
 };
 
+// Returns the error served in the body of a `response` with a non-success status, keeping
+// the raw body as the message if it is not a well-formed API error.
+async fn error_from_response(response: reqwest::Response) -> Error {
+    let code = response.status();
+    let body = match response.text().await {
+        Ok(body) => body,
+        Err(err) => return ApiError::Http(err).into(),
+    };
+    let err = serde_json::from_str::<beacon_api_client::ApiError>(&body)
+        .unwrap_or(beacon_api_client::ApiError::ErrorMessage { code, message: body });
+    ApiError::from(err).into()
+}
+
 /// A `Client` for a service implementing the Builder APIs.
 /// Note that `Client` does not implement the `BlindedBlockProvider` trait so that
 /// it can provide more flexibility to callers with respect to the types
 /// it accepts.
+///
+/// Responses with a non-success status are returned as an [`Error::Api`] carrying
+/// the status code and error message served by the remote.
 #[derive(Clone)]
 pub struct Client {
     api: BeaconApiClient,
@@ -35,7 +51,10 @@ impl Client {
         registrations: &[SignedValidatorRegistration],
     ) -> Result<(), Error> {
         let response = self.api.http_post("/eth/v1/builder/validators", &registrations).await?;
-        api_error_or_ok(response).await.map_err(From::from)
+        if !response.status().is_success() {
+            return Err(error_from_response(response).await)
+        }
+        Ok(())
     }
 
     pub async fn fetch_best_bid(
@@ -51,13 +70,13 @@ impl Client {
         if response.status() == StatusCode::NO_CONTENT {
             return Err(Error::NoBidPrepared(auction_request.clone()))
         }
+        if !response.status().is_success() {
+            return Err(error_from_response(response).await)
+        }
 
-        let result: ApiResult<VersionedValue<SignedBuilderBid>> =
+        let result: VersionedValue<SignedBuilderBid> =
             response.json().await.map_err(beacon_api_client::Error::Http)?;
-        match result {
-            ApiResult::Ok(result) => Ok(result.data),
-            ApiResult::Err(err) => Err(Error::Api(err.into())),
-        }
+        Ok(result.data)
     }
 
     pub async fn open_bid(
@@ -79,13 +98,14 @@ impl Client {
             .await
             .map_err(beacon_api_client::Error::Http)?;
 
+        if !response.status().is_success() {
+            return Err(error_from_response(response).await)
+        }
+
         let result = response
-            .json::<ApiResult<VersionedValue<AuctionContents>>>()
+            .json::<VersionedValue<AuctionContents>>()
             .await
             .map_err(beacon_api_client::Error::Http)?;
-        match result {
-            ApiResult::Ok(result) => Ok(result.data),
-            ApiResult::Err(err) => Err(ApiError::from(err).into()),
-        }
+        Ok(result.data)
     }
 }
@@ -0,0 +1,419 @@
+use crate::{
+    error::Error,
+    types::{
+        deneb::BlobsBundle, BidTrace, ExecutionPayload, RegistrationWithPreferences,
+        SignedBidSubmission, SignedBlindedBeaconBlock, SignedValidatorRegistration,
+    },
+};
+use axum::{
+    http::{header, HeaderMap, HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use beacon_api_client::ETH_CONSENSUS_VERSION_HEADER;
+use ethereum_consensus::{
+    bellatrix::mainnet as bellatrix, capella::mainnet as capella, deneb::mainnet as deneb,
+    phase0::mainnet::VALIDATOR_REGISTRY_LIMIT, primitives::BlsSignature, ssz::prelude::*, Fork,
+};
+
+const JSON_MEDIA_TYPE: &str = "application/json";
+const SSZ_MEDIA_TYPE: &str = "application/octet-stream";
+
+/// Encoding of the body of a request to or response from the builder API
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Json,
+    Ssz,
+}
+
+impl Encoding {
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Self::Json => JSON_MEDIA_TYPE,
+            Self::Ssz => SSZ_MEDIA_TYPE,
+        }
+    }
+
+    // Returns the encoding for a media type like `application/json; charset=utf-8`, if supported.
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        let essence = media_type.split(';').next().unwrap_or_default().trim();
+        if essence.eq_ignore_ascii_case(JSON_MEDIA_TYPE) {
+            Some(Self::Json)
+        } else if essence.eq_ignore_ascii_case(SSZ_MEDIA_TYPE) {
+            Some(Self::Ssz)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the encoding of a request body from its `Content-Type`, which is JSON if absent
+    pub fn of_request(headers: &HeaderMap) -> Result<Self, Error> {
+        let Some(content_type) = headers.get(header::CONTENT_TYPE) else { return Ok(Self::Json) };
+        content_type
+            .to_str()
+            .ok()
+            .and_then(Self::from_media_type)
+            .ok_or_else(|| Error::UnsupportedContentType(format!("{content_type:?}")))
+    }
+
+    /// Returns the encoding of a request body from its `Content-Type`, which must be present
+    pub fn of_typed_request(headers: &HeaderMap) -> Result<Self, Error> {
+        if !headers.contains_key(header::CONTENT_TYPE) {
+            return Err(Error::UnsupportedContentType("missing `Content-Type` header".into()))
+        }
+        Self::of_request(headers)
+    }
+
+    /// Returns the supported encoding the `Accept` header of a request prefers, falling back
+    /// to JSON if the header is absent or accepts neither encoding
+    ///
+    /// Encodings accepted with the same quality are preferred in the order they are listed.
+    pub fn accepted(headers: &HeaderMap) -> Self {
+        let mut preferred: Option<(Self, f32)> = None;
+        let values = headers.get_all(header::ACCEPT).iter().filter_map(|value| value.to_str().ok());
+        for media_range in values.flat_map(|value| value.split(',')) {
+            let mut parameters = media_range.split(';');
+            let Some(encoding) = parameters.next().and_then(Self::from_media_type) else {
+                continue
+            };
+            let quality = parameters
+                .find_map(|parameter| parameter.trim().strip_prefix("q="))
+                .and_then(|quality| quality.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let is_preferred = preferred.map(|(_, best)| quality > best).unwrap_or(true);
+            if quality > 0.0 && is_preferred {
+                preferred = Some((encoding, quality));
+            }
+        }
+        preferred.map(|(encoding, _)| encoding).unwrap_or_default()
+    }
+}
+
+// Name of the `Eth-Consensus-Version` header, normalized to lowercase as header names must be.
+fn consensus_version_header() -> HeaderName {
+    HeaderName::from_static("eth-consensus-version")
+}
+
+// SSZ layouts of a bid submission for each fork, as SSZ does not identify the fork of the
+// payload it carries
+#[derive(Debug, Default, SimpleSerialize)]
+struct BellatrixBidSubmission {
+    message: BidTrace,
+    execution_payload: bellatrix::ExecutionPayload,
+    signature: BlsSignature,
+}
+
+#[derive(Debug, Default, SimpleSerialize)]
+struct CapellaBidSubmission {
+    message: BidTrace,
+    execution_payload: capella::ExecutionPayload,
+    signature: BlsSignature,
+}
+
+#[derive(Debug, Default, SimpleSerialize)]
+struct DenebBidSubmission {
+    message: BidTrace,
+    execution_payload: deneb::ExecutionPayload,
+    blobs_bundle: BlobsBundle,
+    signature: BlsSignature,
+}
+
+fn invalid_json(err: serde_json::Error) -> Error {
+    Error::InvalidRequestBody(err.to_string())
+}
+
+fn invalid_ssz(err: DeserializeError) -> Error {
+    Error::InvalidRequestBody(err.to_string())
+}
+
+// Returns the fork named in the `Eth-Consensus-Version` header of a request with the `headers`.
+fn consensus_version(headers: &HeaderMap) -> Result<Fork, Error> {
+    let version = headers
+        .get(ETH_CONSENSUS_VERSION_HEADER)
+        .and_then(|version| version.to_str().ok())
+        .ok_or_else(|| {
+            let reason = format!("missing `{ETH_CONSENSUS_VERSION_HEADER}` header");
+            Error::InvalidRequestBody(reason)
+        })?;
+    serde_json::from_value(version.to_lowercase().into()).map_err(|_| {
+        Error::InvalidRequestBody(format!("unsupported consensus version `{version}`"))
+    })
+}
+
+/// Decodes a batch of validator registrations from a request `body` with the given `headers`
+///
+/// SSZ bodies are a list of signed registrations, which carry no preferences.
+pub fn decode_registrations(
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<Vec<RegistrationWithPreferences>, Error> {
+    match Encoding::of_typed_request(headers)? {
+        Encoding::Json => serde_json::from_slice(body).map_err(invalid_json),
+        Encoding::Ssz => {
+            let registrations =
+                List::<SignedValidatorRegistration, VALIDATOR_REGISTRY_LIMIT>::deserialize(body)
+                    .map_err(invalid_ssz)?;
+            let registrations = registrations
+                .iter()
+                .map(|registration| RegistrationWithPreferences {
+                    registration: registration.clone(),
+                    preferences: Default::default(),
+                })
+                .collect();
+            Ok(registrations)
+        }
+    }
+}
+
+/// Decodes a builder's bid submission from a request `body` with the given `headers`
+///
+/// SSZ bodies are decoded as the fork named in the `Eth-Consensus-Version` header.
+pub fn decode_bid_submission(
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<SignedBidSubmission, Error> {
+    match Encoding::of_typed_request(headers)? {
+        Encoding::Json => serde_json::from_slice(body).map_err(invalid_json),
+        Encoding::Ssz => {
+            let signed_submission = match consensus_version(headers)? {
+                Fork::Bellatrix => {
+                    let BellatrixBidSubmission { message, execution_payload, signature } =
+                        BellatrixBidSubmission::deserialize(body).map_err(invalid_ssz)?;
+                    SignedBidSubmission {
+                        message,
+                        execution_payload: ExecutionPayload::Bellatrix(execution_payload),
+                        blobs_bundle: None,
+                        signature,
+                    }
+                }
+                Fork::Capella => {
+                    let CapellaBidSubmission { message, execution_payload, signature } =
+                        CapellaBidSubmission::deserialize(body).map_err(invalid_ssz)?;
+                    SignedBidSubmission {
+                        message,
+                        execution_payload: ExecutionPayload::Capella(execution_payload),
+                        blobs_bundle: None,
+                        signature,
+                    }
+                }
+                Fork::Deneb => {
+                    let DenebBidSubmission { message, execution_payload, blobs_bundle, signature } =
+                        DenebBidSubmission::deserialize(body).map_err(invalid_ssz)?;
+                    SignedBidSubmission {
+                        message,
+                        execution_payload: ExecutionPayload::Deneb(execution_payload),
+                        blobs_bundle: Some(blobs_bundle),
+                        signature,
+                    }
+                }
+                fork => {
+                    let reason = format!("no bid submissions exist for the {fork} fork");
+                    return Err(Error::InvalidRequestBody(reason))
+                }
+            };
+            Ok(signed_submission)
+        }
+    }
+}
+
+/// Decodes a signed blinded beacon block from a request `body` with the given `headers`
+///
+/// SSZ does not identify the fork of the block, so SSZ bodies are decoded as the fork named
+/// in the `Eth-Consensus-Version` header.
+pub fn decode_signed_blinded_block(
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<SignedBlindedBeaconBlock, Error> {
+    match Encoding::of_request(headers)? {
+        Encoding::Json => serde_json::from_slice(body).map_err(invalid_json),
+        Encoding::Ssz => {
+            let signed_block = match consensus_version(headers)? {
+                Fork::Bellatrix => SignedBlindedBeaconBlock::Bellatrix(
+                    bellatrix::SignedBlindedBeaconBlock::deserialize(body).map_err(invalid_ssz)?,
+                ),
+                Fork::Capella => SignedBlindedBeaconBlock::Capella(
+                    capella::SignedBlindedBeaconBlock::deserialize(body).map_err(invalid_ssz)?,
+                ),
+                Fork::Deneb => SignedBlindedBeaconBlock::Deneb(
+                    deneb::SignedBlindedBeaconBlock::deserialize(body).map_err(invalid_ssz)?,
+                ),
+                fork => {
+                    let reason = format!("no blinded blocks exist for the {fork} fork");
+                    return Err(Error::InvalidRequestBody(reason))
+                }
+            };
+            Ok(signed_block)
+        }
+    }
+}
+
+/// Returns the SSZ encoding of `value` from the `version` fork as the body of a response
+pub fn ssz_response<T: Serialize>(version: Fork, value: &T) -> Response {
+    let mut body = Vec::new();
+    value.serialize(&mut body).expect("value is serializable");
+    let mut response = body.into_response();
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(SSZ_MEDIA_TYPE));
+    if let Ok(version) = HeaderValue::from_str(&version.to_string()) {
+        headers.insert(consensus_version_header(), version);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{builder_bid, BuilderBid, ExecutionPayloadHeader, SignedBuilderBid};
+    use ethereum_consensus::primitives::U256;
+
+    // SSZ does not identify the fork of a bid, so bids are decoded as those of a known fork.
+    #[derive(Debug, SimpleSerialize)]
+    struct SignedCapellaBuilderBid {
+        message: builder_bid::capella::BuilderBid,
+        signature: BlsSignature,
+    }
+
+    fn headers(name: HeaderName, value: &'static str) -> HeaderMap {
+        HeaderMap::from_iter([(name, HeaderValue::from_static(value))])
+    }
+
+    fn signed_bid() -> SignedBuilderBid {
+        let message = BuilderBid::Capella(builder_bid::capella::BuilderBid {
+            header: ExecutionPayloadHeader::Capella(Default::default()),
+            value: U256::from(1337),
+            public_key: Default::default(),
+        });
+        SignedBuilderBid { message, signature: Default::default() }
+    }
+
+    #[test]
+    fn test_request_encoding() {
+        assert_eq!(Encoding::of_request(&HeaderMap::new()).unwrap(), Encoding::Json);
+        let content_type = headers(header::CONTENT_TYPE, "application/json; charset=utf-8");
+        assert_eq!(Encoding::of_request(&content_type).unwrap(), Encoding::Json);
+        let content_type = headers(header::CONTENT_TYPE, "application/octet-stream");
+        assert_eq!(Encoding::of_request(&content_type).unwrap(), Encoding::Ssz);
+        let content_type = headers(header::CONTENT_TYPE, "text/plain");
+        let result = Encoding::of_request(&content_type);
+        assert!(matches!(result, Err(Error::UnsupportedContentType(..))));
+    }
+
+    #[test]
+    fn test_accepted_encoding() {
+        assert_eq!(Encoding::accepted(&HeaderMap::new()), Encoding::Json);
+        for (accept, expected) in [
+            ("application/octet-stream", Encoding::Ssz),
+            ("application/json", Encoding::Json),
+            ("*/*", Encoding::Json),
+            ("text/html, application/octet-stream", Encoding::Ssz),
+            ("application/octet-stream;q=1.0,application/json;q=0.9", Encoding::Ssz),
+            ("application/octet-stream;q=0.5, application/json", Encoding::Json),
+            ("application/json, application/octet-stream", Encoding::Json),
+            ("application/octet-stream;q=0", Encoding::Json),
+        ] {
+            assert_eq!(Encoding::accepted(&headers(header::ACCEPT, accept)), expected, "{accept}");
+        }
+    }
+
+    #[test]
+    fn test_signed_builder_bid_round_trip() {
+        let signed_bid = signed_bid();
+        let BuilderBid::Capella(bid) = &signed_bid.message else { unreachable!() };
+
+        let json = serde_json::to_vec(&signed_bid).unwrap();
+        let decoded: SignedBuilderBid = serde_json::from_slice(&json).unwrap();
+        assert_eq!(decoded.message, signed_bid.message);
+        assert_eq!(decoded.signature, signed_bid.signature);
+
+        let mut ssz = Vec::new();
+        signed_bid.serialize(&mut ssz).unwrap();
+        let decoded = SignedCapellaBuilderBid::deserialize(&ssz).unwrap();
+        assert_eq!(&decoded.message, bid);
+        assert_eq!(decoded.signature, signed_bid.signature);
+    }
+
+    #[test]
+    fn test_decode_bid_submission() {
+        let submission = CapellaBidSubmission {
+            message: BidTrace { slot: 1, value: U256::from(1337), ..Default::default() },
+            ..Default::default()
+        };
+        let mut ssz = Vec::new();
+        submission.serialize(&mut ssz).unwrap();
+
+        let json_headers = headers(header::CONTENT_TYPE, "application/json");
+        let mut headers = headers(header::CONTENT_TYPE, "application/octet-stream");
+        let result = decode_bid_submission(&headers, &ssz);
+        assert!(matches!(result, Err(Error::InvalidRequestBody(..))));
+
+        headers.insert(consensus_version_header(), HeaderValue::from_static("capella"));
+        let decoded = decode_bid_submission(&headers, &ssz).unwrap();
+        assert!(decoded.blobs_bundle.is_none());
+        let json = serde_json::to_vec(&decoded).unwrap();
+        let ExecutionPayload::Capella(execution_payload) = decoded.execution_payload else {
+            unreachable!()
+        };
+        let decoded = CapellaBidSubmission {
+            message: decoded.message,
+            execution_payload,
+            signature: decoded.signature,
+        };
+        let mut encoded = Vec::new();
+        decoded.serialize(&mut encoded).unwrap();
+        assert_eq!(encoded, ssz);
+
+        let decoded = decode_bid_submission(&json_headers, &json).unwrap();
+        assert_eq!(decoded.message.value, U256::from(1337));
+        let result = decode_bid_submission(&HeaderMap::new(), &json);
+        assert!(matches!(result, Err(Error::UnsupportedContentType(..))));
+    }
+
+    #[test]
+    fn test_decode_registrations() {
+        let registrations = (1..=2)
+            .map(|gas_limit| {
+                let mut registration = SignedValidatorRegistration::default();
+                registration.message.gas_limit = gas_limit;
+                registration
+            })
+            .collect::<Vec<_>>();
+        let list =
+            List::<SignedValidatorRegistration, VALIDATOR_REGISTRY_LIMIT>::try_from(registrations)
+                .unwrap();
+        let mut ssz = Vec::new();
+        list.serialize(&mut ssz).unwrap();
+
+        let content_type = headers(header::CONTENT_TYPE, "application/octet-stream");
+        let decoded = decode_registrations(&content_type, &ssz).unwrap();
+        let gas_limits = decoded
+            .iter()
+            .map(|registration| registration.registration.message.gas_limit)
+            .collect::<Vec<_>>();
+        assert_eq!(gas_limits, vec![1, 2]);
+        assert!(decoded.iter().all(|registration| registration.preferences == Default::default()));
+
+        let result = decode_registrations(&content_type, &ssz[1..]);
+        assert!(matches!(result, Err(Error::InvalidRequestBody(..))));
+        let result = decode_registrations(&HeaderMap::new(), b"[]");
+        assert!(matches!(result, Err(Error::UnsupportedContentType(..))));
+    }
+
+    #[test]
+    fn test_decode_signed_blinded_block() {
+        let signed_block = capella::SignedBlindedBeaconBlock::default();
+        let mut ssz = Vec::new();
+        signed_block.serialize(&mut ssz).unwrap();
+
+        let mut headers = headers(header::CONTENT_TYPE, "application/octet-stream");
+        let result = decode_signed_blinded_block(&headers, &ssz);
+        assert!(matches!(result, Err(Error::InvalidRequestBody(..))));
+
+        headers.insert(consensus_version_header(), HeaderValue::from_static("capella"));
+        let decoded = decode_signed_blinded_block(&headers, &ssz).unwrap();
+        assert!(matches!(decoded, SignedBlindedBeaconBlock::Capella(..)));
+
+        let json = serde_json::to_vec(&SignedBlindedBeaconBlock::Capella(signed_block)).unwrap();
+        let decoded = decode_signed_blinded_block(&HeaderMap::new(), &json).unwrap();
+        assert!(matches!(decoded, SignedBlindedBeaconBlock::Capella(..)));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod client;
+pub(crate) mod encoding;
 pub mod server;
@@ -1,21 +1,27 @@
 use crate::{
-    blinded_block_provider::BlindedBlockProvider,
+    blinded_block_provider::{
+        api::encoding::{
+            decode_registrations, decode_signed_blinded_block, ssz_response, Encoding,
+        },
+        BlindedBlockProvider,
+    },
     error::Error,
     types::{
-        AuctionContents, AuctionRequest, SignedBlindedBeaconBlock, SignedBuilderBid,
-        SignedValidatorRegistration,
+        AuctionRequest, RegistrationResponse, RegistrationWithPreferences, RejectedRegistration,
     },
+    validator_registry,
 };
 use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Bytes,
+    extract::{rejection::PathRejection, Json, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::{get, post, IntoMakeService},
     Router,
 };
 use beacon_api_client::VersionedValue;
 use hyper::server::conn::AddrIncoming;
-use std::net::{Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, SocketAddr};
 use tokio::task::JoinHandle;
 use tracing::{error, info, trace};
 
@@ -26,53 +32,115 @@ pub(crate) async fn handle_status_check() -> impl IntoResponse {
     StatusCode::OK
 }
 
+// Serves a request path the route could not extract as an error like any other.
+fn invalid_path(rejection: PathRejection) -> Error {
+    Error::InvalidRequestPath(rejection.body_text())
+}
+
+// Lists the `errors` of a batch of `count` registrations alongside the number accepted and the
+// number ignored as duplicates, succeeding if any registration was accepted.
+fn registration_errors_response(
+    count: usize,
+    errors: Vec<(usize, validator_registry::Error)>,
+) -> Response {
+    let mut duplicates = 0;
+    let mut rejected = vec![];
+    for (index, err) in errors {
+        match err {
+            validator_registry::Error::DuplicateRegistration(..) => duplicates += 1,
+            err => rejected.push(RejectedRegistration { index, reason: err.to_string() }),
+        }
+    }
+    let accepted = count.saturating_sub(rejected.len() + duplicates);
+    let code = if accepted > 0 { StatusCode::OK } else { StatusCode::BAD_REQUEST };
+    (code, Json(RegistrationResponse { accepted, rejected, duplicates })).into_response()
+}
+
 pub(crate) async fn handle_validator_registration<B: BlindedBlockProvider>(
     State(builder): State<B>,
-    Json(mut registrations): Json<Vec<SignedValidatorRegistration>>,
-) -> Result<(), Error> {
-    trace!(count = registrations.len(), "processing validator registrations");
-    builder.register_validators(&mut registrations).await.map_err(From::from)
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, Error> {
+    let registrations = decode_registrations(&headers, &body)?;
+    let count = registrations.len();
+    trace!(count, "processing validator registrations");
+    let (mut registrations, preferences): (Vec<_>, Vec<_>) = registrations
+        .into_iter()
+        .map(|RegistrationWithPreferences { registration, preferences }| {
+            (registration, preferences)
+        })
+        .unzip();
+    match builder.register_validators_with_preferences(&mut registrations, &preferences).await {
+        Ok(()) => {}
+        Err(Error::RegistrationErrors(errors)) => {
+            return Ok(registration_errors_response(count, errors))
+        }
+        Err(err) => return Err(err),
+    }
+    if builder.queues_registrations() {
+        Ok(StatusCode::ACCEPTED.into_response())
+    } else {
+        Ok(StatusCode::OK.into_response())
+    }
 }
 
 pub(crate) async fn handle_fetch_bid<B: BlindedBlockProvider>(
     State(builder): State<B>,
-    Path(auction_request): Path<AuctionRequest>,
-) -> Result<Json<VersionedValue<SignedBuilderBid>>, Error> {
-    let signed_bid = builder.fetch_best_bid(&auction_request).await?;
-    trace!(%auction_request, %signed_bid, "returning bid");
-    let version = signed_bid.version();
-    let response = VersionedValue { version, data: signed_bid, meta: Default::default() };
-    Ok(Json(response))
+    auction_request: Result<Path<AuctionRequest>, PathRejection>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let Path(auction_request) = auction_request.map_err(invalid_path)?;
+    let encoding = Encoding::accepted(&headers);
+    trace!(%auction_request, ?encoding, "returning bid");
+    match encoding {
+        Encoding::Json => {
+            let body = builder.fetch_best_bid_json(&auction_request).await?;
+            Ok(([(header::CONTENT_TYPE, encoding.media_type())], body).into_response())
+        }
+        Encoding::Ssz => {
+            let signed_bid = builder.fetch_best_bid(&auction_request).await?;
+            Ok(ssz_response(signed_bid.version(), &signed_bid))
+        }
+    }
 }
 
 pub(crate) async fn handle_open_bid<B: BlindedBlockProvider>(
     State(builder): State<B>,
-    Json(mut block): Json<SignedBlindedBeaconBlock>,
-) -> Result<Json<VersionedValue<AuctionContents>>, Error> {
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, Error> {
+    let mut block = decode_signed_blinded_block(&headers, &body)?;
     let auction_contents = builder.open_bid(&mut block).await?;
     let payload = auction_contents.execution_payload();
     let block_hash = payload.block_hash();
     let slot = block.message().slot();
-    trace!(%slot, %block_hash, "returning payload");
+    let encoding = Encoding::accepted(&headers);
+    trace!(%slot, %block_hash, ?encoding, "returning payload");
     let version = payload.version();
-    let response = VersionedValue { version, data: auction_contents, meta: Default::default() };
-    Ok(Json(response))
+    match encoding {
+        Encoding::Json => {
+            let response =
+                VersionedValue { version, data: auction_contents, meta: Default::default() };
+            Ok(Json(response).into_response())
+        }
+        Encoding::Ssz => Ok(ssz_response(version, &auction_contents)),
+    }
 }
 
 pub struct Server<B: BlindedBlockProvider> {
-    host: Ipv4Addr,
+    host: IpAddr,
     port: u16,
     builder: B,
 }
 
 impl<B: BlindedBlockProvider + Clone + Send + Sync + 'static> Server<B> {
-    pub fn new(host: Ipv4Addr, port: u16, builder: B) -> Self {
+    pub fn new(host: IpAddr, port: u16, builder: B) -> Self {
         Self { host, port, builder }
     }
 
-    /// Configures and returns the axum server
-    pub fn serve(&self) -> BlockProviderServer {
-        let router = Router::new()
+    // Routes the builder API to the handlers of the `builder`.
+    fn router(&self) -> Router {
+        Router::new()
             .route("/eth/v1/builder/status", get(handle_status_check))
             .route("/eth/v1/builder/validators", post(handle_validator_registration::<B>))
             .route(
@@ -80,7 +148,12 @@ impl<B: BlindedBlockProvider + Clone + Send + Sync + 'static> Server<B> {
                 get(handle_fetch_bid::<B>),
             )
             .route("/eth/v1/builder/blinded_blocks", post(handle_open_bid::<B>))
-            .with_state(self.builder.clone());
+            .with_state(self.builder.clone())
+    }
+
+    /// Configures and returns the axum server
+    pub fn serve(&self) -> BlockProviderServer {
+        let router = self.router();
         let addr = SocketAddr::from((self.host, self.port));
         axum::Server::bind(&addr).serve(router.into_make_service())
     }
@@ -97,3 +170,175 @@ impl<B: BlindedBlockProvider + Clone + Send + Sync + 'static> Server<B> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        builder_bid, AuctionContents, BuilderBid, ExecutionPayloadHeader, SignedBlindedBeaconBlock,
+        SignedBuilderBid, SignedValidatorRegistration,
+    };
+    use async_trait::async_trait;
+    use axum::{
+        body::Body,
+        http::{HeaderValue, Request},
+    };
+    use ethereum_consensus::{primitives::U256, ssz::prelude::Serialize};
+    use serde_json::Value;
+    use std::net::Ipv4Addr;
+    use tower::ServiceExt;
+
+    #[derive(Clone)]
+    struct StaticBidProvider(SignedBuilderBid);
+
+    #[async_trait]
+    impl BlindedBlockProvider for StaticBidProvider {
+        // NOTE: rejects the registrations at odd indices
+        async fn register_validators(
+            &self,
+            registrations: &mut [SignedValidatorRegistration],
+        ) -> Result<(), Error> {
+            let errors = (0..registrations.len())
+                .filter(|index| index % 2 == 1)
+                .map(|index| (index, validator_registry::Error::UnknownPubkey))
+                .collect::<Vec<_>>();
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(Error::RegistrationErrors(errors))
+            }
+        }
+
+        async fn fetch_best_bid(&self, _: &AuctionRequest) -> Result<SignedBuilderBid, Error> {
+            Ok(self.0.clone())
+        }
+
+        async fn open_bid(
+            &self,
+            _: &mut SignedBlindedBeaconBlock,
+        ) -> Result<AuctionContents, Error> {
+            unimplemented!("only bids are served")
+        }
+    }
+
+    async fn fetch_bid(provider: &StaticBidProvider, headers: HeaderMap) -> (HeaderMap, Bytes) {
+        let path = Ok(Path(AuctionRequest::default()));
+        let response = handle_fetch_bid(State(provider.clone()), path, headers).await.unwrap();
+        let (parts, body) = response.into_parts();
+        (parts.headers, hyper::body::to_bytes(body).await.unwrap())
+    }
+
+    fn signed_bid() -> SignedBuilderBid {
+        let message = BuilderBid::Capella(builder_bid::capella::BuilderBid {
+            header: ExecutionPayloadHeader::Capella(Default::default()),
+            value: U256::from(1337),
+            public_key: Default::default(),
+        });
+        SignedBuilderBid { message, signature: Default::default() }
+    }
+
+    async fn register(count: usize) -> (StatusCode, Bytes) {
+        let provider = StaticBidProvider(signed_bid());
+        let registrations = vec![RegistrationWithPreferences::default(); count];
+        let headers = HeaderMap::from_iter([(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        )]);
+        let body = Bytes::from(serde_json::to_vec(&registrations).unwrap());
+        let response = handle_validator_registration(State(provider), headers, body).await.unwrap();
+        let (parts, body) = response.into_parts();
+        (parts.status, hyper::body::to_bytes(body).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_validator_registration_reports_rejected_entries() {
+        let (code, body) = register(1).await;
+        assert_eq!(code, StatusCode::OK);
+        assert!(body.is_empty());
+
+        let (code, body) = register(4).await;
+        assert_eq!(code, StatusCode::OK);
+        let response: RegistrationResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response.accepted, 2);
+        let rejected = response.rejected.iter().map(|rejected| rejected.index).collect::<Vec<_>>();
+        assert_eq!(rejected, vec![1, 3]);
+        let reason = validator_registry::Error::UnknownPubkey.to_string();
+        assert!(response.rejected.iter().all(|rejected| rejected.reason == reason));
+
+        // a batch is only rejected as a whole if none of it was accepted
+        let errors = vec![(0, validator_registry::Error::UnknownPubkey)];
+        let response = registration_errors_response(1, errors);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_validator_registration_reports_duplicates() {
+        let errors = vec![
+            (0, validator_registry::Error::DuplicateRegistration(2)),
+            (2, validator_registry::Error::UnknownPubkey),
+        ];
+        let response = registration_errors_response(3, errors);
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let response: RegistrationResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response.accepted, 1);
+        assert_eq!(response.duplicates, 1);
+        let rejected = response.rejected.iter().map(|rejected| rejected.index).collect::<Vec<_>>();
+        assert_eq!(rejected, vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_bid_encoding_follows_accept() {
+        let signed_bid = signed_bid();
+        let provider = StaticBidProvider(signed_bid.clone());
+
+        let (headers, body) = fetch_bid(&provider, HeaderMap::new()).await;
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+        let response: VersionedValue<SignedBuilderBid> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response.data.message, signed_bid.message);
+
+        let accept = "application/octet-stream;q=1.0,application/json;q=0.9";
+        let request_headers =
+            HeaderMap::from_iter([(header::ACCEPT, HeaderValue::from_static(accept))]);
+        let (headers, body) = fetch_bid(&provider, request_headers).await;
+        assert_eq!(headers[header::CONTENT_TYPE], "application/octet-stream");
+        assert_eq!(headers["eth-consensus-version"], "capella");
+        let mut expected = Vec::new();
+        signed_bid.serialize(&mut expected).unwrap();
+        assert_eq!(body.as_ref(), expected.as_slice());
+    }
+
+    // Returns the status and error code served for the `request` by a server of a static bid.
+    async fn error_of(request: Request<Body>) -> (StatusCode, Value) {
+        let server = Server::new(Ipv4Addr::LOCALHOST.into(), 0, StaticBidProvider(signed_bid()));
+        let response = server.router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], status.as_u16());
+        (status, body["error_code"].clone())
+    }
+
+    #[tokio::test]
+    async fn test_malformed_requests_served_as_errors() {
+        let request = Request::get("/eth/v1/builder/header/not-a-slot/0x00/0x00")
+            .body(Body::empty())
+            .unwrap();
+        let (status, error_code) = error_of(request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error_code, "invalid_request_path");
+
+        let request = Request::post("/eth/v1/builder/validators")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from("{"))
+            .unwrap();
+        let (status, error_code) = error_of(request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error_code, "invalid_request_body");
+
+        let request = Request::post("/eth/v1/builder/validators").body(Body::from("[]")).unwrap();
+        let (status, error_code) = error_of(request).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(error_code, "unsupported_content_type");
+    }
+}
@@ -7,11 +7,13 @@ pub use {api::client::Client, api::server::Server};
 use crate::{
     error::Error,
     types::{
-        AuctionContents, AuctionRequest, SignedBlindedBeaconBlock, SignedBuilderBid,
-        SignedValidatorRegistration,
+        AuctionContents, AuctionRequest, ProposerPreferences, SignedBlindedBeaconBlock,
+        SignedBuilderBid, SignedValidatorRegistration,
     },
 };
 use async_trait::async_trait;
+#[cfg(feature = "builder-api")]
+use {axum::body::Bytes, beacon_api_client::VersionedValue};
 
 #[async_trait]
 pub trait BlindedBlockProvider {
@@ -20,11 +22,38 @@ pub trait BlindedBlockProvider {
         registrations: &mut [SignedValidatorRegistration],
     ) -> Result<(), Error>;
 
+    /// Registers validators alongside the `preferences` of each proposer, in the same order
+    /// as the `registrations`, ignoring the preferences unless overridden
+    async fn register_validators_with_preferences(
+        &self,
+        registrations: &mut [SignedValidatorRegistration],
+        _preferences: &[ProposerPreferences],
+    ) -> Result<(), Error> {
+        self.register_validators(registrations).await
+    }
+
+    /// Returns `true` if `register_validators` only queues registrations to be processed
+    /// later, in which case callers are told the registrations were accepted (`202`)
+    /// rather than processed (`200`)
+    fn queues_registrations(&self) -> bool {
+        false
+    }
+
     async fn fetch_best_bid(
         &self,
         auction_request: &AuctionRequest,
     ) -> Result<SignedBuilderBid, Error>;
 
+    /// Returns the best bid for the `auction_request` serialized as the body of the response
+    /// to the proposer, so that implementors may cache it across repeated requests
+    #[cfg(feature = "builder-api")]
+    async fn fetch_best_bid_json(&self, auction_request: &AuctionRequest) -> Result<Bytes, Error> {
+        let signed_bid = self.fetch_best_bid(auction_request).await?;
+        let version = signed_bid.version();
+        let response = VersionedValue { version, data: signed_bid, meta: Default::default() };
+        Ok(serde_json::to_vec(&response).expect("bid is serializable").into())
+    }
+
     async fn open_bid(
         &self,
         signed_block: &mut SignedBlindedBeaconBlock,
@@ -1,6 +1,6 @@
 use crate::{
     blinded_block_relayer::BlindedBlockRelayer,
-    types::{ProposerSchedule, SignedBidSubmission},
+    types::{Compliance, ProposerSchedule, SignedBidSubmission},
     Error,
 };
 use beacon_api_client::{api_error_or_ok, mainnet::Client as BeaconApiClient};
@@ -27,4 +27,15 @@ impl BlindedBlockRelayer for Client {
         let response = self.api.http_post("/relay/v1/builder/blocks", signed_submission).await?;
         api_error_or_ok(response).await.map_err(From::from)
     }
+
+    async fn submit_bid_with_compliance(
+        &self,
+        signed_submission: &mut SignedBidSubmission,
+        compliance: Option<Compliance>,
+    ) -> Result<(), Error> {
+        let Some(compliance) = compliance else { return self.submit_bid(signed_submission).await };
+        let path = format!("/relay/v1/builder/blocks?compliance={}", compliance.as_str());
+        let response = self.api.http_post(&path, signed_submission).await?;
+        api_error_or_ok(response).await.map_err(From::from)
+    }
 }
@@ -1,26 +1,43 @@
 use crate::{
     blinded_block_provider::{
-        api::server::{
-            handle_fetch_bid, handle_open_bid, handle_status_check, handle_validator_registration,
+        api::{
+            encoding::decode_bid_submission,
+            server::{
+                handle_fetch_bid, handle_open_bid, handle_status_check,
+                handle_validator_registration,
+            },
         },
         BlindedBlockProvider,
     },
     blinded_block_relayer::BlindedBlockRelayer,
+    body_limit::reject_oversized_body,
+    connection_limit::{ConnectionLimit, LimitedIncoming},
     error::Error,
-    types::{ProposerSchedule, SignedBidSubmission},
+    rate_limit::{limit_by_source, RateLimiter},
+    request_log::{log_request, RequestObserver},
+    tls::{self, ServerConfig},
+    types::{Compliance, ProposerSchedule},
 };
 use axum::{
-    extract::{Json, State},
-    routing::{get, post, IntoMakeService},
+    body::Bytes,
+    extract::{connect_info::IntoMakeServiceWithConnectInfo, DefaultBodyLimit, Json, Query, State},
+    http::{HeaderMap, StatusCode},
+    middleware,
+    routing::{get, post, MethodRouter},
     Router,
 };
-use hyper::server::conn::AddrIncoming;
-use std::net::{Ipv4Addr, SocketAddr};
+use std::{
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::task::JoinHandle;
 use tracing::{error, info, trace};
 
 /// Type alias for the configured axum server
-pub type BlockrelayServer = axum::Server<AddrIncoming, IntoMakeService<Router>>;
+pub type BlockrelayServer =
+    axum::Server<LimitedIncoming, IntoMakeServiceWithConnectInfo<Router, SocketAddr>>;
 
 async fn handle_get_proposal_schedule<R: BlindedBlockRelayer>(
     State(relay): State<R>,
@@ -29,44 +46,252 @@ async fn handle_get_proposal_schedule<R: BlindedBlockRelayer>(
     Ok(Json(relay.get_proposal_schedule().await?))
 }
 
+// Rejects requests to the `route` with a body over `limit` bytes, if provided.
+fn limit_body<S: Clone + Send + Sync + 'static>(
+    route: MethodRouter<S>,
+    limit: Option<usize>,
+) -> MethodRouter<S> {
+    match limit {
+        Some(limit) => route
+            .layer(DefaultBodyLimit::max(limit))
+            .layer(middleware::from_fn_with_state(limit, reject_oversized_body)),
+        None => route,
+    }
+}
+
+async fn handle_registrations_disabled() -> StatusCode {
+    StatusCode::METHOD_NOT_ALLOWED
+}
+
+// Parameters of a bid submission, outside of the signed submission itself
+#[derive(Debug, Default, serde::Deserialize)]
+struct SubmissionParams {
+    // if absent, the compliance of the builder is unknown
+    compliance: Option<Compliance>,
+}
+
 async fn handle_submit_bid<R: BlindedBlockRelayer>(
     State(relay): State<R>,
-    Json(mut signed_bid_submission): Json<SignedBidSubmission>,
+    Query(params): Query<SubmissionParams>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> Result<(), Error> {
-    trace!("handling bid submission");
-    relay.submit_bid(&mut signed_bid_submission).await
+    let mut signed_bid_submission = decode_bid_submission(&headers, &body)?;
+    trace!(compliance = ?params.compliance, "handling bid submission");
+    relay.submit_bid_with_compliance(&mut signed_bid_submission, params.compliance).await
 }
 
 pub struct Server<R: BlindedBlockRelayer + BlindedBlockProvider> {
-    host: Ipv4Addr,
+    host: IpAddr,
     port: u16,
     relay: R,
+    // additional routes served alongside the relay APIs
+    routes: Router,
+    // additional relays, each served with routes of its own under a path prefix
+    identities: Vec<(String, R, Router)>,
+    registration_rate_limiter: Option<Arc<RateLimiter>>,
+    max_registration_bytes: Option<usize>,
+    max_submission_bytes: Option<usize>,
+    accept_registrations: bool,
+    // if present, terminate TLS with this config rather than serving plain HTTP
+    tls: Option<Arc<ServerConfig>>,
+    // if present, log each request and pass its summary to this observer
+    request_observer: Option<RequestObserver>,
+    connection_limit: ConnectionLimit,
+    tcp_keepalive: Option<Duration>,
 }
 
 impl<R: BlindedBlockRelayer + BlindedBlockProvider + Clone + Send + Sync + 'static> Server<R> {
-    pub fn new(host: Ipv4Addr, port: u16, relay: R) -> Self {
-        Self { host, port, relay }
+    pub fn new(host: IpAddr, port: u16, relay: R) -> Self {
+        Self {
+            host,
+            port,
+            relay,
+            routes: Router::new(),
+            identities: vec![],
+            registration_rate_limiter: None,
+            max_registration_bytes: None,
+            max_submission_bytes: None,
+            accept_registrations: true,
+            tls: None,
+            request_observer: None,
+            connection_limit: ConnectionLimit::default(),
+            tcp_keepalive: None,
+        }
     }
 
-    /// Configures and returns the axum server
-    pub fn serve(&self) -> BlockrelayServer {
-        let router = Router::new()
+    /// Terminates TLS with the given `config`, e.g. from [`load_server_config`]
+    ///
+    /// [`load_server_config`]: crate::tls::load_server_config
+    pub fn with_tls(mut self, config: Arc<ServerConfig>) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Rejects validator registrations with a body over `max_bytes` with `413 Payload Too Large`
+    pub fn with_max_registration_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_registration_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Rejects builder submissions with a body over `max_bytes` with `413 Payload Too Large`
+    pub fn with_max_submission_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_submission_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Responds to validator registrations with `405 Method Not Allowed` rather than processing
+    /// them, e.g. when a separate service registers validators with the relay
+    pub fn without_validator_registrations(mut self) -> Self {
+        self.accept_registrations = false;
+        self
+    }
+
+    /// Limits validator registrations to `rate_per_sec` requests per second from each source IP
+    pub fn with_registration_rate_limit(self, rate_per_sec: u32) -> Self {
+        self.with_registration_rate_limiter(Arc::new(RateLimiter::new(rate_per_sec)))
+    }
+
+    /// Limits validator registrations with the given `limiter`, which may be shared so that
+    /// its rate can be updated while the server is running
+    pub fn with_registration_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.registration_rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Logs the method, matched route, status and latency of each request, also passing them
+    /// to `observer`, e.g. to record latencies as metrics
+    pub fn with_request_logging(mut self, observer: RequestObserver) -> Self {
+        self.request_observer = Some(observer);
+        self
+    }
+
+    /// Refuses connections past `max_connections` open at once by closing them as accepted
+    pub fn with_max_connections(self, max_connections: usize) -> Self {
+        self.with_connection_limit(ConnectionLimit::new(Some(max_connections)))
+    }
+
+    /// Counts the open connections with `limit`, refusing those past its maximum if any
+    ///
+    /// The limit applies to every route of the server, including any given with
+    /// [`Self::with_routes`], so routes that must stay reachable under load, e.g. for health
+    /// checks, are better served from a listener of their own.
+    pub fn with_connection_limit(mut self, limit: ConnectionLimit) -> Self {
+        self.connection_limit = limit;
+        self
+    }
+
+    /// Sends TCP keep-alive probes on connections idle for `idle`, so that connections to
+    /// clients that went away without closing them are dropped
+    pub fn with_tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.tcp_keepalive = Some(idle);
+        self
+    }
+
+    /// Serves the given `routes` in addition to the builder and relay APIs
+    pub fn with_routes(mut self, routes: Router) -> Self {
+        self.routes = self.routes.merge(routes);
+        self
+    }
+
+    /// Serves the builder and relay APIs of `relay` and the given `routes` under `/{prefix}`,
+    /// e.g. at `/{prefix}/eth/v1/builder/status`, alongside those of the primary relay
+    ///
+    /// The body limits, rate limit and request logging of the server apply to every relay.
+    pub fn with_relay_at(mut self, prefix: impl Into<String>, relay: R, routes: Router) -> Self {
+        self.identities.push((prefix.into(), relay, routes));
+        self
+    }
+
+    // Returns the route for validator registrations, rejecting them if they are not accepted.
+    fn validator_registration_route(&self) -> MethodRouter<R> {
+        if !self.accept_registrations {
+            return post(handle_registrations_disabled)
+        }
+        let mut validator_registration =
+            limit_body(post(handle_validator_registration::<R>), self.max_registration_bytes);
+        // NOTE: rate limit before checking the size of the body, as the rate limit is cheaper
+        if let Some(limiter) = self.registration_rate_limiter.clone() {
+            validator_registration = validator_registration
+                .layer(middleware::from_fn_with_state(limiter, limit_by_source));
+        }
+        validator_registration
+    }
+
+    // Returns the builder and relay APIs served by `relay`.
+    fn api_router(&self, relay: R) -> Router {
+        let validator_registration = self.validator_registration_route();
+        Router::new()
             .route("/eth/v1/builder/status", get(handle_status_check))
-            .route("/eth/v1/builder/validators", post(handle_validator_registration::<R>))
+            .route("/eth/v1/builder/validators", validator_registration)
             .route(
                 "/eth/v1/builder/header/:slot/:parent_hash/:public_key",
                 get(handle_fetch_bid::<R>),
             )
             .route("/eth/v1/builder/blinded_blocks", post(handle_open_bid::<R>))
             .route("/relay/v1/builder/validators", get(handle_get_proposal_schedule::<R>))
-            .route("/relay/v1/builder/blocks", post(handle_submit_bid::<R>))
-            .with_state(self.relay.clone());
+            .route(
+                "/relay/v1/builder/blocks",
+                limit_body(post(handle_submit_bid::<R>), self.max_submission_bytes),
+            )
+            .with_state(relay)
+    }
+
+    fn router(&self) -> Router {
+        let mut router = self.api_router(self.relay.clone()).merge(self.routes.clone());
+        for (prefix, relay, routes) in &self.identities {
+            let identity = self.api_router(relay.clone()).merge(routes.clone());
+            router = router.nest(&format!("/{prefix}"), identity);
+        }
+        match self.request_observer.clone() {
+            Some(observer) => router.layer(middleware::from_fn_with_state(observer, log_request)),
+            None => router,
+        }
+    }
+
+    // Binds to the address of the server, counting connections against its limit.
+    fn bind(&self) -> Result<LimitedIncoming, hyper::Error> {
+        let addr = SocketAddr::from((self.host, self.port));
+        LimitedIncoming::bind(&addr, self.connection_limit.clone(), self.tcp_keepalive)
+    }
+
+    /// Configures and returns the axum server, serving plain HTTP regardless of any TLS config
+    ///
+    /// Panics if the server cannot bind to its address.
+    pub fn serve(&self) -> BlockrelayServer {
+        let router = self.router();
+        let incoming = self.bind().unwrap_or_else(|err| {
+            panic!("error binding to {}: {err}", SocketAddr::from((self.host, self.port)))
+        });
+        axum::Server::builder(incoming)
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+    }
+
+    // Spawns the server terminating TLS with `config` on a new task returning the handle for it.
+    fn spawn_tls<F>(&self, config: Arc<ServerConfig>, signal: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let router = self.router();
         let addr = SocketAddr::from((self.host, self.port));
-        axum::Server::bind(&addr).serve(router.into_make_service())
+        let incoming = self.bind();
+        tokio::spawn(async move {
+            info!("listening at {addr} with TLS...");
+            let result = match incoming {
+                Ok(incoming) => tls::serve_incoming(incoming, router, Some(config), signal).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                error!(%err, "error while listening for incoming")
+            }
+        })
     }
 
     /// Spawns the server on a new task returning the handle for it
     pub fn spawn(&self) -> JoinHandle<()> {
+        if let Some(config) = self.tls.clone() {
+            return self.spawn_tls(config, std::future::pending())
+        }
         let server = self.serve();
         let addr = server.local_addr();
         tokio::spawn(async move {
@@ -76,4 +301,25 @@ impl<R: BlindedBlockRelayer + BlindedBlockProvider + Clone + Send + Sync + 'stat
             }
         })
     }
+
+    /// Spawns the server on a new task returning the handle for it
+    ///
+    /// Once `signal` resolves, the server stops accepting new connections and
+    /// the task completes after any in-flight requests are served.
+    pub fn spawn_with_graceful_shutdown<F>(&self, signal: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        if let Some(config) = self.tls.clone() {
+            return self.spawn_tls(config, signal)
+        }
+        let server = self.serve();
+        let addr = server.local_addr();
+        tokio::spawn(async move {
+            info!("listening at {addr}...");
+            if let Err(err) = server.with_graceful_shutdown(signal).await {
+                error!(%err, "error while listening for incoming")
+            }
+        })
+    }
 }
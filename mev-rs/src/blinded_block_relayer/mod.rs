@@ -6,7 +6,7 @@ pub use {api::client::Client, api::server::Server};
 
 use crate::{
     error::Error,
-    types::{ProposerSchedule, SignedBidSubmission},
+    types::{Compliance, ProposerSchedule, SignedBidSubmission},
 };
 use async_trait::async_trait;
 
@@ -15,4 +15,14 @@ pub trait BlindedBlockRelayer {
     async fn get_proposal_schedule(&self) -> Result<Vec<ProposerSchedule>, Error>;
 
     async fn submit_bid(&self, signed_submission: &mut SignedBidSubmission) -> Result<(), Error>;
+
+    /// Submits a bid from a builder declaring the `compliance` of its blocks, if any, ignoring
+    /// the declaration unless overridden
+    async fn submit_bid_with_compliance(
+        &self,
+        signed_submission: &mut SignedBidSubmission,
+        _compliance: Option<Compliance>,
+    ) -> Result<(), Error> {
+        self.submit_bid(signed_submission).await
+    }
 }
@@ -1,6 +1,23 @@
 use std::cmp::Ordering;
 
 pub const GAS_BOUND_DIVISOR: u64 = 1024;
+/// Lowest gas limit of a block allowed by the execution layer
+pub const MIN_GAS_LIMIT: u64 = 5000;
+/// Highest gas limit of a block allowed by the execution layer, per EIP-1985
+pub const MAX_GAS_LIMIT: u64 = i64::MAX as u64;
+
+/// Returns `true` if `gas_limit` is within the bounds allowed by the execution layer
+pub fn is_valid_gas_limit(gas_limit: u64) -> bool {
+    (MIN_GAS_LIMIT..=MAX_GAS_LIMIT).contains(&gas_limit)
+}
+
+/// Returns `true` if `gas_limit` is no more than one adjustment step from the
+/// `preferred_gas_limit`, measured from `gas_limit` as the gas limit of the parent
+/// block is not known here
+pub fn respects_preferred_gas_limit(gas_limit: u64, preferred_gas_limit: u64) -> bool {
+    gas_limit.abs_diff(preferred_gas_limit) < gas_limit / GAS_BOUND_DIVISOR ||
+        gas_limit == preferred_gas_limit
+}
 
 pub fn compute_preferred_gas_limit(preferred_gas_limit: u64, parent_gas_limit: u64) -> u64 {
     match preferred_gas_limit.cmp(&parent_gas_limit) {
@@ -48,4 +65,30 @@ mod tests {
             assert!(verify_limits(t.2, t.1))
         }
     }
+
+    #[test]
+    fn test_is_valid_gas_limit() {
+        assert!(!is_valid_gas_limit(0));
+        assert!(!is_valid_gas_limit(MIN_GAS_LIMIT - 1));
+        assert!(is_valid_gas_limit(MIN_GAS_LIMIT));
+        assert!(is_valid_gas_limit(30_000_000));
+        assert!(is_valid_gas_limit(MAX_GAS_LIMIT));
+        assert!(!is_valid_gas_limit(u64::MAX));
+    }
+
+    #[test]
+    fn test_respects_preferred_gas_limit() {
+        for t in &[
+            // gas limit, preferred, respected
+            (30_000_000, 30_000_000, true),
+            (30_029_295, 30_029_300, true),
+            (29_970_705, 29_970_700, true),
+            (30_000_000, 30_029_295, true),
+            (30_000_000, 30_029_400, false),
+            (30_000_000, 29_970_000, false),
+            (30_000_000, 36_000_000, false),
+        ] {
+            assert_eq!(respects_preferred_gas_limit(t.0, t.1), t.2, "{t:?}");
+        }
+    }
 }
@@ -0,0 +1,53 @@
+use axum::{
+    extract::State,
+    http::{header::CONTENT_LENGTH, HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::debug;
+
+// Returns `true` if the request declares a body longer than `limit` bytes.
+fn declares_oversized_body(headers: &HeaderMap, limit: usize) -> bool {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|length| length > limit as u64)
+        .unwrap_or(false)
+}
+
+/// Middleware rejecting requests with `413 Payload Too Large` if they declare a body over
+/// the limit, before any of the body is read
+///
+/// NOTE: bodies without a declared length are instead limited as they are read, by pairing
+/// this with `axum::extract::DefaultBodyLimit`.
+pub(crate) async fn reject_oversized_body<B>(
+    State(limit): State<usize>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if declares_oversized_body(request.headers(), limit) {
+        debug!(limit, path = %request.uri().path(), "request body too large");
+        StatusCode::PAYLOAD_TOO_LARGE.into_response()
+    } else {
+        next.run(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_declares_oversized_body() {
+        let mut headers = HeaderMap::new();
+        assert!(!declares_oversized_body(&headers, 10));
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(10));
+        assert!(!declares_oversized_body(&headers, 10));
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(11));
+        assert!(declares_oversized_body(&headers, 10));
+    }
+}
@@ -0,0 +1,230 @@
+use axum::extract::connect_info::Connected;
+use hyper::server::{
+    accept::Accept,
+    conn::{AddrIncoming, AddrStream},
+};
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tracing::debug;
+
+/// A change to the connections of a server, passed to a [`ConnectionObserver`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    Opened,
+    Closed,
+    /// A connection was accepted past the limit and closed right away
+    Refused,
+}
+
+/// Called with each change to the connections of a server, e.g. to record them as metrics
+pub type ConnectionObserver = Arc<dyn Fn(ConnectionEvent) + Send + Sync>;
+
+/// Counts the open connections of a server, refusing any past `max_connections` if provided
+///
+/// Clones share the same count, so that it can be read while the server is running.
+#[derive(Clone, Default)]
+pub struct ConnectionLimit {
+    max_connections: Option<usize>,
+    open: Arc<AtomicUsize>,
+    observer: Option<ConnectionObserver>,
+}
+
+impl ConnectionLimit {
+    pub fn new(max_connections: Option<usize>) -> Self {
+        Self { max_connections, ..Default::default() }
+    }
+
+    /// Passes each change to the connections to `observer`
+    pub fn with_observer(mut self, observer: ConnectionObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn max_connections(&self) -> Option<usize> {
+        self.max_connections
+    }
+
+    pub fn open_connections(&self) -> usize {
+        self.open.load(Ordering::Relaxed)
+    }
+
+    fn observe(&self, event: ConnectionEvent) {
+        if let Some(observer) = self.observer.as_ref() {
+            observer(event);
+        }
+    }
+
+    // Counts a new connection, returning a guard releasing it once dropped, or `None` if the
+    // limit is reached.
+    fn try_acquire(&self) -> Option<ConnectionGuard> {
+        let max_connections = self.max_connections.unwrap_or(usize::MAX);
+        let acquired = self
+            .open
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |open| {
+                (open < max_connections).then_some(open + 1)
+            })
+            .is_ok();
+        if !acquired {
+            self.observe(ConnectionEvent::Refused);
+            return None
+        }
+        self.observe(ConnectionEvent::Opened);
+        Some(ConnectionGuard { limit: self.clone() })
+    }
+}
+
+// Releases a connection from the count of its limit once dropped.
+struct ConnectionGuard {
+    limit: ConnectionLimit,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.limit.open.fetch_sub(1, Ordering::Relaxed);
+        self.limit.observe(ConnectionEvent::Closed);
+    }
+}
+
+/// A TCP connection accepted by [`LimitedIncoming`], released from its limit once dropped
+pub struct LimitedConnection {
+    stream: AddrStream,
+    _guard: ConnectionGuard,
+}
+
+impl LimitedConnection {
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.stream.remote_addr()
+    }
+}
+
+impl Connected<&LimitedConnection> for SocketAddr {
+    fn connect_info(target: &LimitedConnection) -> Self {
+        target.remote_addr()
+    }
+}
+
+impl AsyncRead for LimitedConnection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for LimitedConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
+
+/// Accepts TCP connections within a [`ConnectionLimit`], for use with [`axum::Server::builder`]
+///
+/// Connections accepted past the limit are closed right away, so that requests on the open
+/// connections are still served rather than the server running out of file descriptors.
+pub struct LimitedIncoming {
+    incoming: AddrIncoming,
+    limit: ConnectionLimit,
+}
+
+impl LimitedIncoming {
+    /// Binds to `addr`, sending TCP keep-alive probes on connections idle for `tcp_keepalive`
+    /// if provided
+    pub fn bind(
+        addr: &SocketAddr,
+        limit: ConnectionLimit,
+        tcp_keepalive: Option<Duration>,
+    ) -> Result<Self, hyper::Error> {
+        let mut incoming = AddrIncoming::bind(addr)?;
+        incoming.set_keepalive(tcp_keepalive);
+        Ok(Self { incoming, limit })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.incoming.local_addr()
+    }
+}
+
+impl Accept for LimitedIncoming {
+    type Conn = LimitedConnection;
+    type Error = io::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let this = self.get_mut();
+        loop {
+            let stream = match Pin::new(&mut this.incoming).poll_accept(cx) {
+                Poll::Ready(Some(Ok(stream))) => stream,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            match this.limit.try_acquire() {
+                Some(guard) => {
+                    return Poll::Ready(Some(Ok(LimitedConnection { stream, _guard: guard })))
+                }
+                None => {
+                    debug!(remote_addr = %stream.remote_addr(), "refusing connection past limit")
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    #[test]
+    fn test_connection_limit() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let observed = events.clone();
+        let limit = ConnectionLimit::new(Some(2))
+            .with_observer(Arc::new(move |event| observed.lock().push(event)));
+
+        let first = limit.try_acquire().unwrap();
+        let second = limit.try_acquire().unwrap();
+        assert_eq!(limit.open_connections(), 2);
+        assert!(limit.try_acquire().is_none());
+
+        drop(first);
+        assert_eq!(limit.open_connections(), 1);
+        let third = limit.try_acquire().unwrap();
+        assert_eq!(limit.open_connections(), 2);
+        drop((second, third));
+        assert_eq!(limit.open_connections(), 0);
+
+        use ConnectionEvent::*;
+        assert_eq!(*events.lock(), vec![Opened, Opened, Refused, Closed, Opened, Closed, Closed]);
+
+        let unlimited = ConnectionLimit::default();
+        let guards = (0..16).map(|_| unlimited.try_acquire().unwrap()).collect::<Vec<_>>();
+        assert_eq!(unlimited.open_connections(), guards.len());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::types::AuctionRequest;
 use beacon_api_client::Error as ApiError;
 use ethereum_consensus::{
-    primitives::{BlsPublicKey, ExecutionAddress, Hash32, ValidatorIndex},
+    primitives::{BlsPublicKey, ExecutionAddress, Hash32, Root, Slot, ValidatorIndex, U256},
     Error as ConsensusError, Fork,
 };
 use thiserror::Error;
@@ -26,8 +26,8 @@ pub enum RelayError {
     InvalidExecutionPayloadInBlock,
     #[error("validator {0:?} does not have registered fee recipient {1:?}")]
     InvalidFeeRecipient(BlsPublicKey, ExecutionAddress),
-    // #[error("validator {0:?} does not have (adjusted) registered gas limit {1}")]
-    // InvalidGasLimitForProposer(BlsPublicKey, u64),
+    #[error("block has gas limit {2} but validator {0:?} registered gas limit {1}")]
+    InvalidGasLimitForProposer(BlsPublicKey, u64, u64),
     #[error("bid trace declares gas limit of {0:?} but execution payload has {1:?}")]
     InvalidGasLimit(u64, u64),
     #[error("bid trace declares gas usage of {0} but execution payload uses {1}")]
@@ -36,16 +36,64 @@ pub enum RelayError {
     InvalidParentHash(Hash32, Hash32),
     #[error("bid trace declares block hash of {0:?} but execution payload has {1:?}")]
     InvalidBlockHash(Hash32, Hash32),
+    #[error("parent hash {0:?} is not the hash of the execution block at the head of the chain")]
+    ParentNotHead(Hash32),
+    #[error("execution payload has withdrawals root {provided:?} but {expected:?} was expected")]
+    InvalidWithdrawalsRoot { expected: Root, provided: Root },
     #[error("missing auction for {0}")]
     MissingAuction(AuctionRequest),
+    #[error("no bid with block hash {1:?} was served for {0}")]
+    BidNotServed(AuctionRequest, Hash32),
+    #[error("block submission for a Deneb payload is missing its blobs bundle")]
+    MissingBlobsBundle,
+    #[error("{0} payloads are not supported by this relay")]
+    UnsupportedFork(Fork),
     #[error("signed blinded beacon block is invalid or equivocated")]
     InvalidSignedBlindedBeaconBlock,
+    #[error(
+        "signed blinded beacon block for slot {slot} is not signed by proposer {public_key:?}"
+    )]
+    InvalidProposerSignature { slot: Slot, public_key: BlsPublicKey },
+    #[error("block submission is not signed by builder {0:?}")]
+    InvalidBuilderSignature(BlsPublicKey),
     #[error("validator with public key {0:?} is not currently registered")]
     ValidatorNotRegistered(BlsPublicKey),
     #[error("validator with index {0} was not found in consensus")]
     UnknownValidatorIndex(ValidatorIndex),
+    #[error("validator {provided:?} is not the assigned proposer {expected:?} for slot {slot}")]
+    UnassignedProposer { slot: Slot, expected: BlsPublicKey, provided: BlsPublicKey },
+    #[error("proposer duties for slot {0} are not known")]
+    MissingProposerDuties(Slot),
     #[error("builder with public key {0:?} is not currently registered")]
     BuilderNotRegistered(BlsPublicKey),
+    #[error("builder with public key {0:?} is not allowed to submit bids to this relay")]
+    BuilderNotAllowed(BlsPublicKey),
+    #[error("builder {public_key:?} is banned for failures; retry after {retry_after_secs}s")]
+    BuilderBanned { public_key: BlsPublicKey, retry_after_secs: u64 },
+    #[error("requested slot {slot} is too far from the current slot {current_slot}")]
+    SlotOutsideWindow { slot: Slot, current_slot: Slot },
+    #[error("submission for slot {slot} arrived {elapsed_ms}ms in, past the {cutoff_ms}ms cutoff")]
+    SubmissionAfterCutoff { slot: Slot, elapsed_ms: u64, cutoff_ms: u64 },
+    #[error(
+        "submission for slot {slot} is over {max_slots_ahead} slots after slot {current_slot}"
+    )]
+    SubmissionTooFarAhead { slot: Slot, current_slot: Slot, max_slots_ahead: Slot },
+    #[error("block hash {0:?} was already submitted by builder {1:?}")]
+    DuplicateBlockHash(Hash32, BlsPublicKey),
+    #[error("bid value of {0} is below the minimum bid value of {1}")]
+    BidBelowMinimum(U256, U256),
+    #[error("block failed validation: {0}")]
+    InvalidBlock(String),
+    #[error("could not validate block: {0}")]
+    BlockValidationUnavailable(String),
+    #[error("relay is running in observation mode and does not reveal payloads")]
+    ObservationMode,
+    #[error("invalid bid from upstream relay: {0}")]
+    InvalidUpstreamBid(String),
+    #[error("upstream relay {0} timed out")]
+    UpstreamRelayTimeout(String),
+    #[error("payload could not be revealed within {0}ms")]
+    PayloadTimeout(u64),
 }
 
 #[derive(Debug, Error)]
@@ -54,37 +102,425 @@ pub enum Error {
     InvalidFork { expected: Fork, provided: Fork },
     #[error("no bid prepared for request {0}")]
     NoBidPrepared(AuctionRequest),
+    #[error("not ready to serve requests")]
+    NotReady,
+    #[error("too many validator registrations waiting to be processed")]
+    RegistrationQueueFull,
+    #[error("too many requests for payloads being handled")]
+    TooManyPayloadRequests,
+    #[error("too many bid submissions waiting to be stored")]
+    BidQueueFull,
+    #[error("beacon node is still syncing at head slot {0}")]
+    BeaconNodeSyncing(Slot),
+    #[error("beacon node is on a chain with genesis validators root {provided}, not {expected}")]
+    GenesisMismatch { expected: Root, provided: Root },
+    #[error("invalid beacon node URL: {0}")]
+    InvalidBeaconNodeUrl(String),
+    #[error("invalid block validation URL: {0}")]
+    InvalidBlockValidationUrl(String),
+    #[error("invalid upstream relay URL: {0}")]
+    InvalidUpstreamRelayUrl(String),
+    #[error("invalid secret key configuration: {0}")]
+    InvalidSecretKey(String),
+    #[error("invalid relay identity: {0}")]
+    InvalidRelayIdentity(String),
+    #[error("unknown network `{0}`; expected mainnet, sepolia, goerli, holesky or a config path")]
+    UnknownNetwork(String),
+    #[error("admin token must not be empty")]
+    EmptyAdminToken,
+    #[error("signing self-test failed: {0}")]
+    SelfTestFailed(String),
+    #[error("invalid fork schedule: {0}")]
+    InvalidForkSchedule(String),
+    #[error("invalid TLS configuration: {0}")]
+    InvalidTlsConfig(String),
+    #[error("unsupported content type: {0}")]
+    UnsupportedContentType(String),
+    #[error("invalid request body: {0}")]
+    InvalidRequestBody(String),
+    #[error("invalid request path: {0}")]
+    InvalidRequestPath(String),
+    #[error("genesis time override {0} is after the current time")]
+    GenesisTimeInFuture(u64),
+    #[error("port {port} is configured for both the {first} and {second} servers")]
+    PortConflict { port: u16, first: &'static str, second: &'static str },
+    #[error("could not find an ephemeral port to bind to: {0}")]
+    NoEphemeralPort(String),
     #[error(transparent)]
     ValidatorRegistry(#[from] crate::validator_registry::Error),
     #[error(transparent)]
     ProposerScheduler(#[from] crate::proposer_scheduler::Error),
-    #[error("validator registration errors: {0:?}")]
-    RegistrationErrors(Vec<crate::validator_registry::Error>),
+    #[error("registration store error: {0}")]
+    RegistrationStore(String),
+    #[error("validator registration errors (by index in request): {0:?}")]
+    RegistrationErrors(Vec<(usize, crate::validator_registry::Error)>),
     #[error(transparent)]
     Boost(#[from] BoostError),
     #[error(transparent)]
     Relay(#[from] RelayError),
     #[error(transparent)]
     Consensus(#[from] ConsensusError),
+    #[error("request to beacon node timed out: {0}")]
+    BeaconNodeTimeout(ApiError),
     #[error(transparent)]
-    Api(#[from] ApiError),
+    Api(ApiError),
+}
+
+impl From<ApiError> for Error {
+    fn from(err: ApiError) -> Self {
+        match err {
+            ApiError::Http(ref inner) if inner.is_timeout() => Self::BeaconNodeTimeout(err),
+            err => Self::Api(err),
+        }
+    }
+}
+
+/// Stable identifier of the kind of an [`Error`], served alongside its message so that clients
+/// can tell errors apart without parsing the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    InvalidFork,
+    NoBid,
+    NotReady,
+    Overloaded,
+    InvalidConfig,
+    UnsupportedContentType,
+    InvalidRequestBody,
+    InvalidRequestPath,
+    InvalidRegistration,
+    UnknownAuction,
+    UnknownBid,
+    InvalidBid,
+    InvalidBidTrace,
+    InvalidBlock,
+    InvalidFeeRecipient,
+    InvalidGasLimit,
+    MissingBlobsBundle,
+    InvalidSignature,
+    ValidatorNotRegistered,
+    UnknownValidator,
+    UnassignedProposer,
+    BuilderNotAllowed,
+    BuilderBanned,
+    SlotOutsideWindow,
+    SubmissionAfterCutoff,
+    SubmissionTooFarAhead,
+    DuplicateBlockHash,
+    BidBelowMinimum,
+    ObservationMode,
+    PayloadUnavailable,
+    Timeout,
+    UpstreamError,
+    ConsensusError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidFork => "invalid_fork",
+            Self::NoBid => "no_bid",
+            Self::NotReady => "not_ready",
+            Self::Overloaded => "overloaded",
+            Self::InvalidConfig => "invalid_config",
+            Self::UnsupportedContentType => "unsupported_content_type",
+            Self::InvalidRequestBody => "invalid_request_body",
+            Self::InvalidRequestPath => "invalid_request_path",
+            Self::InvalidRegistration => "invalid_registration",
+            Self::UnknownAuction => "unknown_auction",
+            Self::UnknownBid => "unknown_bid",
+            Self::InvalidBid => "invalid_bid",
+            Self::InvalidBidTrace => "invalid_bid_trace",
+            Self::InvalidBlock => "invalid_block",
+            Self::InvalidFeeRecipient => "invalid_fee_recipient",
+            Self::InvalidGasLimit => "invalid_gas_limit",
+            Self::MissingBlobsBundle => "missing_blobs_bundle",
+            Self::InvalidSignature => "invalid_signature",
+            Self::ValidatorNotRegistered => "validator_not_registered",
+            Self::UnknownValidator => "unknown_validator",
+            Self::UnassignedProposer => "unassigned_proposer",
+            Self::BuilderNotAllowed => "builder_not_allowed",
+            Self::BuilderBanned => "builder_banned",
+            Self::SlotOutsideWindow => "slot_outside_window",
+            Self::SubmissionAfterCutoff => "submission_after_cutoff",
+            Self::SubmissionTooFarAhead => "submission_too_far_ahead",
+            Self::DuplicateBlockHash => "duplicate_block_hash",
+            Self::BidBelowMinimum => "bid_below_minimum",
+            Self::ObservationMode => "observation_mode",
+            Self::PayloadUnavailable => "payload_unavailable",
+            Self::Timeout => "timeout",
+            Self::UpstreamError => "upstream_error",
+            Self::ConsensusError => "consensus_error",
+            Self::InternalError => "internal_error",
+        }
+    }
+}
+
+impl BoostError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::BidPublicKeyMismatch { .. } => ErrorCode::InvalidBid,
+            Self::MissingOpenBid => ErrorCode::UnknownBid,
+            Self::CouldNotRegister => ErrorCode::UpstreamError,
+            Self::MissingPayload(..) => ErrorCode::PayloadUnavailable,
+        }
+    }
+}
+
+impl RelayError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::InvalidAuctionRequest(..) | Self::MissingAuction(..) => ErrorCode::UnknownAuction,
+            Self::BidNotServed(..) => ErrorCode::UnknownBid,
+            Self::InvalidExecutionPayloadInBlock |
+            Self::InvalidSignedBlindedBeaconBlock |
+            Self::InvalidWithdrawalsRoot { .. } |
+            Self::InvalidBlock(..) => ErrorCode::InvalidBlock,
+            Self::InvalidFeeRecipient(..) => ErrorCode::InvalidFeeRecipient,
+            Self::InvalidGasLimitForProposer(..) | Self::InvalidGasLimit(..) => {
+                ErrorCode::InvalidGasLimit
+            }
+            Self::InvalidGasUsed(..) |
+            Self::InvalidParentHash(..) |
+            Self::InvalidBlockHash(..) |
+            Self::ParentNotHead(..) => ErrorCode::InvalidBidTrace,
+            Self::MissingBlobsBundle => ErrorCode::MissingBlobsBundle,
+            Self::UnsupportedFork(..) => ErrorCode::InvalidFork,
+            Self::InvalidProposerSignature { .. } | Self::InvalidBuilderSignature(..) => {
+                ErrorCode::InvalidSignature
+            }
+            Self::ValidatorNotRegistered(..) => ErrorCode::ValidatorNotRegistered,
+            Self::UnknownValidatorIndex(..) => ErrorCode::UnknownValidator,
+            Self::UnassignedProposer { .. } => ErrorCode::UnassignedProposer,
+            Self::MissingProposerDuties(..) => ErrorCode::NotReady,
+            Self::BuilderNotRegistered(..) | Self::BuilderNotAllowed(..) => {
+                ErrorCode::BuilderNotAllowed
+            }
+            Self::BuilderBanned { .. } => ErrorCode::BuilderBanned,
+            Self::SlotOutsideWindow { .. } => ErrorCode::SlotOutsideWindow,
+            Self::SubmissionAfterCutoff { .. } => ErrorCode::SubmissionAfterCutoff,
+            Self::SubmissionTooFarAhead { .. } => ErrorCode::SubmissionTooFarAhead,
+            Self::DuplicateBlockHash(..) => ErrorCode::DuplicateBlockHash,
+            Self::BidBelowMinimum(..) => ErrorCode::BidBelowMinimum,
+            Self::BlockValidationUnavailable(..) => ErrorCode::UpstreamError,
+            Self::ObservationMode => ErrorCode::ObservationMode,
+            Self::InvalidUpstreamBid(..) => ErrorCode::InvalidBid,
+            Self::UpstreamRelayTimeout(..) | Self::PayloadTimeout(..) => ErrorCode::Timeout,
+        }
+    }
+}
+
+impl Error {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::InvalidFork { .. } => ErrorCode::InvalidFork,
+            Self::NoBidPrepared(..) => ErrorCode::NoBid,
+            Self::NotReady | Self::BeaconNodeSyncing(..) => ErrorCode::NotReady,
+            Self::RegistrationQueueFull | Self::TooManyPayloadRequests | Self::BidQueueFull => {
+                ErrorCode::Overloaded
+            }
+            Self::InvalidBeaconNodeUrl(..) |
+            Self::InvalidBlockValidationUrl(..) |
+            Self::InvalidUpstreamRelayUrl(..) |
+            Self::InvalidSecretKey(..) |
+            Self::InvalidRelayIdentity(..) |
+            Self::UnknownNetwork(..) |
+            Self::EmptyAdminToken |
+            Self::SelfTestFailed(..) |
+            Self::InvalidForkSchedule(..) |
+            Self::InvalidTlsConfig(..) |
+            Self::GenesisTimeInFuture(..) |
+            Self::GenesisMismatch { .. } |
+            Self::PortConflict { .. } |
+            Self::NoEphemeralPort(..) => ErrorCode::InvalidConfig,
+            Self::UnsupportedContentType(..) => ErrorCode::UnsupportedContentType,
+            Self::InvalidRequestBody(..) => ErrorCode::InvalidRequestBody,
+            Self::InvalidRequestPath(..) => ErrorCode::InvalidRequestPath,
+            Self::ValidatorRegistry(..) | Self::RegistrationErrors(..) => {
+                ErrorCode::InvalidRegistration
+            }
+            Self::ProposerScheduler(..) | Self::Api(..) => ErrorCode::UpstreamError,
+            Self::RegistrationStore(..) => ErrorCode::InternalError,
+            Self::Boost(err) => err.code(),
+            Self::Relay(err) => err.code(),
+            Self::Consensus(..) => ErrorCode::ConsensusError,
+            Self::BeaconNodeTimeout(..) => ErrorCode::Timeout,
+        }
+    }
+
+    /// Returns the status of a response serving this error
+    ///
+    /// Errors of the relay itself or of the services it depends on are served with a `5xx`
+    /// status, so that clients never take them for faults of their request.
+    #[cfg(feature = "api")]
+    pub fn status_code(&self) -> StatusCode {
+        match self.code() {
+            ErrorCode::NoBid => StatusCode::NO_CONTENT,
+            ErrorCode::BuilderBanned => StatusCode::FORBIDDEN,
+            ErrorCode::UnsupportedContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            ErrorCode::NotReady | ErrorCode::Overloaded => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::UpstreamError | ErrorCode::PayloadUnavailable => StatusCode::BAD_GATEWAY,
+            ErrorCode::InvalidConfig | ErrorCode::ConsensusError | ErrorCode::InternalError => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            ErrorCode::InvalidFork |
+            ErrorCode::InvalidRequestBody |
+            ErrorCode::InvalidRequestPath |
+            ErrorCode::InvalidRegistration |
+            ErrorCode::UnknownAuction |
+            ErrorCode::UnknownBid |
+            ErrorCode::InvalidBid |
+            ErrorCode::InvalidBidTrace |
+            ErrorCode::InvalidBlock |
+            ErrorCode::InvalidFeeRecipient |
+            ErrorCode::InvalidGasLimit |
+            ErrorCode::MissingBlobsBundle |
+            ErrorCode::InvalidSignature |
+            ErrorCode::ValidatorNotRegistered |
+            ErrorCode::UnknownValidator |
+            ErrorCode::UnassignedProposer |
+            ErrorCode::BuilderNotAllowed |
+            ErrorCode::SlotOutsideWindow |
+            ErrorCode::SubmissionAfterCutoff |
+            ErrorCode::SubmissionTooFarAhead |
+            ErrorCode::DuplicateBlockHash |
+            ErrorCode::BidBelowMinimum |
+            ErrorCode::ObservationMode => StatusCode::BAD_REQUEST,
+        }
+    }
 }
 
 #[cfg(feature = "api")]
 use axum::extract::Json;
 #[cfg(feature = "api")]
-use axum::http::StatusCode;
+use axum::http::{header::RETRY_AFTER, StatusCode};
 #[cfg(feature = "api")]
 use axum::response::{IntoResponse, Response};
 
+// Serves errors as `{"code": <status>, "message": <message>, "error_code": <code>}`.
+//
+// NOTE: `code` is the HTTP status, as in the error bodies of the builder API that clients
+// already parse, so the stable code of the error is served as the additional `error_code`.
 #[cfg(feature = "api")]
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        let message = self.to_string();
-        let code = match self {
-            Self::NoBidPrepared(..) => StatusCode::NO_CONTENT,
-            _ => StatusCode::BAD_REQUEST,
-        };
-        (code, Json(beacon_api_client::ApiError::ErrorMessage { code, message })).into_response()
+        let code = self.status_code();
+        // NOTE: proposers expect an empty `204` when there is no bid, rather than an error
+        if code == StatusCode::NO_CONTENT {
+            return code.into_response()
+        }
+        let body = Json(serde_json::json!({
+            "code": code.as_u16(),
+            "message": self.to_string(),
+            "error_code": self.code().as_str(),
+        }));
+        if let Self::Relay(RelayError::BuilderBanned { retry_after_secs, .. }) = self {
+            return (code, [(RETRY_AFTER, retry_after_secs.to_string())], body).into_response()
+        }
+        (code, body).into_response()
+    }
+}
+
+#[cfg(all(test, feature = "api"))]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    async fn response_of(err: Error) -> (StatusCode, Value) {
+        let response = err.into_response();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    // Returns an error served by a beacon node.
+    fn beacon_error() -> ApiError {
+        let message = "internal error".into();
+        ApiError::Api(beacon_api_client::ApiError::ErrorMessage {
+            code: StatusCode::INTERNAL_SERVER_ERROR,
+            message,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_error_response_codes() {
+        let errors = [
+            (
+                RelayError::SlotOutsideWindow { slot: 10, current_slot: 20 }.into(),
+                StatusCode::BAD_REQUEST,
+                "slot_outside_window",
+            ),
+            (
+                RelayError::InvalidBuilderSignature(Default::default()).into(),
+                StatusCode::BAD_REQUEST,
+                "invalid_signature",
+            ),
+            (Error::BidQueueFull, StatusCode::SERVICE_UNAVAILABLE, "overloaded"),
+            (RelayError::PayloadTimeout(3000).into(), StatusCode::GATEWAY_TIMEOUT, "timeout"),
+            (
+                Error::UnsupportedContentType("text/plain".into()),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported_content_type",
+            ),
+            (
+                Error::RegistrationStore("disk full".into()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+            ),
+            (
+                RelayError::BlockValidationUnavailable("connection refused".into()).into(),
+                StatusCode::BAD_GATEWAY,
+                "upstream_error",
+            ),
+            (beacon_error().into(), StatusCode::BAD_GATEWAY, "upstream_error"),
+            (
+                Error::ProposerScheduler(beacon_error().into()),
+                StatusCode::BAD_GATEWAY,
+                "upstream_error",
+            ),
+            (
+                BoostError::MissingPayload(Default::default()).into(),
+                StatusCode::BAD_GATEWAY,
+                "payload_unavailable",
+            ),
+            (Error::BeaconNodeSyncing(10), StatusCode::SERVICE_UNAVAILABLE, "not_ready"),
+        ];
+        for (err, status, error_code) in errors {
+            let message = err.to_string();
+            assert_eq!(err.code().as_str(), error_code);
+            let (code, body) = response_of(err).await;
+            assert_eq!(code, status, "{error_code}");
+            assert_eq!(body["code"], status.as_u16());
+            assert_eq!(body["message"], message);
+            assert_eq!(body["error_code"], error_code);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_banned_builder_response() {
+        let err =
+            RelayError::BuilderBanned { public_key: Default::default(), retry_after_secs: 12 };
+        let response = Error::from(err).into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(response.headers()[RETRY_AFTER], "12");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error_code"], "builder_banned");
+    }
+
+    #[test]
+    fn test_error_body_parses_as_api_error() {
+        let body = serde_json::json!({ "code": 400, "message": "m", "error_code": "invalid_fork" });
+        let err: beacon_api_client::ApiError = serde_json::from_value(body).unwrap();
+        assert!(matches!(err, beacon_api_client::ApiError::ErrorMessage { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_no_bid_response_is_empty() {
+        let response = Error::NoBidPrepared(Default::default()).into_response();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(body.is_empty());
     }
 }
@@ -1,21 +1,37 @@
+mod beacon_node_pool;
 pub mod blinded_block_provider;
 pub mod blinded_block_relayer;
 pub mod block_validation;
+#[cfg(feature = "api")]
+mod body_limit;
 pub mod config;
+#[cfg(feature = "api")]
+pub mod connection_limit;
 mod error;
+mod network;
 mod proposer_scheduler;
+#[cfg(feature = "api")]
+pub mod rate_limit;
 pub mod relay;
+#[cfg(feature = "api")]
+pub mod request_log;
 #[cfg(feature = "serde")]
 pub mod serde;
 pub mod signing;
+#[cfg(feature = "api")]
+pub mod tls;
 pub mod types;
 mod validator_registry;
 
+pub use beacon_node_pool::{is_transport_error, BeaconNodePool, ConnectionStatus};
 pub use blinded_block_provider::BlindedBlockProvider;
 pub use blinded_block_relayer::BlindedBlockRelayer;
 
 pub use block_validation::*;
 pub use error::*;
+pub use network::parse_network;
 pub use proposer_scheduler::ProposerScheduler;
 pub use relay::{Relay, RelayEndpoint};
-pub use validator_registry::ValidatorRegistry;
+pub use validator_registry::{
+    verify_registration_signature, Error as ValidatorRegistryError, ValidatorRegistry,
+};
@@ -0,0 +1,54 @@
+use crate::Error;
+use ethereum_consensus::networks::Network;
+use std::path::Path;
+
+/// Parses the name of a public network, ignoring case and surrounding whitespace, or the path to
+/// the config file of a custom network
+///
+/// A value is taken as a path if it contains a path separator or has a `.yaml` or `.yml`
+/// extension, so that a misspelled name is reported rather than loaded as a file.
+pub fn parse_network(network: &str) -> Result<Network, Error> {
+    let network = network.trim();
+    let parsed = match network.to_lowercase().as_str() {
+        "mainnet" => Network::Mainnet,
+        "sepolia" => Network::Sepolia,
+        "goerli" => Network::Goerli,
+        "holesky" => Network::Holesky,
+        _ if is_path(network) => Network::Custom(network.to_string()),
+        _ => return Err(Error::UnknownNetwork(network.to_string())),
+    };
+    Ok(parsed)
+}
+
+fn is_path(network: &str) -> bool {
+    let has_config_extension = Path::new(network)
+        .extension()
+        .map(|extension| {
+            extension.eq_ignore_ascii_case("yaml") || extension.eq_ignore_ascii_case("yml")
+        })
+        .unwrap_or(false);
+    network.contains(std::path::is_separator) || has_config_extension
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_network() {
+        assert!(matches!(parse_network("mainnet"), Ok(Network::Mainnet)));
+        assert!(matches!(parse_network("Holesky"), Ok(Network::Holesky)));
+        assert!(matches!(parse_network(" sepolia\n"), Ok(Network::Sepolia)));
+        assert!(matches!(parse_network("GOERLI"), Ok(Network::Goerli)));
+
+        for path in ["/etc/devnet/Config.yaml", "./devnet", "config.yml"] {
+            let parsed = parse_network(&format!(" {path} "));
+            assert!(matches!(parsed, Ok(Network::Custom(custom)) if custom == path), "{path}");
+        }
+
+        for unknown in ["mainet", "", "holesky-devnet"] {
+            let err = parse_network(unknown).unwrap_err();
+            assert!(matches!(err, Error::UnknownNetwork(network) if network == unknown));
+        }
+    }
+}
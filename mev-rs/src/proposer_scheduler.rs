@@ -1,7 +1,11 @@
-use crate::{types::ProposerSchedule, validator_registry::ValidatorRegistry};
-use beacon_api_client::{mainnet::Client, Error as ApiError, ProposerDuty};
-use ethereum_consensus::primitives::{Epoch, Slot};
+use crate::{
+    beacon_node_pool::BeaconNodePool, types::ProposerSchedule,
+    validator_registry::ValidatorRegistry,
+};
+use beacon_api_client::{Error as ApiError, ProposerDuty};
+use ethereum_consensus::primitives::{BlsPublicKey, Epoch, Slot};
 use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashSet};
 use thiserror::Error;
 use tracing::warn;
 
@@ -12,13 +16,18 @@ pub enum Error {
 }
 
 pub struct ProposerScheduler {
-    api: Client,
+    api: BeaconNodePool,
     slots_per_epoch: Slot,
     state: Mutex<State>,
 }
 
 #[derive(Default)]
 struct State {
+    // proposer duties fetched from consensus, by slot
+    duties: BTreeMap<Slot, ProposerDuty>,
+    // proposer duties of an upcoming epoch fetched ahead of its start, replacing any cached
+    // duties of the epoch once it starts
+    prefetched: Option<(Epoch, Vec<ProposerDuty>)>,
     // schedules are monotonically increasing by `slot`
     // but may not be contiguous as schedules are created only
     // if we have a valid registration from the proposer
@@ -26,71 +35,125 @@ struct State {
 }
 
 impl ProposerScheduler {
-    pub fn new(api: Client, slots_per_epoch: Slot) -> Self {
+    pub fn new(api: BeaconNodePool, slots_per_epoch: Slot) -> Self {
         Self { api, slots_per_epoch, state: Default::default() }
     }
 
-    async fn fetch_duties_if_missing(
-        &self,
-        epoch: Epoch,
-        all_duties: &mut Vec<ProposerDuty>,
-    ) -> Result<(), Error> {
-        {
-            let slot = epoch * self.slots_per_epoch;
-            let state = self.state.lock();
-            if state.proposer_schedule.iter().any(|schedule| schedule.slot >= slot) {
-                return Ok(())
-            }
+    /// Returns `true` if any proposer duties for `epoch` are cached
+    pub fn has_duties_for(&self, epoch: Epoch) -> bool {
+        let start = epoch * self.slots_per_epoch;
+        let end = start + self.slots_per_epoch;
+        self.state.lock().duties.range(start..end).next().is_some()
+    }
+
+    // Fetches proposer duties for `epoch` unless they are already cached, returning
+    // whether duties for `epoch` are available.
+    async fn fetch_duties_if_missing(&self, epoch: Epoch) -> bool {
+        if self.has_duties_for(epoch) {
+            return true
         }
         // TODO be tolerant to re-orgs
-        let (_dependent_root, duties) = self.api.get_proposer_duties(epoch).await?;
-        all_duties.extend(duties);
-        Ok(())
+        match self.api.call(|api| async move { api.get_proposer_duties(epoch).await }).await {
+            Ok((_dependent_root, duties)) => {
+                let mut state = self.state.lock();
+                state.duties.extend(duties.into_iter().map(|duty| (duty.slot, duty)));
+                true
+            }
+            Err(err) => {
+                warn!(%err, epoch, "could not get proposer duties from consensus; retaining previous duties");
+                false
+            }
+        }
     }
 
-    // Fetches proposer duties for the current epoch `epoch` and the next epoch.
-    async fn fetch_new_duties(&self, epoch: Epoch) -> Vec<ProposerDuty> {
-        let mut duties = vec![];
-        if let Err(err) = self.fetch_duties_if_missing(epoch, &mut duties).await {
-            warn!(%err, epoch, "could not get proposer duties from consensus");
+    /// Fetches the proposer duties for the upcoming `epoch` ahead of its start, to replace those
+    /// already cached for it once [`Self::on_epoch`] is called for `epoch`
+    ///
+    /// Duties already prefetched for `epoch` are not fetched again.
+    pub async fn prefetch(&self, epoch: Epoch) -> Result<(), Error> {
+        let is_prefetched = |state: &State| {
+            state.prefetched.as_ref().map(|(prefetched, _)| *prefetched == epoch).unwrap_or(false)
+        };
+        if is_prefetched(&self.state.lock()) {
+            return Ok(())
         }
-        if let Err(err) = self.fetch_duties_if_missing(epoch + 1, &mut duties).await {
-            warn!(%err, epoch = epoch + 1, "could not get proposer duties from consensus");
-        }
-        duties
+        let (_dependent_root, duties) =
+            self.api.call(|api| async move { api.get_proposer_duties(epoch).await }).await?;
+        self.state.lock().prefetched = Some((epoch, duties));
+        Ok(())
     }
 
+    // Replaces the cached duties of `epoch` with any prefetched for it, dropping any prefetched
+    // for an earlier epoch.
+    fn apply_prefetched(&self, epoch: Epoch) {
+        let mut state = self.state.lock();
+        let duties = match state.prefetched.take() {
+            Some((prefetched_epoch, duties)) if prefetched_epoch == epoch => duties,
+            Some(prefetched) if prefetched.0 > epoch => {
+                state.prefetched = Some(prefetched);
+                return
+            }
+            _ => return,
+        };
+        let start = epoch * self.slots_per_epoch;
+        let end = start + self.slots_per_epoch;
+        state.duties.retain(|duty_slot, _| !(start..end).contains(duty_slot));
+        state.duties.extend(duties.into_iter().map(|duty| (duty.slot, duty)));
+    }
+
+    /// Refreshes the cached proposer duties for the current epoch `epoch` and the next epoch,
+    /// and rebuilds the proposal schedule from those proposers with a registration in
+    /// the `validator_registry`
+    ///
+    /// Duties prefetched for `epoch` with [`Self::prefetch`] become active instead of fetching
+    /// them. If duties for `epoch` cannot be fetched, the previously cached duties are retained.
     pub async fn on_epoch(
         &self,
         epoch: Epoch,
         validator_registry: &ValidatorRegistry,
     ) -> Result<(), Error> {
-        let extension = self
-            .fetch_new_duties(epoch)
-            .await
-            .iter()
+        self.apply_prefetched(epoch);
+        let has_current_duties = self.fetch_duties_if_missing(epoch).await;
+        self.fetch_duties_if_missing(epoch + 1).await;
+
+        let slot = epoch * self.slots_per_epoch;
+        let mut state = self.state.lock();
+        if has_current_duties {
+            // drop old duties
+            state.duties.retain(|&duty_slot, _| duty_slot >= slot);
+        }
+        let proposer_schedule = state
+            .duties
+            .values()
+            .filter(|duty| duty.slot >= slot)
             .filter_map(|duty| {
                 let public_key = &duty.public_key;
                 validator_registry.get_signed_registration(public_key).map(|entry| {
                     ProposerSchedule {
                         slot: duty.slot,
                         validator_index: duty.validator_index,
-                        entry: entry.clone(),
+                        entry,
                     }
                 })
             })
-            // collect so we do the work *before* grabbing the lock
             .collect::<Vec<_>>();
-
-        let slot = epoch * self.slots_per_epoch;
-        let mut state = self.state.lock();
-        // drop old schedules
-        state.proposer_schedule.retain(|schedule| schedule.slot >= slot);
-        // add new schedules
-        state.proposer_schedule.extend(extension);
+        state.proposer_schedule = proposer_schedule;
         Ok(())
     }
 
+    /// Returns the public key of the validator expected to propose at `slot`, if known
+    pub fn get_proposer_public_key(&self, slot: Slot) -> Option<BlsPublicKey> {
+        let state = self.state.lock();
+        state.duties.get(&slot).map(|duty| duty.public_key.clone())
+    }
+
+    /// Returns the public keys of the validators expected to propose in the current or
+    /// next epoch, per the cached proposer duties
+    pub fn get_scheduled_proposers(&self) -> HashSet<BlsPublicKey> {
+        let state = self.state.lock();
+        state.duties.values().map(|duty| duty.public_key.clone()).collect()
+    }
+
     pub fn get_proposal_schedule(&self) -> Result<Vec<ProposerSchedule>, Error> {
         // NOTE: if external APIs hold, then the expected schedules are
         // those currently in the `state`.
@@ -0,0 +1,164 @@
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use tracing::debug;
+
+// Maximum number of sources tracked at once, to bound the memory used by the limiter.
+const MAX_TRACKED_SOURCES: usize = 16_384;
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// A token bucket rate limiter keyed by the source IP address of a request.
+#[derive(Debug)]
+pub struct RateLimiter {
+    // also the capacity of each bucket
+    rate_per_sec: AtomicU32,
+    max_sources: usize,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Allows `rate_per_sec` requests per second from each source, with bursts of up to
+    /// `rate_per_sec` requests
+    pub fn new(rate_per_sec: u32) -> Self {
+        Self::with_max_sources(rate_per_sec, MAX_TRACKED_SOURCES)
+    }
+
+    fn with_max_sources(rate_per_sec: u32, max_sources: usize) -> Self {
+        let rate_per_sec = AtomicU32::new(rate_per_sec.max(1));
+        Self { rate_per_sec, max_sources, buckets: Default::default() }
+    }
+
+    /// Updates the limit to `rate_per_sec` requests per second from each source
+    pub fn set_rate_per_sec(&self, rate_per_sec: u32) {
+        self.rate_per_sec.store(rate_per_sec.max(1), Ordering::Relaxed);
+    }
+
+    pub fn rate_per_sec(&self) -> u32 {
+        self.rate_per_sec.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if a request from `source` is within the limit, consuming a token if so
+    pub fn check(&self, source: IpAddr) -> bool {
+        self.check_at(source, Instant::now())
+    }
+
+    fn refilled_tokens(bucket: &Bucket, now: Instant, rate_per_sec: f64) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.last_refill).as_secs_f64();
+        (bucket.tokens + elapsed * rate_per_sec).min(rate_per_sec)
+    }
+
+    fn check_at(&self, source: IpAddr, now: Instant) -> bool {
+        let rate_per_sec = f64::from(self.rate_per_sec());
+        let mut buckets = self.buckets.lock();
+        if !buckets.contains_key(&source) && buckets.len() >= self.max_sources {
+            self.evict(&mut buckets, now, rate_per_sec);
+        }
+        let bucket =
+            buckets.entry(source).or_insert(Bucket { tokens: rate_per_sec, last_refill: now });
+        bucket.tokens = Self::refilled_tokens(bucket, now, rate_per_sec);
+        bucket.last_refill = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Drops buckets that have refilled completely, as they behave the same as untracked sources,
+    // falling back to the least recently used bucket if every tracked source is still active.
+    fn evict(&self, buckets: &mut HashMap<IpAddr, Bucket>, now: Instant, rate_per_sec: f64) {
+        buckets.retain(|_, bucket| Self::refilled_tokens(bucket, now, rate_per_sec) < rate_per_sec);
+        if buckets.len() >= self.max_sources {
+            let least_recent = buckets
+                .iter()
+                .min_by_key(|(_, bucket)| bucket.last_refill)
+                .map(|(source, _)| *source);
+            if let Some(source) = least_recent {
+                buckets.remove(&source);
+            }
+        }
+    }
+}
+
+/// Middleware rejecting requests with `429 Too Many Requests` once their source exceeds the limit
+pub(crate) async fn limit_by_source<B>(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if limiter.check(addr.ip()) {
+        next.run(request).await
+    } else {
+        debug!(source = %addr.ip(), path = %request.uri().path(), "rate limit exceeded");
+        StatusCode::TOO_MANY_REQUESTS.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::Ipv4Addr, time::Duration};
+
+    #[test]
+    fn test_rate_limiter_refills() {
+        let limiter = RateLimiter::new(2);
+        let source = IpAddr::from(Ipv4Addr::LOCALHOST);
+        let now = Instant::now();
+        assert!(limiter.check_at(source, now));
+        assert!(limiter.check_at(source, now));
+        assert!(!limiter.check_at(source, now));
+
+        let other = IpAddr::from(Ipv4Addr::new(10, 0, 0, 1));
+        assert!(limiter.check_at(other, now));
+
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.check_at(source, later));
+        assert!(!limiter.check_at(source, later));
+    }
+
+    #[test]
+    fn test_rate_limiter_updates_rate() {
+        let limiter = RateLimiter::new(1);
+        let source = IpAddr::from(Ipv4Addr::LOCALHOST);
+        let now = Instant::now();
+        assert!(limiter.check_at(source, now));
+        assert!(!limiter.check_at(source, now));
+
+        limiter.set_rate_per_sec(4);
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.check_at(source, later));
+        assert!(limiter.check_at(source, later));
+        assert!(!limiter.check_at(source, later));
+    }
+
+    #[test]
+    fn test_rate_limiter_bounds_sources() {
+        let limiter = RateLimiter::with_max_sources(1, 2);
+        let now = Instant::now();
+        for i in 0..8 {
+            let source = IpAddr::from(Ipv4Addr::new(10, 0, 0, i));
+            assert!(limiter.check_at(source, now));
+            assert!(limiter.buckets.lock().len() <= 2);
+        }
+    }
+}
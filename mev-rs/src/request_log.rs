@@ -0,0 +1,92 @@
+use axum::{
+    extract::{MatchedPath, State},
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::info;
+
+/// A request handled by a server, without any of its headers, parameters or body
+#[derive(Debug, Clone)]
+pub struct RequestSummary {
+    pub method: Method,
+    /// Route the request matched, e.g. `/eth/v1/builder/header/:slot/:parent_hash/:public_key`,
+    /// or `None` if it matched no route
+    pub route: Option<String>,
+    pub status: StatusCode,
+    pub latency: Duration,
+}
+
+/// Called with the summary of each request handled, e.g. to record its latency
+pub type RequestObserver = Arc<dyn Fn(&RequestSummary) + Send + Sync>;
+
+/// Middleware logging the method, route, status and latency of each request, and passing
+/// them to the observer
+///
+/// NOTE: only the matched route is logged, rather than the path of the request, so that
+/// public keys and other parameters of the request are kept out of the logs.
+pub(crate) async fn log_request<B>(
+    State(observer): State<RequestObserver>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let method = request.method().clone();
+    let route = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string());
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let summary =
+        RequestSummary { method, route, status: response.status(), latency: start.elapsed() };
+    info!(
+        method = %summary.method,
+        route = summary.route.as_deref().unwrap_or("unmatched"),
+        status = summary.status.as_u16(),
+        latency = ?summary.latency,
+        "handled request"
+    );
+    observer(&summary);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        middleware,
+        routing::{get, post},
+        Router,
+    };
+    use parking_lot::Mutex;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_log_request_observes_matched_route() {
+        let summaries = Arc::new(Mutex::new(Vec::new()));
+        let observed = summaries.clone();
+        let observer: RequestObserver =
+            Arc::new(move |summary: &RequestSummary| observed.lock().push(summary.clone()));
+        let router = Router::new()
+            .route("/eth/v1/builder/status/:public_key", get(|| async { StatusCode::OK }))
+            .route("/eth/v1/builder/blinded_blocks", post(|| async { StatusCode::BAD_REQUEST }))
+            .layer(middleware::from_fn_with_state(observer, log_request));
+
+        let request = Request::get("/eth/v1/builder/status/0xabcd").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let request = Request::post("/eth/v1/builder/blinded_blocks").body(Body::empty()).unwrap();
+        router.oneshot(request).await.unwrap();
+
+        let summaries = summaries.lock();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].method, Method::GET);
+        assert_eq!(summaries[0].route.as_deref(), Some("/eth/v1/builder/status/:public_key"));
+        assert_eq!(summaries[0].status, StatusCode::OK);
+        assert_eq!(summaries[1].method, Method::POST);
+        assert_eq!(summaries[1].route.as_deref(), Some("/eth/v1/builder/blinded_blocks"));
+        assert_eq!(summaries[1].status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,17 +1,16 @@
-use ethereum_consensus::{
+pub use ethereum_consensus::{
     builder::compute_builder_domain,
+    crypto::SecretKey,
+    signing::{compute_signing_root, sign_with_domain, verify_signature},
+};
+use ethereum_consensus::{
     domains::DomainType,
     phase0::mainnet::compute_domain,
     primitives::{BlsSignature, Root, Slot},
-    signing::sign_with_domain,
     ssz::prelude::Merkleized,
     state_transition::Context,
     Error,
 };
-pub use ethereum_consensus::{
-    crypto::SecretKey,
-    signing::{compute_signing_root, verify_signature},
-};
 
 pub fn compute_consensus_signing_root<T: Merkleized>(
     data: &mut T,
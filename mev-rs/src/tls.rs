@@ -0,0 +1,249 @@
+use crate::{
+    connection_limit::{ConnectionLimit, LimitedConnection, LimitedIncoming},
+    error::Error,
+};
+use axum::{extract::connect_info::Connected, Router};
+use hyper::server::accept::Accept;
+use rustls_pemfile::Item;
+use std::{
+    fs::File,
+    future::Future,
+    io::{self, BufReader},
+    net::SocketAddr,
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::mpsc,
+};
+use tokio_rustls::{
+    rustls::{Certificate, PrivateKey},
+    server::TlsStream,
+    TlsAcceptor,
+};
+
+pub use tokio_rustls::rustls::ServerConfig;
+use tracing::debug;
+
+// Amount of time a client has to complete the TLS handshake before the connection is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Reads every PEM-encoded item from the file at `path`.
+fn read_pem_file(path: &Path) -> Result<Vec<Item>, Error> {
+    let file = File::open(path).map_err(|err| {
+        Error::InvalidTlsConfig(format!("could not open `{}`: {err}", path.display()))
+    })?;
+    rustls_pemfile::read_all(&mut BufReader::new(file)).map_err(|err| {
+        Error::InvalidTlsConfig(format!("could not read `{}`: {err}", path.display()))
+    })
+}
+
+/// Loads the PEM-encoded certificate chain at `cert_path` and the private key at `key_path`
+/// into a config to terminate TLS with
+pub fn load_server_config(cert_path: &Path, key_path: &Path) -> Result<Arc<ServerConfig>, Error> {
+    let certs = read_pem_file(cert_path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::X509Certificate(cert) => Some(Certificate(cert)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if certs.is_empty() {
+        let reason = format!("no certificates found in `{}`", cert_path.display());
+        return Err(Error::InvalidTlsConfig(reason))
+    }
+    let key = read_pem_file(key_path)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            Error::InvalidTlsConfig(format!("no private key found in `{}`", key_path.display()))
+        })?;
+
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|err| Error::InvalidTlsConfig(format!("invalid certificate or key: {err}")))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// A TLS connection accepted by [`TlsIncoming`]
+pub struct TlsConnection {
+    stream: TlsStream<LimitedConnection>,
+    remote_addr: SocketAddr,
+}
+
+impl TlsConnection {
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+}
+
+impl Connected<&TlsConnection> for SocketAddr {
+    fn connect_info(target: &TlsConnection) -> Self {
+        target.remote_addr
+    }
+}
+
+impl AsyncRead for TlsConnection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
+
+/// Accepts TCP connections and terminates TLS on each, for use with [`axum::Server::builder`]
+///
+/// Handshakes run concurrently so that a slow client does not hold up accepting others;
+/// connections that fail the handshake are dropped without stopping the server.
+pub struct TlsIncoming {
+    incoming: LimitedIncoming,
+    acceptor: TlsAcceptor,
+    connections_tx: mpsc::UnboundedSender<TlsConnection>,
+    connections_rx: mpsc::UnboundedReceiver<TlsConnection>,
+}
+
+impl TlsIncoming {
+    pub fn bind(addr: &SocketAddr, config: Arc<ServerConfig>) -> Result<Self, hyper::Error> {
+        let incoming = LimitedIncoming::bind(addr, ConnectionLimit::default(), None)?;
+        Ok(Self::new(incoming, config))
+    }
+
+    /// Terminates TLS with `config` on the connections accepted by `incoming`
+    ///
+    /// Connections count against the limit of `incoming` from before their handshake.
+    pub fn new(incoming: LimitedIncoming, config: Arc<ServerConfig>) -> Self {
+        let (connections_tx, connections_rx) = mpsc::unbounded_channel();
+        Self { incoming, acceptor: TlsAcceptor::from(config), connections_tx, connections_rx }
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.incoming.local_addr()
+    }
+
+    fn handshake(&self, stream: LimitedConnection) {
+        let remote_addr = stream.remote_addr();
+        let accept = self.acceptor.accept(stream);
+        let connections = self.connections_tx.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, accept).await {
+                Ok(Ok(stream)) => {
+                    let _ = connections.send(TlsConnection { stream, remote_addr });
+                }
+                Ok(Err(err)) => debug!(%err, %remote_addr, "TLS handshake failed"),
+                Err(_) => debug!(%remote_addr, "TLS handshake timed out"),
+            }
+        });
+    }
+}
+
+impl Accept for TlsIncoming {
+    type Conn = TlsConnection;
+    type Error = io::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let this = self.get_mut();
+        loop {
+            match Pin::new(&mut this.incoming).poll_accept(cx) {
+                Poll::Ready(Some(Ok(stream))) => this.handshake(stream),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => break,
+            }
+        }
+        // NOTE: the sender held by `this` keeps the channel open
+        this.connections_rx.poll_recv(cx).map(|connection| connection.map(Ok))
+    }
+}
+
+/// Serves `router` at `addr` until `signal` resolves, terminating TLS with `tls` if provided
+pub async fn serve<F>(
+    addr: SocketAddr,
+    router: Router,
+    tls: Option<Arc<ServerConfig>>,
+    signal: F,
+) -> Result<(), hyper::Error>
+where
+    F: Future<Output = ()>,
+{
+    let incoming = LimitedIncoming::bind(&addr, ConnectionLimit::default(), None)?;
+    serve_incoming(incoming, router, tls, signal).await
+}
+
+/// Serves `router` on the connections accepted by `incoming` until `signal` resolves,
+/// terminating TLS with `tls` if provided
+pub async fn serve_incoming<F>(
+    incoming: LimitedIncoming,
+    router: Router,
+    tls: Option<Arc<ServerConfig>>,
+    signal: F,
+) -> Result<(), hyper::Error>
+where
+    F: Future<Output = ()>,
+{
+    let make_service = router.into_make_service_with_connect_info::<SocketAddr>();
+    match tls {
+        Some(config) => {
+            let incoming = TlsIncoming::new(incoming, config);
+            axum::Server::builder(incoming).serve(make_service).with_graceful_shutdown(signal).await
+        }
+        None => {
+            axum::Server::builder(incoming).serve(make_service).with_graceful_shutdown(signal).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_server_config_with_missing_files() {
+        let dir = std::env::temp_dir();
+        let cert_path = dir.join("mev-rs-test-missing-cert.pem");
+        let key_path = dir.join("mev-rs-test-missing-key.pem");
+        let result = load_server_config(&cert_path, &key_path);
+        assert!(matches!(result, Err(Error::InvalidTlsConfig(..))));
+
+        let cert_path = dir.join("mev-rs-test-empty-cert.pem");
+        std::fs::write(&cert_path, "").unwrap();
+        let result = load_server_config(&cert_path, &key_path);
+        std::fs::remove_file(&cert_path).unwrap();
+        let Err(Error::InvalidTlsConfig(reason)) = result else {
+            panic!("loaded config without any certificates")
+        };
+        assert!(reason.starts_with("no certificates found"));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::types::ExecutionPayload;
-use ethereum_consensus::Fork;
+use ethereum_consensus::{ssz::prelude::*, Fork};
 
 pub mod bellatrix {
     use super::ExecutionPayload;
@@ -21,15 +21,15 @@ pub mod deneb {
         ssz::prelude::*,
     };
 
-    #[derive(Debug)]
+    #[derive(Debug, Default, Clone, SimpleSerialize)]
     #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct BlobsBundle {
-        commitments: List<KzgCommitment, MAX_BLOB_COMMITMENTS_PER_BLOCK>,
-        proofs: List<KzgProof, MAX_BLOB_COMMITMENTS_PER_BLOCK>,
-        blobs: List<Blob, MAX_BLOB_COMMITMENTS_PER_BLOCK>,
+        pub commitments: List<KzgCommitment, MAX_BLOB_COMMITMENTS_PER_BLOCK>,
+        pub proofs: List<KzgProof, MAX_BLOB_COMMITMENTS_PER_BLOCK>,
+        pub blobs: List<Blob, MAX_BLOB_COMMITMENTS_PER_BLOCK>,
     }
 
-    #[derive(Debug)]
+    #[derive(Debug, SimpleSerialize)]
     #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct AuctionContents {
         pub execution_payload: ExecutionPayload,
@@ -37,9 +37,10 @@ pub mod deneb {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, SimpleSerialize)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[serde(untagged)]
+#[ssz(transparent)]
 pub enum AuctionContents {
     Bellatrix(bellatrix::AuctionContents),
     Capella(capella::AuctionContents),
@@ -1,9 +1,9 @@
 use crate::{
-    signing::{compute_builder_signing_root, verify_signature},
-    types::ExecutionPayload,
+    signing::{compute_builder_domain, compute_signing_root, verify_signature},
+    types::{auction_contents::deneb::BlobsBundle, ExecutionPayload},
 };
 use ethereum_consensus::{
-    primitives::{BlsPublicKey, BlsSignature, ExecutionAddress, Hash32, Slot, U256},
+    primitives::{BlsPublicKey, BlsSignature, Domain, ExecutionAddress, Hash32, Slot, U256},
     ssz::prelude::*,
     state_transition::Context,
     Error,
@@ -34,13 +34,52 @@ pub struct BidTrace {
 pub struct SignedBidSubmission {
     pub message: BidTrace,
     pub execution_payload: ExecutionPayload,
+    // only present for submissions from Deneb onwards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blobs_bundle: Option<BlobsBundle>,
     pub signature: BlsSignature,
 }
 
 impl SignedBidSubmission {
     pub fn verify_signature(&mut self, context: &Context) -> Result<(), Error> {
-        let signing_root = compute_builder_signing_root(&mut self.message, context)?;
+        let domain = compute_builder_domain(context)?;
+        self.verify_signature_with_domain(domain)
+    }
+
+    /// Verifies the signature of the submission against a precomputed builder `domain`
+    pub fn verify_signature_with_domain(&mut self, domain: Domain) -> Result<(), Error> {
+        let signing_root = compute_signing_root(&mut self.message, domain)?;
         let public_key = &self.message.builder_public_key;
         verify_signature(public_key, signing_root.as_ref(), &self.signature)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From the relay data API spec
+    const BID_TRACE_JSON: &str = r#"
+    {
+        "slot": "1",
+        "parent_hash": "0xcf8e0d4e9587369b2301d0790347320302cc0943d5a1884560367e8208d920f2",
+        "block_hash": "0xcf8e0d4e9587369b2301d0790347320302cc0943d5a1884560367e8208d920f2",
+        "builder_pubkey": "0x93247f2209abcacf57b75a51dafae777f9dd38bc7053d1af526f220a7489a6d3a2753e5f3e8b1cfe39b56f43611df74a",
+        "proposer_pubkey": "0x93247f2209abcacf57b75a51dafae777f9dd38bc7053d1af526f220a7489a6d3a2753e5f3e8b1cfe39b56f43611df74a",
+        "proposer_fee_recipient": "0xabcf8e0d4e9587369b2301d0790347320302cc09",
+        "gas_limit": "1",
+        "gas_used": "1",
+        "value": "1"
+    }
+    "#;
+
+    #[test]
+    fn test_bid_trace_json() {
+        let bid_trace: BidTrace = serde_json::from_str(BID_TRACE_JSON.trim()).unwrap();
+        assert_eq!(bid_trace.slot, 1);
+        assert_eq!(bid_trace.value, U256::from(1));
+
+        let expected: serde_json::Value = serde_json::from_str(BID_TRACE_JSON.trim()).unwrap();
+        assert_eq!(serde_json::to_value(&bid_trace).unwrap(), expected);
+    }
+}
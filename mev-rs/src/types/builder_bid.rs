@@ -1,10 +1,13 @@
 use crate::{
-    signing::{compute_builder_signing_root, sign_builder_message, verify_signature, SecretKey},
+    signing::{
+        compute_builder_signing_root, sign_builder_message, sign_with_domain, verify_signature,
+        SecretKey,
+    },
     types::ExecutionPayloadHeader,
 };
 use ethereum_consensus::{
     deneb::{mainnet::MAX_BLOB_COMMITMENTS_PER_BLOCK, polynomial_commitments::KzgCommitment},
-    primitives::{BlsPublicKey, BlsSignature},
+    primitives::{BlsPublicKey, BlsSignature, Domain},
     ssz::prelude::*,
     state_transition::Context,
     Error, Fork,
@@ -130,6 +133,16 @@ impl BuilderBid {
         let signature = sign_builder_message(&mut self, secret_key, context)?;
         Ok(SignedBuilderBid { message: self, signature })
     }
+
+    /// Signs the bid with a precomputed builder `domain`
+    pub fn sign_with_domain(
+        mut self,
+        secret_key: &SecretKey,
+        domain: Domain,
+    ) -> Result<SignedBuilderBid, Error> {
+        let signature = sign_with_domain(&mut self, secret_key, domain)?;
+        Ok(SignedBuilderBid { message: self, signature })
+    }
 }
 
 #[derive(Debug, Clone, SimpleSerialize, serde::Serialize, serde::Deserialize)]
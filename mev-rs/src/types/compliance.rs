@@ -0,0 +1,66 @@
+use crate::types::SignedValidatorRegistration;
+
+/// Whether the blocks of a builder are filtered for compliance with sanctions lists, like
+/// the OFAC list, or include any transaction
+///
+/// A builder that does not declare a compliance is of unknown compliance; relays decide how
+/// its bids are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[serde(rename_all = "snake_case")]
+pub enum Compliance {
+    /// Blocks exclude transactions involving sanctioned addresses
+    Compliant,
+    /// Blocks include transactions regardless of sanctions lists
+    Uncensored,
+}
+
+impl Compliance {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Compliant => "compliant",
+            Self::Uncensored => "uncensored",
+        }
+    }
+}
+
+/// Preferences of a proposer for the bids served to it, sent alongside its registration
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProposerPreferences {
+    /// If present, only bids from builders of this compliance are served to the proposer;
+    /// otherwise every bid is eligible
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<Compliance>,
+}
+
+/// A validator registration extended with the optional preferences of the proposer
+///
+/// NOTE: the preferences are not covered by the signature of the registration.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistrationWithPreferences {
+    #[serde(flatten)]
+    pub registration: SignedValidatorRegistration,
+    #[serde(default)]
+    pub preferences: ProposerPreferences,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registration_with_preferences_json() {
+        let registration = SignedValidatorRegistration::default();
+        let json = serde_json::to_value(&registration).unwrap();
+        let decoded: RegistrationWithPreferences = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(decoded.preferences, ProposerPreferences::default());
+
+        let mut extended = json;
+        extended["preferences"] = serde_json::json!({ "compliance": "compliant" });
+        let decoded: RegistrationWithPreferences = serde_json::from_value(extended).unwrap();
+        assert_eq!(decoded.preferences.compliance, Some(Compliance::Compliant));
+        assert_eq!(decoded.registration.message.gas_limit, registration.message.gas_limit);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::types::{ExecutionPayload, ExecutionPayloadHeader};
+use ethereum_consensus::Error;
+
+/// Derives the header committing to `execution_payload`, computing the roots of its lists
+/// as consensus clients do so that the header matches the one in a block built from the payload
+pub fn header_from_payload(
+    execution_payload: &ExecutionPayload,
+) -> Result<ExecutionPayloadHeader, Error> {
+    // NOTE: merkleization requires mutable access to cache intermediate roots
+    let header = match execution_payload.clone() {
+        ExecutionPayload::Bellatrix(mut payload) => {
+            ExecutionPayloadHeader::Bellatrix((&mut payload).try_into()?)
+        }
+        ExecutionPayload::Capella(mut payload) => {
+            ExecutionPayloadHeader::Capella((&mut payload).try_into()?)
+        }
+        ExecutionPayload::Deneb(mut payload) => {
+            ExecutionPayloadHeader::Deneb((&mut payload).try_into()?)
+        }
+    };
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_consensus::{
+        capella::{mainnet as capella, Withdrawal},
+        deneb::mainnet as deneb,
+        primitives::ExecutionAddress,
+        serde::try_bytes_from_hex_str,
+        ssz::prelude::*,
+    };
+
+    // Roots of the lists below, computed independently of this crate
+    const TRANSACTIONS_ROOT: &str =
+        "0x162711ad28f2218cf89b6f49ffedfccc1529adc36a7e9e22475e7d132d6641db";
+    const WITHDRAWALS_ROOT: &str =
+        "0x87eb5374c76e2441139f254a27630d90e505631f56047caeda2d352e5cd99ab0";
+    const EMPTY_TRANSACTIONS_ROOT: &str =
+        "0x7ffe241ea60187fdb0187bfa22de35d1f9bed7ab061d9401fd47e34a54fbede1";
+
+    fn transactions<const N: usize, const M: usize>() -> List<ByteList<N>, M> {
+        let transactions = vec![
+            ByteList::try_from((0..40).collect::<Vec<u8>>()).unwrap(),
+            ByteList::try_from(vec![0xff; 5]).unwrap(),
+        ];
+        List::try_from(transactions).unwrap()
+    }
+
+    fn withdrawals<const N: usize>() -> List<Withdrawal, N> {
+        let withdrawals = vec![
+            Withdrawal {
+                index: 7,
+                validator_index: 42,
+                address: ExecutionAddress::try_from([0xab; 20].as_ref()).unwrap(),
+                amount: 32_000_000_000,
+            },
+            Withdrawal {
+                index: 8,
+                validator_index: 43,
+                address: ExecutionAddress::try_from([0xcd; 20].as_ref()).unwrap(),
+                amount: 1,
+            },
+        ];
+        List::try_from(withdrawals).unwrap()
+    }
+
+    fn assert_root(root: &Node, expected: &str) {
+        assert_eq!(root.as_ref(), try_bytes_from_hex_str(expected).unwrap().as_slice());
+    }
+
+    #[test]
+    fn test_capella_header_from_payload() {
+        let mut payload = capella::ExecutionPayload {
+            block_number: 100,
+            gas_limit: 30_000_000,
+            transactions: transactions(),
+            withdrawals: withdrawals(),
+            ..Default::default()
+        };
+        let header = header_from_payload(&ExecutionPayload::Capella(payload.clone())).unwrap();
+        let ExecutionPayloadHeader::Capella(mut header) = header else {
+            panic!("header is for a different fork than the payload")
+        };
+        assert_root(&header.transactions_root, TRANSACTIONS_ROOT);
+        assert_root(&header.withdrawals_root, WITHDRAWALS_ROOT);
+        assert_eq!(header.block_number, 100);
+        assert_eq!(header.gas_limit, 30_000_000);
+        // a header commits to the same root as its payload
+        assert_eq!(header.hash_tree_root().unwrap(), payload.hash_tree_root().unwrap());
+    }
+
+    #[test]
+    fn test_deneb_header_from_payload() {
+        let mut payload = deneb::ExecutionPayload {
+            block_number: 100,
+            transactions: transactions(),
+            withdrawals: withdrawals(),
+            blob_gas_used: 131_072,
+            excess_blob_gas: 262_144,
+            ..Default::default()
+        };
+        let header = header_from_payload(&ExecutionPayload::Deneb(payload.clone())).unwrap();
+        let ExecutionPayloadHeader::Deneb(mut header) = header else {
+            panic!("header is for a different fork than the payload")
+        };
+        assert_root(&header.transactions_root, TRANSACTIONS_ROOT);
+        assert_root(&header.withdrawals_root, WITHDRAWALS_ROOT);
+        assert_eq!(header.blob_gas_used, 131_072);
+        assert_eq!(header.excess_blob_gas, 262_144);
+        assert_eq!(header.hash_tree_root().unwrap(), payload.hash_tree_root().unwrap());
+
+        let payload = deneb::ExecutionPayload::default();
+        let header = header_from_payload(&ExecutionPayload::Deneb(payload)).unwrap();
+        let ExecutionPayloadHeader::Deneb(header) = header else {
+            panic!("header is for a different fork than the payload")
+        };
+        assert_root(&header.transactions_root, EMPTY_TRANSACTIONS_ROOT);
+    }
+}
@@ -2,14 +2,20 @@ mod auction_contents;
 mod auction_request;
 mod block_submission;
 pub mod builder_bid;
+mod compliance;
+mod execution_payload;
 mod proposer_schedule;
+mod registration_response;
 
 pub use auction_contents::*;
 pub use auction_request::*;
 pub use block_submission::*;
 pub use builder_bid::{BuilderBid, SignedBuilderBid};
+pub use compliance::*;
 pub use ethereum_consensus::{
     builder::SignedValidatorRegistration,
     types::mainnet::{ExecutionPayload, ExecutionPayloadHeader, SignedBlindedBeaconBlock},
 };
+pub use execution_payload::header_from_payload;
 pub use proposer_schedule::*;
+pub use registration_response::*;
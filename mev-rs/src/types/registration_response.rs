@@ -0,0 +1,24 @@
+/// Outcome of a batch of validator registrations of which some were rejected
+///
+/// Served with a `200` if any registration in the batch was accepted, and a `400` otherwise,
+/// so that proposers only resubmit the rejected registrations.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistrationResponse {
+    /// Number of registrations in the batch that were accepted
+    pub accepted: usize,
+    /// Registrations that were rejected, in the order they appear in the batch
+    pub rejected: Vec<RejectedRegistration>,
+    /// Number of registrations in the batch that were ignored for a newer registration of the
+    /// same validator in the batch
+    #[serde(default)]
+    pub duplicates: usize,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RejectedRegistration {
+    /// Index of the registration in the batch
+    pub index: usize,
+    pub reason: String,
+}
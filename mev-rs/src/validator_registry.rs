@@ -1,17 +1,24 @@
 use crate::{
-    signing::{compute_builder_signing_root, verify_signature},
+    beacon_node_pool::BeaconNodePool,
+    block_validation::is_valid_gas_limit,
+    signing::{compute_builder_signing_root, compute_signing_root, verify_signature},
     types::SignedValidatorRegistration,
 };
-use beacon_api_client::{
-    mainnet::Client, Error as ApiError, StateId, ValidatorStatus, ValidatorSummary,
+use beacon_api_client::{Error as ApiError, StateId, ValidatorStatus, ValidatorSummary};
+use blst::{
+    blst_scalar,
+    min_pk::{PublicKey as BlstPublicKey, Signature as BlstSignature},
+    BLST_ERROR,
 };
 use ethereum_consensus::{
     builder::ValidatorRegistration,
-    primitives::{BlsPublicKey, Epoch, Slot, ValidatorIndex},
+    primitives::{BlsPublicKey, Domain, Epoch, Root, Slot, ValidatorIndex},
+    ssz::prelude::Serialize,
     state_transition::Context,
     Error as ConsensusError,
 };
 use parking_lot::RwLock;
+use rand::Rng;
 use rayon::prelude::*;
 use std::{
     cmp::Ordering,
@@ -20,14 +27,28 @@ use std::{
 use thiserror::Error;
 use tracing::trace;
 
+// Number of seconds a registration's timestamp may be ahead of local time before it is rejected.
+const MAX_FUTURE_TIMESTAMP_SECS: u64 = 10;
+
+// Domain separation tag of the BLS signatures of the consensus layer.
+const BLS_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+// Number of bits of the random scalar each signature of a batch is weighed by.
+const BATCH_RANDOM_BITS: usize = 64;
+
 #[derive(Debug, Error)]
 pub enum Error {
+    #[error("registration must have a non-zero timestamp: {0:?}")]
+    MissingTimestamp(ValidatorRegistration),
     #[error("local time is {1} but registration has timestamp from future: {0:?}")]
     FutureRegistration(ValidatorRegistration, u64),
+    #[error("registration has gas limit outside of protocol bounds: {0:?}")]
+    InvalidGasLimit(ValidatorRegistration),
     #[error("validator has registration from timestamp {1}; outdated registration: {0:?}")]
     OutdatedRegistration(ValidatorRegistration, u64),
     #[error("registration is for validator with invalid status {1}: {0:?}")]
     ValidatorStatus(ValidatorRegistration, ValidatorStatus),
+    #[error("registration is superseded by one from timestamp {0} in the same batch")]
+    DuplicateRegistration(u64),
     #[error("missing knowledge of pubkey in validator set")]
     UnknownPubkey,
     #[error("missing knowledge of index in validator set")]
@@ -38,18 +59,39 @@ pub enum Error {
     Consensus(#[from] ConsensusError),
 }
 
-fn validate_registration_is_not_from_future(
+// `current_timestamp` is the local time in seconds since the Unix epoch
+fn validate_registration_timestamp(
     message: &ValidatorRegistration,
     current_timestamp: u64,
 ) -> Result<(), Error> {
     let timestamp = message.timestamp;
-    if timestamp > current_timestamp + 10 {
+    if timestamp == 0 {
+        Err(Error::MissingTimestamp(message.clone()))
+    } else if timestamp > current_timestamp + MAX_FUTURE_TIMESTAMP_SECS {
         Err(Error::FutureRegistration(message.clone(), current_timestamp))
     } else {
         Ok(())
     }
 }
 
+fn validate_registration_gas_limit(message: &ValidatorRegistration) -> Result<(), Error> {
+    if is_valid_gas_limit(message.gas_limit) {
+        Ok(())
+    } else {
+        Err(Error::InvalidGasLimit(message.clone()))
+    }
+}
+
+/// Verifies the signature of the `registration` against the builder domain
+pub fn verify_registration_signature(
+    registration: &mut SignedValidatorRegistration,
+    context: &Context,
+) -> Result<(), ConsensusError> {
+    let message = &mut registration.message;
+    let signing_root = compute_builder_signing_root(message, context)?;
+    verify_signature(&message.public_key, signing_root.as_ref(), &registration.signature)
+}
+
 fn determine_validator_registration_status(
     timestamp: u64,
     latest_timestamp: u64,
@@ -89,13 +131,13 @@ pub struct State {
 
 // Maintains validators we are aware of
 pub struct ValidatorRegistry {
-    client: Client,
+    client: BeaconNodePool,
     slots_per_epoch: Slot,
     state: RwLock<State>,
 }
 
 impl ValidatorRegistry {
-    pub fn new(client: Client, slots_per_epoch: Slot) -> Self {
+    pub fn new(client: BeaconNodePool, slots_per_epoch: Slot) -> Self {
         let state = RwLock::new(Default::default());
         Self { client, slots_per_epoch, state }
     }
@@ -103,7 +145,12 @@ impl ValidatorRegistry {
     // TODO: load more efficiently
     pub async fn on_epoch(&self, epoch: Epoch) -> Result<(), Error> {
         let slot = epoch * self.slots_per_epoch;
-        let summaries = self.client.get_validators(StateId::Slot(slot), &[], &[]).await?;
+        let summaries = self
+            .client
+            .call(
+                |client| async move { client.get_validators(StateId::Slot(slot), &[], &[]).await },
+            )
+            .await?;
         let mut state = self.state.write();
         for summary in summaries.into_iter() {
             let public_key = summary.validator.public_key.clone();
@@ -147,20 +194,110 @@ impl ValidatorRegistry {
     //         .map(|(key, _)| key.clone())
     // }
 
-    fn process_registration<'a>(
-        &'a self,
-        registration: &'a mut SignedValidatorRegistration,
+    pub fn registration_count(&self) -> usize {
+        self.state.read().validator_preferences.len()
+    }
+
+    // Evicts registrations, oldest by timestamp first, until at most `max_registrations` remain,
+    // never evicting the registrations of validators in `retained`. Returns the keys of the
+    // evicted registrations.
+    pub fn evict_oldest_registrations(
+        &self,
+        max_registrations: usize,
+        retained: &HashSet<BlsPublicKey>,
+    ) -> Vec<BlsPublicKey> {
+        let mut state = self.state.write();
+        let excess = state.validator_preferences.len().saturating_sub(max_registrations);
+        if excess == 0 {
+            return vec![]
+        }
+        let mut candidates = state
+            .validator_preferences
+            .iter()
+            .filter(|(public_key, _)| !retained.contains(*public_key))
+            .map(|(public_key, registration)| (registration.message.timestamp, public_key.clone()))
+            .collect::<Vec<_>>();
+        candidates.sort_unstable_by_key(|(timestamp, _)| *timestamp);
+        let evicted_keys = candidates
+            .into_iter()
+            .take(excess)
+            .map(|(_, public_key)| public_key)
+            .collect::<Vec<_>>();
+        for public_key in &evicted_keys {
+            state.validator_preferences.remove(public_key);
+        }
+        evicted_keys
+    }
+
+    // Restores previously processed `registrations`, e.g. from persistent storage, keeping any
+    // more recent registration already present. Returns the keys of the restored registrations.
+    pub fn restore_registrations(
+        &self,
+        registrations: Vec<SignedValidatorRegistration>,
+    ) -> HashSet<BlsPublicKey> {
+        let mut state = self.state.write();
+        let mut restored_keys = HashSet::new();
+        for registration in registrations {
+            let public_key = registration.message.public_key.clone();
+            let is_newer = state
+                .validator_preferences
+                .get(&public_key)
+                .map(|existing| existing.message.timestamp < registration.message.timestamp)
+                .unwrap_or(true);
+            if is_newer {
+                restored_keys.insert(public_key.clone());
+                state.validator_preferences.insert(public_key, registration);
+            }
+        }
+        restored_keys
+    }
+
+    // Replaces all registrations with `registrations`, e.g. re-read from persistent storage,
+    // keeping any more recent registration already present for a validator among them. Returns
+    // the number of registrations present afterwards.
+    //
+    // NOTE: the new registrations are collected, and the previous ones dropped, outside of the
+    // write lock, so that readers are only held up by the swap; a registration processed in the
+    // meantime may be lost until the validator resends it, as validators do each epoch.
+    pub fn replace_registrations(&self, registrations: Vec<SignedValidatorRegistration>) -> usize {
+        let validator_preferences = {
+            let state = self.state.read();
+            registrations
+                .into_iter()
+                .map(|registration| {
+                    let public_key = registration.message.public_key.clone();
+                    let registration = match state.validator_preferences.get(&public_key) {
+                        Some(existing)
+                            if existing.message.timestamp > registration.message.timestamp =>
+                        {
+                            existing.clone()
+                        }
+                        _ => registration,
+                    };
+                    (public_key, registration)
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        let count = validator_preferences.len();
+        let previous =
+            std::mem::replace(&mut self.state.write().validator_preferences, validator_preferences);
+        drop(previous);
+        count
+    }
+
+    // Validates everything about the `message` except its signature, returning `true` if it is
+    // a new registration for the validator.
+    fn validate_registration(
+        &self,
+        message: &ValidatorRegistration,
         current_timestamp: u64,
-        context: &Context,
-    ) -> Result<Option<&'a mut SignedValidatorRegistration>, Error> {
+    ) -> Result<bool, Error> {
         let state = self.state.read();
-        let latest_timestamp = state
-            .validator_preferences
-            .get(&registration.message.public_key)
-            .map(|r| r.message.timestamp);
-        let message = &mut registration.message;
+        let latest_timestamp =
+            state.validator_preferences.get(&message.public_key).map(|r| r.message.timestamp);
 
-        validate_registration_is_not_from_future(message, current_timestamp)?;
+        validate_registration_timestamp(message, current_timestamp)?;
+        validate_registration_gas_limit(message)?;
 
         let registration_status = if let Some(latest_timestamp) = latest_timestamp {
             let status =
@@ -181,41 +318,327 @@ impl ValidatorRegistry {
             .ok_or(Error::UnknownPubkey)?;
         validate_validator_status(message, validator_status)?;
 
-        let signing_root = compute_builder_signing_root(message, context)?;
-        let public_key = &message.public_key;
-        verify_signature(public_key, signing_root.as_ref(), &registration.signature)?;
-
-        let update = if matches!(registration_status, ValidatorRegistrationStatus::New) {
-            trace!(%public_key, "processed new registration");
-            Some(registration)
-        } else {
-            None
-        };
-        Ok(update)
+        Ok(matches!(registration_status, ValidatorRegistrationStatus::New))
     }
 
     // Returns set of public keys for updated (including new) registrations successfully processed
-    // and any errors encountered while processing.
+    // and any errors encountered while processing, along with the index of the failed registration.
+    // `current_timestamp` is the local time in seconds since the Unix epoch and `builder_domain`
+    // is the domain registrations are signed with.
     pub fn process_registrations(
         &self,
         registrations: &mut [SignedValidatorRegistration],
         current_timestamp: u64,
-        context: &Context,
-    ) -> (HashSet<BlsPublicKey>, Vec<Error>) {
-        let (updates, errs): (Vec<_>, Vec<_>) = registrations
+        builder_domain: Domain,
+    ) -> (HashSet<BlsPublicKey>, Vec<(usize, Error)>) {
+        let validations = registrations
+            .par_iter()
+            .map(|registration| {
+                self.validate_registration(&registration.message, current_timestamp)
+            })
+            .collect::<Vec<_>>();
+
+        let mut errs = vec![];
+        let mut candidates = vec![];
+        for ((index, registration), validation) in
+            registrations.iter_mut().enumerate().zip(validations)
+        {
+            match validation {
+                Ok(is_new) => candidates.push((index, is_new, registration)),
+                Err(err) => errs.push((index, err)),
+            }
+        }
+
+        let signing_roots = candidates
             .par_iter_mut()
-            .map(|registration| self.process_registration(registration, current_timestamp, context))
-            .partition(|result| result.is_ok());
+            .map(|(_, _, registration)| {
+                compute_signing_root(&mut registration.message, builder_domain)
+            })
+            .collect::<Vec<_>>();
+        let mut verifiable = vec![];
+        for ((index, is_new, registration), signing_root) in
+            candidates.into_iter().zip(signing_roots)
+        {
+            match signing_root {
+                Ok(signing_root) => verifiable.push((index, is_new, registration, signing_root)),
+                Err(err) => errs.push((index, err.into())),
+            }
+        }
+
+        let verified = if verify_signatures_in_batch(&verifiable) {
+            verifiable
+        } else {
+            // NOTE: at least one signature is invalid, so verify each to find which
+            let results = verifiable
+                .par_iter()
+                .map(|(_, _, registration, signing_root)| {
+                    let public_key = &registration.message.public_key;
+                    verify_signature(public_key, signing_root.as_ref(), &registration.signature)
+                })
+                .collect::<Vec<_>>();
+            let mut verified = vec![];
+            for (candidate, result) in verifiable.into_iter().zip(results) {
+                match result {
+                    Ok(()) => verified.push(candidate),
+                    Err(err) => errs.push((candidate.0, err.into())),
+                }
+            }
+            verified
+        };
+
         let mut state = self.state.write();
         let mut updated_keys = HashSet::new();
-        for update in updates {
-            if let Some(signed_registration) = update.expect("validated successfully") {
+        for (_, is_new, signed_registration, _) in verified {
+            // NOTE: check again as registrations may be processed concurrently,
+            // so that the latest registration by timestamp always wins
+            let is_latest = state
+                .validator_preferences
+                .get(&signed_registration.message.public_key)
+                .map(|existing| existing.message.timestamp < signed_registration.message.timestamp)
+                .unwrap_or(true);
+            if is_new && is_latest {
                 let public_key = signed_registration.message.public_key.clone();
+                trace!(%public_key, "processed new registration");
                 updated_keys.insert(public_key.clone());
                 state.validator_preferences.insert(public_key, signed_registration.clone());
             }
         }
 
-        (updated_keys, errs.into_iter().map(|err| err.expect_err("validation failed")).collect())
+        errs.sort_by_key(|(index, _)| *index);
+        (updated_keys, errs)
+    }
+}
+
+// Decodes the BLS `value`, e.g. a public key or signature, with `decode` from its serialized bytes.
+fn to_blst<T: Serialize, B>(
+    value: &T,
+    decode: impl Fn(&[u8]) -> Result<B, BLST_ERROR>,
+) -> Option<B> {
+    let mut bytes = Vec::new();
+    value.serialize(&mut bytes).ok()?;
+    decode(&bytes).ok()
+}
+
+// Verifies the signatures of all `registrations` at once, returning `true` only if every
+// signature is valid.
+//
+// NOTE: each signature is weighed by a random scalar before aggregating, so that invalid
+// signatures crafted to cancel each other out never verify as a batch.
+//
+// NOTE: the builder domain does not depend on the genesis validators root, so the
+// `signing_root` of each registration is the same under any fork of the chain.
+fn verify_signatures_in_batch(
+    registrations: &[(usize, bool, &mut SignedValidatorRegistration, Root)],
+) -> bool {
+    if registrations.is_empty() {
+        return true
+    }
+    let public_keys = registrations
+        .iter()
+        .map(|(_, _, registration, _)| {
+            to_blst(&registration.message.public_key, BlstPublicKey::from_bytes)
+        })
+        .collect::<Option<Vec<_>>>();
+    let signatures = registrations
+        .iter()
+        .map(|(_, _, registration, _)| to_blst(&registration.signature, BlstSignature::from_bytes))
+        .collect::<Option<Vec<_>>>();
+    let (Some(public_keys), Some(signatures)) = (public_keys, signatures) else { return false };
+    let messages = registrations
+        .iter()
+        .map(|(_, _, _, signing_root)| signing_root.as_ref())
+        .collect::<Vec<_>>();
+    let mut rng = rand::thread_rng();
+    let scalars = registrations
+        .iter()
+        .map(|_| {
+            let mut scalar = blst_scalar::default();
+            let weight: u64 = rng.gen_range(1..=u64::MAX);
+            scalar.b[..8].copy_from_slice(&weight.to_le_bytes());
+            scalar
+        })
+        .collect::<Vec<_>>();
+    let result = BlstSignature::verify_multiple_aggregate_signatures(
+        &messages,
+        BLS_DST,
+        &public_keys.iter().collect::<Vec<_>>(),
+        true,
+        &signatures.iter().collect::<Vec<_>>(),
+        true,
+        &scalars,
+        BATCH_RANDOM_BITS,
+    );
+    result == BLST_ERROR::BLST_SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        block_validation::{MAX_GAS_LIMIT, MIN_GAS_LIMIT},
+        signing::{sign_builder_message, SecretKey},
+    };
+
+    const CURRENT_TIMESTAMP: u64 = 1_700_000_000;
+
+    #[test]
+    fn test_validate_registration_timestamp() {
+        let mut message =
+            ValidatorRegistration { timestamp: CURRENT_TIMESTAMP, ..Default::default() };
+        assert!(validate_registration_timestamp(&message, CURRENT_TIMESTAMP).is_ok());
+
+        message.timestamp = 0;
+        let result = validate_registration_timestamp(&message, CURRENT_TIMESTAMP);
+        assert!(matches!(result, Err(Error::MissingTimestamp(..))));
+
+        message.timestamp = CURRENT_TIMESTAMP + 60 * 60;
+        let result = validate_registration_timestamp(&message, CURRENT_TIMESTAMP);
+        assert!(matches!(result, Err(Error::FutureRegistration(..))));
+    }
+
+    #[test]
+    fn test_validate_registration_gas_limit() {
+        let mut message = ValidatorRegistration { gas_limit: 30_000_000, ..Default::default() };
+        assert!(validate_registration_gas_limit(&message).is_ok());
+
+        for gas_limit in [0, MIN_GAS_LIMIT - 1, MAX_GAS_LIMIT + 1, u64::MAX] {
+            message.gas_limit = gas_limit;
+            let result = validate_registration_gas_limit(&message);
+            assert!(matches!(result, Err(Error::InvalidGasLimit(..))));
+        }
+    }
+
+    #[test]
+    fn test_evict_oldest_registrations() {
+        let client = BeaconNodePool::from(beacon_api_client::mainnet::Client::new(
+            url::Url::parse("http://127.0.0.1:1").unwrap(),
+        ));
+        let registry = ValidatorRegistry::new(client, 32);
+        let public_keys = (1..=4u8)
+            .map(|i| SecretKey::try_from([i; 32].as_ref()).unwrap().public_key())
+            .collect::<Vec<_>>();
+        let registrations = public_keys
+            .iter()
+            .enumerate()
+            .map(|(i, public_key)| SignedValidatorRegistration {
+                message: ValidatorRegistration {
+                    timestamp: CURRENT_TIMESTAMP + i as u64,
+                    public_key: public_key.clone(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect();
+        registry.restore_registrations(registrations);
+
+        assert!(registry.evict_oldest_registrations(4, &HashSet::new()).is_empty());
+        assert_eq!(registry.registration_count(), 4);
+
+        // the oldest registration is retained, so the next oldest are evicted instead
+        let retained = HashSet::from([public_keys[0].clone()]);
+        let evicted = registry.evict_oldest_registrations(2, &retained);
+        assert_eq!(evicted, vec![public_keys[1].clone(), public_keys[2].clone()]);
+        assert_eq!(registry.registration_count(), 2);
+        assert!(registry.get_signed_registration(&public_keys[0]).is_some());
+        assert!(registry.get_signed_registration(&public_keys[3]).is_some());
+
+        // retained registrations are kept even if over the limit
+        let retained = HashSet::from_iter(public_keys.iter().cloned());
+        assert!(registry.evict_oldest_registrations(0, &retained).is_empty());
+        assert_eq!(registry.registration_count(), 2);
+    }
+
+    #[test]
+    fn test_replace_registrations() {
+        let client = BeaconNodePool::from(beacon_api_client::mainnet::Client::new(
+            url::Url::parse("http://127.0.0.1:1").unwrap(),
+        ));
+        let registry = ValidatorRegistry::new(client, 32);
+        let public_keys = (1..=3u8)
+            .map(|i| SecretKey::try_from([i; 32].as_ref()).unwrap().public_key())
+            .collect::<Vec<_>>();
+        let registration = |public_key: &BlsPublicKey, timestamp| SignedValidatorRegistration {
+            message: ValidatorRegistration {
+                timestamp,
+                public_key: public_key.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        registry.restore_registrations(vec![
+            registration(&public_keys[0], CURRENT_TIMESTAMP + 1),
+            registration(&public_keys[1], CURRENT_TIMESTAMP),
+        ]);
+
+        let count = registry.replace_registrations(vec![
+            registration(&public_keys[0], CURRENT_TIMESTAMP),
+            registration(&public_keys[2], CURRENT_TIMESTAMP),
+        ]);
+        assert_eq!(count, 2);
+        assert_eq!(registry.registration_count(), 2);
+        // a more recent registration is kept over the replacement, and any validator without a
+        // replacement is dropped
+        let kept = registry.get_signed_registration(&public_keys[0]).unwrap();
+        assert_eq!(kept.message.timestamp, CURRENT_TIMESTAMP + 1);
+        assert!(registry.get_signed_registration(&public_keys[1]).is_none());
+        assert!(registry.get_signed_registration(&public_keys[2]).is_some());
+    }
+
+    #[test]
+    fn test_verify_registration_signature() {
+        let context = Context::for_holesky();
+        let secret_key = SecretKey::try_from([1u8; 32].as_ref()).unwrap();
+        let mut message = ValidatorRegistration {
+            timestamp: CURRENT_TIMESTAMP,
+            public_key: secret_key.public_key(),
+            ..Default::default()
+        };
+        let signature = sign_builder_message(&mut message, &secret_key, &context).unwrap();
+        let mut registration = SignedValidatorRegistration { message, signature };
+        verify_registration_signature(&mut registration, &context).expect("is valid signature");
+
+        registration.message.gas_limit += 1;
+        assert!(verify_registration_signature(&mut registration, &context).is_err());
+    }
+
+    #[test]
+    fn test_verify_signatures_in_batch() {
+        let context = Context::for_holesky();
+        let mut registrations = (1..=4u8)
+            .map(|i| {
+                let secret_key = SecretKey::try_from([i; 32].as_ref()).unwrap();
+                let mut message = ValidatorRegistration {
+                    timestamp: CURRENT_TIMESTAMP,
+                    public_key: secret_key.public_key(),
+                    ..Default::default()
+                };
+                let signature = sign_builder_message(&mut message, &secret_key, &context).unwrap();
+                SignedValidatorRegistration { message, signature }
+            })
+            .collect::<Vec<_>>();
+
+        let batch = |registrations: &mut [SignedValidatorRegistration]| {
+            registrations
+                .iter_mut()
+                .enumerate()
+                .map(|(index, registration)| {
+                    let signing_root =
+                        compute_builder_signing_root(&mut registration.message, &context).unwrap();
+                    (index, true, registration, signing_root)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert!(verify_signatures_in_batch(&batch(&mut registrations)));
+
+        // invalid signatures that cancel out in the sum of the batch, as each is the signature
+        // of the other registration
+        let mut swapped = registrations.clone();
+        let signature = swapped[0].signature.clone();
+        swapped[0].signature = swapped[1].signature.clone();
+        swapped[1].signature = signature;
+        assert!(!verify_signatures_in_batch(&batch(&mut swapped)));
+
+        registrations[2].message.gas_limit += 1;
+        assert!(!verify_signatures_in_batch(&batch(&mut registrations)));
     }
 }